@@ -0,0 +1,102 @@
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use crate::common::*;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+
+fn sign(c: &mut Criterion) {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM).unwrap();
+
+    // The appendix C request has exactly 5 headers
+    let request = parse_request(HTTP_REQUEST);
+    c.bench_function("sign 5 headers", |b| {
+        b.iter(|| {
+            httpsig::create_signature_header(
+                black_box(&request),
+                "Test",
+                MessageDigest::sha256(),
+                &private_key,
+            )
+            .unwrap()
+        })
+    });
+
+    let mut request = parse_request(HTTP_REQUEST);
+    for i in 0..15 {
+        request.headers_mut().insert(
+            http::header::HeaderName::from_bytes(format!("x-header-{}", i).as_bytes()).unwrap(),
+            "some value".parse().unwrap(),
+        );
+    }
+    c.bench_function("sign 20 headers", |b| {
+        b.iter(|| {
+            httpsig::create_signature_header(
+                black_box(&request),
+                "Test",
+                MessageDigest::sha256(),
+                &private_key,
+            )
+            .unwrap()
+        })
+    });
+}
+
+fn sign_reusable(c: &mut Criterion) {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM).unwrap();
+    let params = httpsig::SignatureParams::new("Test", MessageDigest::sha256());
+
+    let mut request = parse_request(HTTP_REQUEST);
+    c.bench_function("add signature header", |b| {
+        b.iter(|| {
+            httpsig::add_signature_header_with_params(
+                black_box(&mut request),
+                &params,
+                &private_key,
+            )
+            .unwrap()
+        })
+    });
+
+    let mut signer = httpsig::ReusableSigner::new(params, private_key);
+    c.bench_function("add signature header (reusable signer)", |b| {
+        b.iter(|| signer.sign(black_box(&mut request)).unwrap())
+    });
+}
+
+// An HMAC key makes the signature itself cheap, so the time is mostly spent building the signing
+// string
+fn signing_string_template(c: &mut Criterion) {
+    let key = PKey::hmac(b"secret").unwrap();
+    let request = parse_request(HTTP_REQUEST);
+
+    c.bench_function("signing string (builder)", |b| {
+        b.iter(|| {
+            httpsig::compute_signature(black_box(&request), MessageDigest::sha256(), &key).unwrap()
+        })
+    });
+
+    // The same headers `compute_signature` signs for this request
+    let template = httpsig::SigningStringTemplate::new(&[
+        "(request-target)",
+        "host",
+        "date",
+        "content-type",
+        "digest",
+        "content-length",
+    ]);
+    c.bench_function("signing string (template)", |b| {
+        b.iter(|| {
+            let mut signer = Signer::new(MessageDigest::sha256(), &key).unwrap();
+            signer
+                .update(&template.fill(black_box(&request)).unwrap())
+                .unwrap();
+            signer.sign_to_vec().unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, sign, sign_reusable, signing_string_template);
+criterion_main!(benches);
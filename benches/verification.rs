@@ -0,0 +1,125 @@
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use crate::common::*;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Counts allocations, to compare the verification functions that reuse buffers with the ones that
+// don't
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+// https://tools.ietf.org/html/draft-cavage-http-signatures-10#appendix-C.3
+const SIGNATURE_HEADER: &str = r#"keyId="Test",algorithm="rsa-sha256",headers="(request-target) host date content-type digest content-length",signature="vSdrb+dS3EceC9bcwHSo4MlyKS59iFIrhgYkz8+oVLEEzmYZZvRs8rgOp+63LEM3v+MFHB32NfpB2bEKBIvB1q52LaEUHFv120V01IL+TAD48XaERZFukWgHoBTLMhYS2Gb51gWxpeIq8knRmPnYePbF5MOkR0Zkly4zKH7s1dE=""#;
+
+fn verify(c: &mut Criterion) {
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM).unwrap();
+
+    let mut request = parse_request(HTTP_REQUEST);
+    request
+        .headers_mut()
+        .insert("signature", SIGNATURE_HEADER.parse().unwrap());
+    c.bench_function("verify valid signature", |b| {
+        b.iter(|| {
+            assert!(httpsig::verify_request(
+                black_box(&request),
+                MessageDigest::sha256(),
+                &public_key
+            )
+            .unwrap())
+        })
+    });
+
+    let mut verifier = httpsig::ReusableVerifier::new(MessageDigest::sha256(), public_key.clone());
+    c.bench_function("verify valid signature (reusable verifier)", |b| {
+        b.iter(|| assert!(verifier.verify(black_box(&request)).unwrap()))
+    });
+
+    let parts = httpsig::find_signature_in_request(&request).unwrap();
+    let options = httpsig::VerificationOptions::default();
+    let verify_parts = || {
+        assert!(httpsig::verify_signature_parts_with_options(
+            black_box(&request),
+            &parts,
+            MessageDigest::sha256(),
+            &public_key,
+            &options
+        )
+        .unwrap())
+    };
+    let mut buffers = httpsig::VerificationBuffers::new();
+    let mut verify_parts_with_buffers = || {
+        assert!(httpsig::verify_signature_parts_with_buffers(
+            black_box(&request),
+            &parts,
+            MessageDigest::sha256(),
+            &public_key,
+            &options,
+            &mut buffers
+        )
+        .unwrap())
+    };
+    // Once to grow the buffers
+    verify_parts_with_buffers();
+    eprintln!(
+        "allocations per verification: {} without reusing buffers, {} reusing buffers",
+        count_allocations(verify_parts),
+        count_allocations(&mut verify_parts_with_buffers)
+    );
+    c.bench_function("verify signature parts", |b| b.iter(verify_parts));
+    c.bench_function("verify signature parts (reused buffers)", |b| {
+        b.iter(&mut verify_parts_with_buffers)
+    });
+
+    // Same signature, different date
+    request
+        .headers_mut()
+        .insert("date", "Mon, 06 Jan 2014 21:31:40 GMT".parse().unwrap());
+    c.bench_function("verify invalid signature", |b| {
+        b.iter(|| {
+            assert!(!httpsig::verify_request(
+                black_box(&request),
+                MessageDigest::sha256(),
+                &public_key
+            )
+            .unwrap())
+        })
+    });
+}
+
+fn parse(c: &mut Criterion) {
+    c.bench_function("parse signature header", |b| {
+        b.iter(|| httpsig::parse_signature_parts(black_box(SIGNATURE_HEADER)).unwrap())
+    });
+    c.bench_function("extract key id", |b| {
+        b.iter(|| httpsig::parse::key_id(black_box(SIGNATURE_HEADER)).unwrap())
+    });
+}
+
+criterion_group!(benches, verify, parse);
+criterion_main!(benches);
@@ -0,0 +1,41 @@
+// Serves an inbox that only accepts requests signed by a known key. Handlers can extract the
+// verified signature with `ReqData<VerificationResult>`. Requests whose signed `Date` is more than
+// five minutes off are rejected, so captured requests can't be replayed later.
+//
+//     cargo run --example actix_verification --features actix-web
+
+use actix_web::web::{self, ReqData};
+use actix_web::{App, HttpResponse, HttpServer};
+use httpsig::{HttpSigMiddleware, InMemoryKeyStore, VerificationOptions, VerificationResult};
+use openssl::pkey::PKey;
+use std::time::Duration;
+
+async fn inbox(signature: ReqData<VerificationResult>) -> HttpResponse {
+    HttpResponse::Ok().body(format!("signed by {}", signature.key_id))
+}
+
+fn main() -> std::io::Result<()> {
+    let private_key = openssl::rsa::Rsa::generate(2048)?;
+    let public_key = PKey::public_key_from_pem(&private_key.public_key_to_pem()?)?;
+
+    let mut key_store = InMemoryKeyStore::new();
+    key_store.insert("example-key", public_key);
+    let key_store = web::Data::new(key_store);
+
+    actix_web::rt::System::new().block_on(
+        HttpServer::new(move || {
+            App::new()
+                .wrap(
+                    HttpSigMiddleware::from_data(key_store.clone()).with_options(
+                        VerificationOptions {
+                            max_clock_skew: Some(Duration::from_secs(300)),
+                            ..VerificationOptions::default()
+                        },
+                    ),
+                )
+                .route("/inbox", web::post().to(inbox))
+        })
+        .bind(("127.0.0.1", 8080))?
+        .run(),
+    )
+}
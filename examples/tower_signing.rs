@@ -0,0 +1,86 @@
+// Signs requests from a hyper client, with the signing layer stacked between tower-http's
+// `TraceLayer` and `TimeoutLayer` in a `tower::ServiceBuilder`. The server, on a local port,
+// verifies the signature and responds with whether it was valid. `TraceLayer` reports each request
+// as `tracing` events, which a subscriber such as `tracing-subscriber` would print.
+//
+//     cargo run --example tower_signing --features tower-http
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Public};
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tower::{ServiceBuilder, ServiceExt};
+use tower_http::timeout::TimeoutLayer;
+use tower_http::trace::TraceLayer;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+async fn inbox(
+    request: hyper::Request<hyper::body::Incoming>,
+    public_key: PKey<Public>,
+) -> Result<hyper::Response<Full<Bytes>>, Infallible> {
+    let verified = httpsig::verify_message(&request, MessageDigest::sha256(), &public_key);
+    let (status, body) = match verified {
+        Ok(true) => (hyper::StatusCode::OK, "signature verified"),
+        Ok(false) | Err(_) => (hyper::StatusCode::UNAUTHORIZED, "invalid signature"),
+    };
+    let mut response = hyper::Response::new(Full::from(body));
+    *response.status_mut() = status;
+    Ok(response)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), BoxError> {
+    let rsa = openssl::rsa::Rsa::generate(2048)?;
+    let public_key = PKey::public_key_from_pem(&rsa.public_key_to_pem()?)?;
+    let private_key = PKey::from_rsa(rsa)?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let public_key = public_key.clone();
+            let service = service_fn(move |request| inbox(request, public_key.clone()));
+            tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+        }
+    });
+
+    let params = httpsig::SignatureParams::new("example-key", MessageDigest::sha256());
+    let client = Client::builder(TokioExecutor::new()).build_http::<Full<Bytes>>();
+    let client = ServiceBuilder::new()
+        .layer(TraceLayer::new_for_http())
+        .layer(TimeoutLayer::with_status_code(
+            hyper::StatusCode::GATEWAY_TIMEOUT,
+            Duration::from_secs(10),
+        ))
+        // `TimeoutLayer` needs a response body it can create empty on timeout
+        .map_response(|response: hyper::Response<hyper::body::Incoming>| {
+            response.map(BodyExt::boxed)
+        })
+        // The body is still a `String` here, so the layer can set the `Digest` header from it
+        .layer(httpsig::SigningLayer::new(params, private_key).with_digest())
+        .map_request(|request: hyper::Request<String>| request.map(Full::from))
+        .service(client);
+
+    let request = hyper::Request::post(format!("http://{}/inbox", addr))
+        .header("host", addr.to_string())
+        .header(
+            "date",
+            httpdate::fmt_http_date(std::time::SystemTime::now()),
+        )
+        .body(r#"{"hello": "world"}"#.to_owned())?;
+
+    let response = client.oneshot(request).await?;
+    let status = response.status();
+    let body = response.into_body().collect().await?.to_bytes();
+    println!("{}: {}", status, String::from_utf8_lossy(&body));
+
+    Ok(())
+}
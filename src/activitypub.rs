@@ -0,0 +1,48 @@
+use crate::{add_digest_header, add_signature_header_with_params, HttpSigError, SignatureParams};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{HasPrivate, PKeyRef};
+use std::time::SystemTime;
+
+const SIGNED_HEADERS: [&str; 4] = ["(request-target)", "host", "date", "digest"];
+
+// Prepares a request for delivery to an ActivityPub inbox: sets `Date` to the current time, sets
+// `Digest` from the body, sets `Host` from the URI if it's missing, and signs
+// `(request-target) host date digest` with SHA-256.
+pub fn sign_activitypub_request<T: AsRef<[u8]>>(
+    request: &mut http::Request<T>,
+    key_id: &str,
+    private_key: &PKeyRef<impl HasPrivate>,
+) -> Result<(), HttpSigError> {
+    sign_activitypub_request_at(request, key_id, private_key, SystemTime::now())
+}
+
+// Like `sign_activitypub_request`, but sets `Date` to `now` instead of the current time. Signing
+// the same request twice with the same `now` produces the same headers, which is useful in tests.
+pub fn sign_activitypub_request_at<T: AsRef<[u8]>>(
+    request: &mut http::Request<T>,
+    key_id: &str,
+    private_key: &PKeyRef<impl HasPrivate>,
+    now: SystemTime,
+) -> Result<(), HttpSigError> {
+    let date = httpdate::fmt_http_date(now);
+    request.headers_mut().insert("date", date.parse()?);
+
+    add_digest_header(request)?;
+
+    if !request.headers().contains_key("host") {
+        let host = match request.uri().authority_part() {
+            Some(authority) => authority.as_str().parse()?,
+            None => {
+                return Err(HttpSigError::MissingHeader {
+                    name: "host".to_owned(),
+                })
+            }
+        };
+        request.headers_mut().insert("host", host);
+    }
+
+    let mut params = SignatureParams::new(key_id, MessageDigest::sha256());
+    params.headers = Some(SIGNED_HEADERS.iter().map(|&h| h.to_owned()).collect());
+
+    add_signature_header_with_params(request, &params, private_key)
+}
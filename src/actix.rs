@@ -0,0 +1,128 @@
+use crate::request::request_head;
+use crate::{
+    verify_request_with_resolver_and_options, HttpSigError, KeyResolver, VerificationOptions,
+    VerificationResult,
+};
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::web::Data;
+use actix_web::{Error, HttpMessage, HttpResponse};
+use openssl::hash::MessageDigest;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::sync::Arc;
+
+// Verifies the signature of every request against a key store. Verified requests are passed on
+// with a `VerificationResult` that handlers can extract with `ReqData`; anything else gets a
+// `401 Unauthorized`.
+//
+// By default, requests are verified with the default `VerificationOptions`, which don't check when
+// the request was signed, so a captured request is accepted again whenever it's replayed. Use
+// `with_options` to set `max_clock_skew` or `max_signature_age`.
+pub struct HttpSigMiddleware<R> {
+    key_store: Data<R>,
+    options: Arc<VerificationOptions>,
+}
+
+impl<R> HttpSigMiddleware<R> {
+    pub fn new(key_store: R) -> Self {
+        HttpSigMiddleware::from_data(Data::new(key_store))
+    }
+
+    // Shares a key store that is also registered as app data
+    pub fn from_data(key_store: Data<R>) -> Self {
+        HttpSigMiddleware {
+            key_store,
+            options: Arc::new(VerificationOptions::default()),
+        }
+    }
+
+    pub fn with_options(self, options: VerificationOptions) -> Self {
+        HttpSigMiddleware {
+            options: Arc::new(options),
+            ..self
+        }
+    }
+}
+
+impl<R> Clone for HttpSigMiddleware<R> {
+    fn clone(&self) -> Self {
+        HttpSigMiddleware {
+            key_store: self.key_store.clone(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl<S, B, R> Transform<S, ServiceRequest> for HttpSigMiddleware<R>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+    R: KeyResolver + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = HttpSigMiddlewareService<S, R>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(HttpSigMiddlewareService {
+            inner: service,
+            key_store: self.key_store.clone(),
+            options: self.options.clone(),
+        }))
+    }
+}
+
+pub struct HttpSigMiddlewareService<S, R> {
+    inner: S,
+    key_store: Data<R>,
+    options: Arc<VerificationOptions>,
+}
+
+impl<S, B, R> Service<ServiceRequest> for HttpSigMiddlewareService<S, R>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+    R: KeyResolver + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(inner);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        match verify(&request, self.key_store.get_ref(), &self.options) {
+            Ok(result) => {
+                request.extensions_mut().insert(result);
+                let response = self.inner.call(request);
+                Box::pin(async move { Ok(response.await?.map_into_left_body()) })
+            }
+            Err(_) => {
+                let response = HttpResponse::Unauthorized().finish().map_into_right_body();
+                Box::pin(ready(Ok(request.into_response(response))))
+            }
+        }
+    }
+}
+
+// actix-web uses a newer version of the `http` crate, so the request head is copied first
+fn verify(
+    request: &ServiceRequest,
+    key_store: &impl KeyResolver,
+    options: &VerificationOptions,
+) -> Result<VerificationResult, HttpSigError> {
+    let uri = request.uri().to_string();
+    let head = request_head(
+        request.method().as_str(),
+        &uri,
+        request
+            .headers()
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_bytes())),
+    )?;
+
+    verify_request_with_resolver_and_options(&head, key_store, MessageDigest::sha256(), options)
+}
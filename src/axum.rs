@@ -0,0 +1,125 @@
+// An axum extractor for requests signed by a known key:
+//
+//     async fn inbox(SignedRequest(info): SignedRequest) -> String {
+//         format!("hello, {}", info.key_id)
+//     }
+//
+//     let app = Router::new()
+//         .route("/inbox", post(inbox))
+//         .with_state(Arc::new(key_store) as SharedKeyResolver);
+//
+// The key resolver comes from the router state, either directly or through `FromRef` on a larger
+// state type. Extraction fails with `401 Unauthorized` if the signature is missing or invalid.
+//
+// A bare `SharedKeyResolver` verifies with the default `VerificationOptions`, which don't check when
+// the request was signed, so a captured request is accepted again whenever it's replayed. To reject
+// old requests, use an `HttpSigState` with `max_clock_skew` or `max_signature_age` set instead:
+//
+//     .with_state(HttpSigState::new(Arc::new(key_store)).with_options(VerificationOptions {
+//         max_clock_skew: Some(Duration::from_secs(300)),
+//         ..VerificationOptions::default()
+//     }));
+//
+// The extractor only sees the request head, so it doesn't check the body against the `Digest`
+// header. Handlers that accept a body should also call `verify_digest_header`.
+
+use crate::request::request_head;
+use crate::{
+    verify_request_with_resolver_and_options, HttpSigError, KeyResolver, VerificationOptions,
+    VerificationResult,
+};
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use openssl::hash::MessageDigest;
+use std::fmt;
+use std::sync::Arc;
+
+pub type SharedKeyResolver = Arc<dyn KeyResolver + Send + Sync>;
+
+// What `SignedRequest` verifies with
+#[derive(Clone)]
+pub struct HttpSigState {
+    pub key_resolver: SharedKeyResolver,
+    pub options: Arc<VerificationOptions>,
+}
+
+impl HttpSigState {
+    pub fn new(key_resolver: SharedKeyResolver) -> Self {
+        HttpSigState {
+            key_resolver,
+            options: Arc::new(VerificationOptions::default()),
+        }
+    }
+
+    pub fn with_options(self, options: VerificationOptions) -> Self {
+        HttpSigState {
+            options: Arc::new(options),
+            ..self
+        }
+    }
+}
+
+// So a plain `SharedKeyResolver` can still be the router state
+impl FromRef<SharedKeyResolver> for HttpSigState {
+    fn from_ref(key_resolver: &SharedKeyResolver) -> Self {
+        HttpSigState::new(key_resolver.clone())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SignedRequest(pub VerificationResult);
+
+#[derive(Debug)]
+pub struct HttpSigRejection(pub HttpSigError);
+
+impl fmt::Display for HttpSigRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl IntoResponse for HttpSigRejection {
+    fn into_response(self) -> Response {
+        (StatusCode::UNAUTHORIZED, self.to_string()).into_response()
+    }
+}
+
+impl<S> FromRequestParts<S> for SignedRequest
+where
+    S: Send + Sync,
+    HttpSigState: FromRef<S>,
+{
+    type Rejection = HttpSigRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let state = HttpSigState::from_ref(state);
+        verify(parts, &state.key_resolver, &state.options)
+            .map(SignedRequest)
+            .map_err(HttpSigRejection)
+    }
+}
+
+// axum uses a newer version of the `http` crate, so the request head is copied first
+fn verify(
+    parts: &Parts,
+    key_resolver: &impl KeyResolver,
+    options: &VerificationOptions,
+) -> Result<VerificationResult, HttpSigError> {
+    let uri = parts.uri.path_and_query().map_or_else(
+        || parts.uri.path(),
+        |path_and_query| path_and_query.as_str(),
+    );
+
+    let head = request_head(
+        parts.method.as_str(),
+        uri,
+        parts
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_bytes())),
+    )?;
+
+    verify_request_with_resolver_and_options(&head, key_resolver, MessageDigest::sha256(), options)
+}
@@ -0,0 +1,241 @@
+use crate::{Base64Alphabet, HttpSigError};
+use base64::Engine as _;
+use openssl::hash::{hash, DigestBytes, Hasher, MessageDigest};
+use std::fmt;
+use std::io::{self, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    fn message_digest(self) -> MessageDigest {
+        match self {
+            DigestAlgorithm::Sha256 => MessageDigest::sha256(),
+            DigestAlgorithm::Sha512 => MessageDigest::sha512(),
+        }
+    }
+}
+
+// Which header `add_content_digest_header` sets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestFormat {
+    // `Digest: SHA-256=<base64>`, as used by the draft's examples
+    // https://tools.ietf.org/html/rfc3230
+    Rfc3230,
+    // `Content-Digest: sha-256=:<base64>:`, a structured field dictionary
+    // https://www.rfc-editor.org/rfc/rfc9530.html
+    Rfc9530,
+}
+
+// Returns e.g. `SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=`
+pub fn compute_rfc3230_digest(
+    body: &[u8],
+    algorithm: DigestAlgorithm,
+) -> Result<String, HttpSigError> {
+    let name = match algorithm {
+        DigestAlgorithm::Sha256 => "SHA-256",
+        DigestAlgorithm::Sha512 => "SHA-512",
+    };
+    let digest = hash(algorithm.message_digest(), body)?;
+    Ok(format!(
+        "{}={}",
+        name,
+        Base64Alphabet::Standard.engine().encode(digest)
+    ))
+}
+
+// Returns e.g. `sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:`
+pub fn compute_rfc9530_digest(
+    body: &[u8],
+    algorithm: DigestAlgorithm,
+) -> Result<String, HttpSigError> {
+    let name = match algorithm {
+        DigestAlgorithm::Sha256 => "sha-256",
+        DigestAlgorithm::Sha512 => "sha-512",
+    };
+    let digest = hash(algorithm.message_digest(), body)?;
+    Ok(format!(
+        "{}=:{}:",
+        name,
+        Base64Alphabet::Standard.engine().encode(digest)
+    ))
+}
+
+// Sets the `Digest` header to the SHA-256 digest of the request body
+pub fn add_digest_header<T: AsRef<[u8]>>(
+    request: &mut http::Request<T>,
+) -> Result<(), HttpSigError> {
+    add_content_digest_header(request, DigestAlgorithm::Sha256, DigestFormat::Rfc3230)
+}
+
+// Sets the `Digest` or `Content-Digest` header, depending on `format`
+pub fn add_content_digest_header<T: AsRef<[u8]>>(
+    request: &mut http::Request<T>,
+    algorithm: DigestAlgorithm,
+    format: DigestFormat,
+) -> Result<(), HttpSigError> {
+    let body = request.body().as_ref();
+    let (header_name, header) = match format {
+        DigestFormat::Rfc3230 => ("digest", compute_rfc3230_digest(body, algorithm)?),
+        DigestFormat::Rfc9530 => ("content-digest", compute_rfc9530_digest(body, algorithm)?),
+    };
+    request.headers_mut().insert(header_name, header.parse()?);
+    Ok(())
+}
+
+// Checks the request body against its `Digest` header (https://tools.ietf.org/html/rfc3230), e.g.
+// `Digest: SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=`. The signature only covers the
+// header, so this needs to be checked separately to make sure the body wasn't tampered with.
+//
+// Returns `Ok(false)` if the header is missing or malformed (not ASCII, an instance without `=`, or
+// a digest that isn't base64), lists no supported algorithm, or any supported digest doesn't
+// match. Errors are only for OpenSSL failing to hash the body.
+pub fn verify_digest_header<T: AsRef<[u8]>>(
+    request: &http::Request<T>,
+) -> Result<bool, HttpSigError> {
+    let header = match request
+        .headers()
+        .get("digest")
+        .map(|header| header.to_str())
+    {
+        Some(Ok(header)) => header,
+        Some(Err(_)) | None => return Ok(false),
+    };
+
+    check_digest_header(header, |digest| Ok(hash(digest, request.body().as_ref())?))
+}
+
+// Checks each supported digest in a `Digest` header against `digest_of` the body
+fn check_digest_header(
+    header: &str,
+    mut digest_of: impl FnMut(MessageDigest) -> Result<DigestBytes, HttpSigError>,
+) -> Result<bool, HttpSigError> {
+    let mut verified = false;
+
+    for instance in header.split(',') {
+        let mut kv = instance.trim().splitn(2, '=');
+
+        let (algorithm, value) = match (kv.next(), kv.next()) {
+            (Some(algorithm), Some(value)) => (algorithm, value),
+            _ => return Ok(false),
+        };
+
+        let digest = match digest_for_algorithm(algorithm) {
+            Some(digest) => digest,
+            None => continue,
+        };
+
+        let expected = match Base64Alphabet::Standard.engine().decode(value) {
+            Ok(expected) => expected,
+            Err(_) => return Ok(false),
+        };
+        if *digest_of(digest)? != expected[..] {
+            return Ok(false);
+        }
+
+        verified = true;
+    }
+
+    Ok(verified)
+}
+
+fn digest_for_algorithm(algorithm: &str) -> Option<MessageDigest> {
+    if algorithm.eq_ignore_ascii_case("sha-256") {
+        Some(MessageDigest::sha256())
+    } else if algorithm.eq_ignore_ascii_case("sha-512") {
+        Some(MessageDigest::sha512())
+    } else {
+        None
+    }
+}
+
+// Checks a body against its `Digest` header as it's read, so that a large body can be streamed to
+// disk without holding it in memory. Write the body to it, then check the header with `verify`:
+//
+//     let mut verifier = DigestVerifier::new()?;
+//     for chunk in body {
+//         file.write_all(&chunk)?;
+//         verifier.write_all(&chunk)?;
+//     }
+//     if !verifier.verify(digest_header)? { /* reject */ }
+//
+// The header isn't known until the end, so the body is hashed with each supported algorithm.
+// `verify` returns the same as `verify_digest_header` would for the whole body.
+pub struct DigestVerifier {
+    sha256: Hasher,
+    sha512: Hasher,
+}
+
+impl DigestVerifier {
+    pub fn new() -> Result<Self, HttpSigError> {
+        Ok(DigestVerifier {
+            sha256: Hasher::new(MessageDigest::sha256())?,
+            sha512: Hasher::new(MessageDigest::sha512())?,
+        })
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) -> Result<(), HttpSigError> {
+        self.sha256.update(chunk)?;
+        self.sha512.update(chunk)?;
+        Ok(())
+    }
+
+    // `digest_header` is the value of the request's `Digest` header
+    pub fn verify(mut self, digest_header: &str) -> Result<bool, HttpSigError> {
+        let sha256 = self.sha256.finish()?;
+        let sha512 = self.sha512.finish()?;
+        check_digest_header(digest_header, |digest| {
+            Ok(if digest == MessageDigest::sha256() {
+                sha256
+            } else {
+                sha512
+            })
+        })
+    }
+}
+
+impl fmt::Debug for DigestVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DigestVerifier").finish_non_exhaustive()
+    }
+}
+
+impl Write for DigestVerifier {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf).map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Hashing doesn't block, so every write completes immediately
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncWrite for DigestVerifier {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<io::Result<usize>> {
+        std::task::Poll::Ready(self.get_mut().write(buf))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
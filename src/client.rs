@@ -0,0 +1,150 @@
+use crate::{add_signature_header_with_params, HttpSigError, SignatureParams};
+use openssl::pkey::{HasPrivate, PKey, PKeyRef, Private};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{IntoUrl, Method};
+
+// Wraps a `reqwest::Client` so that every request it sends is signed
+pub struct SigningClient {
+    inner: reqwest::Client,
+    params: SignatureParams,
+    private_key: PKey<Private>,
+}
+
+impl SigningClient {
+    pub fn new(
+        inner: reqwest::Client,
+        params: SignatureParams,
+        private_key: PKey<Private>,
+    ) -> Self {
+        SigningClient {
+            inner,
+            params,
+            private_key,
+        }
+    }
+
+    pub fn get<U: IntoUrl>(&self, url: U) -> SigningRequestBuilder<'_> {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post<U: IntoUrl>(&self, url: U) -> SigningRequestBuilder<'_> {
+        self.request(Method::POST, url)
+    }
+
+    pub fn put<U: IntoUrl>(&self, url: U) -> SigningRequestBuilder<'_> {
+        self.request(Method::PUT, url)
+    }
+
+    pub fn patch<U: IntoUrl>(&self, url: U) -> SigningRequestBuilder<'_> {
+        self.request(Method::PATCH, url)
+    }
+
+    pub fn delete<U: IntoUrl>(&self, url: U) -> SigningRequestBuilder<'_> {
+        self.request(Method::DELETE, url)
+    }
+
+    pub fn head<U: IntoUrl>(&self, url: U) -> SigningRequestBuilder<'_> {
+        self.request(Method::HEAD, url)
+    }
+
+    pub fn request<U: IntoUrl>(&self, method: Method, url: U) -> SigningRequestBuilder<'_> {
+        SigningRequestBuilder {
+            client: self,
+            inner: self.inner.request(method, url),
+        }
+    }
+
+    pub fn execute(
+        &self,
+        mut request: reqwest::Request,
+    ) -> Result<reqwest::Response, HttpSigError> {
+        self.sign(&mut request)?;
+        Ok(self.inner.execute(request)?)
+    }
+
+    pub fn sign(&self, request: &mut reqwest::Request) -> Result<(), HttpSigError> {
+        sign_reqwest_request(request, &self.params, &self.private_key)
+    }
+}
+
+// Mirrors `reqwest::RequestBuilder`, signing the request when it's built or sent
+pub struct SigningRequestBuilder<'a> {
+    client: &'a SigningClient,
+    inner: reqwest::RequestBuilder,
+}
+
+impl<'a> SigningRequestBuilder<'a> {
+    pub fn header<K, V>(self, key: K, value: V) -> Self
+    where
+        HeaderName: http::HttpTryFrom<K>,
+        HeaderValue: http::HttpTryFrom<V>,
+    {
+        self.map(|inner| inner.header(key, value))
+    }
+
+    pub fn headers(self, headers: HeaderMap) -> Self {
+        self.map(|inner| inner.headers(headers))
+    }
+
+    pub fn body<T: Into<reqwest::Body>>(self, body: T) -> Self {
+        self.map(|inner| inner.body(body))
+    }
+
+    // For any `reqwest::RequestBuilder` method not mirrored here
+    pub fn map<F>(self, f: F) -> Self
+    where
+        F: FnOnce(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+    {
+        SigningRequestBuilder {
+            client: self.client,
+            inner: f(self.inner),
+        }
+    }
+
+    pub fn build(self) -> Result<reqwest::Request, HttpSigError> {
+        let mut request = self.inner.build()?;
+        self.client.sign(&mut request)?;
+        Ok(request)
+    }
+
+    pub fn send(self) -> Result<reqwest::Response, HttpSigError> {
+        let client = self.client;
+        let request = self.build()?;
+        Ok(client.inner.execute(request)?)
+    }
+}
+
+// Signs an already-built `reqwest::Request`, e.g. before passing it to `reqwest::Client::execute`.
+// `Host` is added from the URL if it isn't set, so that it can be signed.
+pub fn sign_reqwest_request(
+    request: &mut reqwest::Request,
+    params: &SignatureParams,
+    private_key: &PKeyRef<impl HasPrivate>,
+) -> Result<(), HttpSigError> {
+    // reqwest only adds `Host` when the request is sent, so add it here to be able to sign it
+    if !request.headers().contains_key("host") {
+        if let Some(host) = request.url().host_str() {
+            let host = match request.url().port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_owned(),
+            };
+            request.headers_mut().insert("host", host.parse()?);
+        }
+    }
+
+    let mut target = request.url().path().to_owned();
+    if let Some(query) = request.url().query() {
+        target.push('?');
+        target.push_str(query);
+    }
+
+    let mut http_request = http::Request::new(());
+    *http_request.method_mut() = request.method().clone();
+    *http_request.uri_mut() = target.parse()?;
+    *http_request.headers_mut() = request.headers().clone();
+
+    add_signature_header_with_params(&mut http_request, params, private_key)?;
+
+    *request.headers_mut() = http_request.into_parts().0.headers;
+    Ok(())
+}
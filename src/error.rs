@@ -0,0 +1,571 @@
+use crate::{ParseError, PolicyViolation};
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// Errors from signing and verifying. Each variant's `Display` is a short, lowercase message:
+///
+/// ```
+/// use httpsig::{HttpSigError, ParseError};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let name = || "date".to_owned();
+/// let jan_5_2014 = UNIX_EPOCH + Duration::from_secs(1_388_957_500);
+/// let cases = vec![
+///     (HttpSigError::MissingSignature, "request has no signature"),
+///     (HttpSigError::InvalidSignatureHeader, "malformed signature header"),
+///     (
+///         HttpSigError::Parse(ParseError::MissingKeyId),
+///         "signature header is missing required 'keyId' field",
+///     ),
+///     (HttpSigError::InvalidSignature, "signature verification failed"),
+///     (
+///         HttpSigError::UnknownKey { key_id: "Test".to_owned() },
+///         "unknown key id 'Test'",
+///     ),
+///     (
+///         HttpSigError::MissingHeader { name: name() },
+///         "required header 'date' is not present",
+///     ),
+///     (
+///         HttpSigError::InvalidComponent { component: "date".to_owned() },
+///         "invalid component identifier date",
+///     ),
+///     (
+///         HttpSigError::MissingDictionaryKey { name: name(), key: "a".to_owned() },
+///         "header 'date' has no member 'a'",
+///     ),
+///     (
+///         HttpSigError::MissingQueryParam { name: "pet".to_owned() },
+///         "query parameter 'pet' not present",
+///     ),
+///     (
+///         HttpSigError::RepeatedQueryParam { name: "pet".to_owned() },
+///         "query parameter 'pet' appears more than once, so only '@query' can cover it",
+///     ),
+///     (
+///         HttpSigError::InvalidStructuredField { name: name() },
+///         "header 'date' is not a valid structured field",
+///     ),
+///     (
+///         HttpSigError::IncompatibleKey { algorithm: "hmac-sha256".to_owned() },
+///         "key can't be used with algorithm 'hmac-sha256'",
+///     ),
+///     (
+///         HttpSigError::MissingContentLength,
+///         "signature covers content-length, but the request has none (was it sent chunked?)",
+///     ),
+///     (
+///         HttpSigError::TooManySignedHeaders { count: 65, max: 64 },
+///         "signature covers 65 headers, but at most 64 are allowed",
+///     ),
+///     (
+///         HttpSigError::HostMismatch {
+///             expected: "example.com".to_owned(),
+///             actual: "example.org".to_owned(),
+///         },
+///         "host header is 'example.org', but the request was received on 'example.com'",
+///     ),
+///     (HttpSigError::HostNotSigned, "signature doesn't cover the host header"),
+///     (
+///         HttpSigError::DateNotSigned,
+///         "signature doesn't cover the date header or (created)",
+///     ),
+///     (
+///         HttpSigError::SignatureLengthMismatch { expected: 256, actual: 255 },
+///         "signature is 255 bytes, but the key produces 256-byte signatures",
+///     ),
+///     (
+///         HttpSigError::DigestMismatch { algorithm: "rsa-sha1".to_owned() },
+///         "signature algorithm 'rsa-sha1' doesn't use the verifier's digest",
+///     ),
+///     (HttpSigError::NonceNotSigned, "signature doesn't cover a nonce"),
+///     (
+///         HttpSigError::ReplayedNonce { nonce: "abc".to_owned() },
+///         "nonce 'abc' has already been used",
+///     ),
+///     (HttpSigError::UnsupportedKeyFormat, "unsupported key format"),
+///     (
+///         HttpSigError::PseudoHeaderNotAllowed {
+///             name: "(created)".to_owned(),
+///             algorithm: "rsa-sha256".to_owned(),
+///         },
+///         "'(created)' can't be signed with algorithm 'rsa-sha256'",
+///     ),
+///     (
+///         HttpSigError::SignatureExpired { expired_at: jan_5_2014 },
+///         "signature expired at Sun, 05 Jan 2014 21:31:40 GMT",
+///     ),
+///     (
+///         HttpSigError::SignatureTooOld {
+///             created: jan_5_2014,
+///             max_age: Duration::from_secs(300),
+///         },
+///         "signature was created at Sun, 05 Jan 2014 21:31:40 GMT, more than 300s ago",
+///     ),
+///     (
+///         HttpSigError::ClockSkew { skew: Duration::from_secs(301) },
+///         "date header is 301s away from the current time",
+///     ),
+///     (HttpSigError::IncompleteRequest, "incomplete request"),
+/// ];
+///
+/// for (error, expected) in cases {
+///     assert_eq!(error.to_string(), expected);
+/// }
+/// ```
+///
+/// Variants wrapping another crate's error add some context to its message, e.g.
+/// `invalid base64 signature: ...` for `Base64`.
+#[derive(Debug)]
+pub enum HttpSigError {
+    // Neither a `Signature` header nor an `Authorization: Signature` header was present
+    MissingSignature,
+    // The signature header contains bytes outside of ASCII
+    InvalidSignatureHeader,
+    // The signature header couldn't be parsed
+    Parse(ParseError),
+    // The signature didn't match the request
+    InvalidSignature,
+    // No key is known for the signature's `keyId`
+    UnknownKey {
+        key_id: String,
+    },
+    // A header that was requested to be signed, or that the signature covers, isn't in the request
+    MissingHeader {
+        name: String,
+    },
+    // An RFC 9421 component identifier that is malformed, unsupported or repeated
+    InvalidComponent {
+        component: String,
+    },
+    // An RFC 9421 component covers a member of a dictionary header that the header doesn't have
+    MissingDictionaryKey {
+        name: String,
+        key: String,
+    },
+    // A query parameter covered by `@query-param` isn't in the request
+    MissingQueryParam {
+        name: String,
+    },
+    // A query parameter covered by `@query-param` appears more than once, so it can't be covered.
+    // RFC 9421 leaves the order of repeated parameters undefined; cover `@query` instead.
+    RepeatedQueryParam {
+        name: String,
+    },
+    // A header covered with `;sf` or `;key` isn't a valid structured field
+    InvalidStructuredField {
+        name: String,
+    },
+    // The key's type can't be used with the requested algorithm
+    IncompatibleKey {
+        algorithm: String,
+    },
+    // The signature covers `content-length`, but the request doesn't have one. Signatures covering
+    // `content-length` can't be verified once the body is sent with `Transfer-Encoding: chunked`.
+    MissingContentLength,
+    // The signature covers more headers than `VerificationOptions::max_signed_headers`
+    TooManySignedHeaders {
+        count: usize,
+        max: usize,
+    },
+    // The `host` header isn't `VerificationOptions::expected_host`
+    HostMismatch {
+        expected: String,
+        actual: String,
+    },
+    // `VerificationOptions::expected_host` is set, but the signature doesn't cover `host`
+    HostNotSigned,
+    // `VerificationOptions::max_clock_skew` or `max_signature_age` is set, but the signature covers
+    // neither `date` nor `(created)`
+    DateNotSigned,
+    // The decoded signature can't have been produced by the verifying key
+    SignatureLengthMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    // The signature's `algorithm` names a different hash than the one it's verified with
+    DigestMismatch {
+        algorithm: String,
+    },
+    // A nonce store was given, but the signature doesn't cover a `(nonce)`
+    NonceNotSigned,
+    // The signature's nonce has already been used
+    ReplayedNonce {
+        nonce: String,
+    },
+    // A DER-encoded key that isn't PKCS #8, PKCS #1 or SubjectPublicKeyInfo
+    UnsupportedKeyFormat,
+    // The signature covers `(created)` or `(expires)` with an algorithm that doesn't allow it
+    PseudoHeaderNotAllowed {
+        name: String,
+        algorithm: String,
+    },
+    // The signature's `expires` parameter is in the past
+    SignatureExpired {
+        expired_at: SystemTime,
+    },
+    // The signature's signed `Date` or `created` is older than
+    // `VerificationOptions::max_signature_age`
+    SignatureTooOld {
+        created: SystemTime,
+        max_age: Duration,
+    },
+    // The `Date` header isn't a valid HTTP date
+    InvalidDate(httpdate::Error),
+    // The `Date` header is further from the current time than allowed
+    ClockSkew {
+        skew: Duration,
+    },
+    Base64(base64::DecodeError),
+    OpenSsl(openssl::error::ErrorStack),
+    InvalidHeaderValue(http::header::InvalidHeaderValue),
+    NonUtf8HeaderValue(http::header::ToStrError),
+    Io(std::io::Error),
+    Fmt(fmt::Error),
+    MalformedRequest(httparse::Error),
+    IncompleteRequest,
+    Http(http::Error),
+    InvalidUri(http::uri::InvalidUri),
+    #[cfg(feature = "reqwest")]
+    Reqwest(reqwest::Error),
+    #[cfg(any(feature = "jwk", feature = "json"))]
+    Json(serde_json::Error),
+    // A JWK that's missing a field, or is for an unsupported key type or curve
+    #[cfg(feature = "jwk")]
+    InvalidJwk {
+        reason: String,
+    },
+    // The document fetched from a `keyId` URL has no `publicKeyPem`
+    #[cfg(feature = "http-resolver")]
+    InvalidKeyDocument {
+        key_id: String,
+    },
+}
+
+impl fmt::Display for HttpSigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HttpSigError::MissingSignature => write!(f, "request has no signature"),
+            HttpSigError::InvalidSignatureHeader => write!(f, "malformed signature header"),
+            HttpSigError::Parse(e) => write!(f, "{}", e),
+            HttpSigError::InvalidSignature => write!(f, "signature verification failed"),
+            HttpSigError::UnknownKey { key_id } => write!(f, "unknown key id '{}'", key_id),
+            HttpSigError::MissingHeader { name } => {
+                write!(f, "required header '{}' is not present", name)
+            }
+            HttpSigError::InvalidComponent { component } => {
+                write!(f, "invalid component identifier {}", component)
+            }
+            HttpSigError::MissingDictionaryKey { name, key } => {
+                write!(f, "header '{}' has no member '{}'", name, key)
+            }
+            HttpSigError::MissingQueryParam { name } => {
+                write!(f, "query parameter '{}' not present", name)
+            }
+            HttpSigError::RepeatedQueryParam { name } => {
+                write!(
+                    f,
+                    "query parameter '{}' appears more than once, so only '@query' can cover it",
+                    name
+                )
+            }
+            HttpSigError::InvalidStructuredField { name } => {
+                write!(f, "header '{}' is not a valid structured field", name)
+            }
+            HttpSigError::IncompatibleKey { algorithm } => {
+                write!(f, "key can't be used with algorithm '{}'", algorithm)
+            }
+            HttpSigError::MissingContentLength => write!(
+                f,
+                "signature covers content-length, but the request has none (was it sent chunked?)"
+            ),
+            HttpSigError::TooManySignedHeaders { count, max } => write!(
+                f,
+                "signature covers {} headers, but at most {} are allowed",
+                count, max
+            ),
+            HttpSigError::HostMismatch { expected, actual } => write!(
+                f,
+                "host header is '{}', but the request was received on '{}'",
+                actual, expected
+            ),
+            HttpSigError::HostNotSigned => write!(f, "signature doesn't cover the host header"),
+            HttpSigError::DateNotSigned => {
+                write!(f, "signature doesn't cover the date header or (created)")
+            }
+            HttpSigError::SignatureLengthMismatch { expected, actual } => write!(
+                f,
+                "signature is {} bytes, but the key produces {}-byte signatures",
+                actual, expected
+            ),
+            HttpSigError::DigestMismatch { algorithm } => write!(
+                f,
+                "signature algorithm '{}' doesn't use the verifier's digest",
+                algorithm
+            ),
+            HttpSigError::NonceNotSigned => write!(f, "signature doesn't cover a nonce"),
+            HttpSigError::ReplayedNonce { nonce } => {
+                write!(f, "nonce '{}' has already been used", nonce)
+            }
+            HttpSigError::UnsupportedKeyFormat => write!(f, "unsupported key format"),
+            HttpSigError::PseudoHeaderNotAllowed { name, algorithm } => write!(
+                f,
+                "'{}' can't be signed with algorithm '{}'",
+                name, algorithm
+            ),
+            HttpSigError::SignatureExpired { expired_at } => write!(
+                f,
+                "signature expired at {}",
+                httpdate::fmt_http_date(*expired_at)
+            ),
+            HttpSigError::SignatureTooOld { created, max_age } => write!(
+                f,
+                "signature was created at {}, more than {}s ago",
+                httpdate::fmt_http_date(*created),
+                max_age.as_secs()
+            ),
+            HttpSigError::InvalidDate(e) => write!(f, "invalid date header: {}", e),
+            HttpSigError::ClockSkew { skew } => write!(
+                f,
+                "date header is {}s away from the current time",
+                skew.as_secs()
+            ),
+            HttpSigError::Base64(e) => write!(f, "invalid base64 signature: {}", e),
+            HttpSigError::OpenSsl(e) => write!(f, "openssl error: {}", e),
+            HttpSigError::InvalidHeaderValue(e) => write!(f, "invalid header value: {}", e),
+            HttpSigError::NonUtf8HeaderValue(e) => {
+                write!(f, "header value is not visible ASCII: {}", e)
+            }
+            HttpSigError::Io(e) => write!(f, "{}", e),
+            HttpSigError::Fmt(e) => write!(f, "{}", e),
+            HttpSigError::MalformedRequest(e) => write!(f, "malformed request: {}", e),
+            HttpSigError::IncompleteRequest => write!(f, "incomplete request"),
+            HttpSigError::Http(e) => write!(f, "invalid request: {}", e),
+            HttpSigError::InvalidUri(e) => write!(f, "invalid uri: {}", e),
+            #[cfg(feature = "reqwest")]
+            HttpSigError::Reqwest(e) => write!(f, "{}", e),
+            #[cfg(any(feature = "jwk", feature = "json"))]
+            HttpSigError::Json(e) => write!(f, "invalid JSON: {}", e),
+            #[cfg(feature = "jwk")]
+            HttpSigError::InvalidJwk { reason } => write!(f, "invalid JWK: {}", reason),
+            #[cfg(feature = "http-resolver")]
+            HttpSigError::InvalidKeyDocument { key_id } => {
+                write!(f, "no public key found at '{}'", key_id)
+            }
+        }
+    }
+}
+
+impl Error for HttpSigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HttpSigError::Parse(e) => Some(e),
+            HttpSigError::InvalidDate(e) => Some(e),
+            HttpSigError::Base64(e) => Some(e),
+            HttpSigError::OpenSsl(e) => Some(e),
+            HttpSigError::InvalidHeaderValue(e) => Some(e),
+            HttpSigError::NonUtf8HeaderValue(e) => Some(e),
+            HttpSigError::Io(e) => Some(e),
+            HttpSigError::Fmt(e) => Some(e),
+            HttpSigError::MalformedRequest(e) => Some(e),
+            HttpSigError::Http(e) => Some(e),
+            HttpSigError::InvalidUri(e) => Some(e),
+            #[cfg(feature = "reqwest")]
+            HttpSigError::Reqwest(e) => Some(e),
+            #[cfg(any(feature = "jwk", feature = "json"))]
+            HttpSigError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// Why `verify_request_strict` rejected a request
+#[derive(Debug)]
+pub enum VerificationError {
+    // Neither a `Signature` header nor an `Authorization: Signature` header was present
+    MissingSignatureHeader,
+    // A header the signature covers isn't in the request
+    MissingCoveredHeader {
+        name: String,
+    },
+    InvalidBase64(base64::DecodeError),
+    // The signature doesn't match the request
+    CryptographicFailure,
+    // The signature's `algorithm` parameter is for a different type of key than the verifying key
+    AlgorithmMismatch {
+        declared: String,
+        key_type: String,
+    },
+    SignatureExpired {
+        expired_at: SystemTime,
+    },
+    SignatureTooOld {
+        created: SystemTime,
+        max_age: Duration,
+    },
+    // The signature verified, but a `SignaturePolicy` rejected it
+    Policy(PolicyViolation),
+    // Anything else, e.g. a malformed signature header or a date outside the allowed clock skew
+    Other(HttpSigError),
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerificationError::MissingSignatureHeader => write!(f, "request has no signature"),
+            VerificationError::MissingCoveredHeader { name } => {
+                write!(
+                    f,
+                    "header '{}' is covered by the signature but not present",
+                    name
+                )
+            }
+            VerificationError::InvalidBase64(e) => write!(f, "invalid base64 signature: {}", e),
+            VerificationError::CryptographicFailure => write!(f, "signature verification failed"),
+            VerificationError::AlgorithmMismatch { declared, key_type } => write!(
+                f,
+                "signature algorithm '{}' can't be verified with a {} key",
+                declared, key_type
+            ),
+            VerificationError::SignatureExpired { expired_at } => HttpSigError::SignatureExpired {
+                expired_at: *expired_at,
+            }
+            .fmt(f),
+            VerificationError::SignatureTooOld { created, max_age } => {
+                HttpSigError::SignatureTooOld {
+                    created: *created,
+                    max_age: *max_age,
+                }
+                .fmt(f)
+            }
+            VerificationError::Policy(violation) => violation.fmt(f),
+            VerificationError::Other(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for VerificationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            VerificationError::InvalidBase64(e) => Some(e),
+            VerificationError::Policy(violation) => Some(violation),
+            VerificationError::Other(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<HttpSigError> for VerificationError {
+    fn from(e: HttpSigError) -> Self {
+        match e {
+            HttpSigError::MissingSignature => VerificationError::MissingSignatureHeader,
+            HttpSigError::MissingHeader { name } => {
+                VerificationError::MissingCoveredHeader { name }
+            }
+            HttpSigError::MissingContentLength => VerificationError::MissingCoveredHeader {
+                name: "content-length".to_owned(),
+            },
+            HttpSigError::Base64(e) => VerificationError::InvalidBase64(e),
+            HttpSigError::InvalidSignature => VerificationError::CryptographicFailure,
+            HttpSigError::SignatureExpired { expired_at } => {
+                VerificationError::SignatureExpired { expired_at }
+            }
+            HttpSigError::SignatureTooOld { created, max_age } => {
+                VerificationError::SignatureTooOld { created, max_age }
+            }
+            e => VerificationError::Other(e),
+        }
+    }
+}
+
+impl From<PolicyViolation> for VerificationError {
+    fn from(violation: PolicyViolation) -> Self {
+        match violation {
+            PolicyViolation::SignatureTooOld { created, max_age } => {
+                VerificationError::SignatureTooOld { created, max_age }
+            }
+            violation => VerificationError::Policy(violation),
+        }
+    }
+}
+
+impl From<ParseError> for HttpSigError {
+    fn from(e: ParseError) -> Self {
+        HttpSigError::Parse(e)
+    }
+}
+
+impl From<base64::DecodeError> for HttpSigError {
+    fn from(e: base64::DecodeError) -> Self {
+        HttpSigError::Base64(e)
+    }
+}
+
+impl From<openssl::error::ErrorStack> for HttpSigError {
+    fn from(e: openssl::error::ErrorStack) -> Self {
+        HttpSigError::OpenSsl(e)
+    }
+}
+
+impl From<http::header::InvalidHeaderValue> for HttpSigError {
+    fn from(e: http::header::InvalidHeaderValue) -> Self {
+        HttpSigError::InvalidHeaderValue(e)
+    }
+}
+
+impl From<http::header::ToStrError> for HttpSigError {
+    fn from(e: http::header::ToStrError) -> Self {
+        HttpSigError::NonUtf8HeaderValue(e)
+    }
+}
+
+impl From<std::io::Error> for HttpSigError {
+    fn from(e: std::io::Error) -> Self {
+        HttpSigError::Io(e)
+    }
+}
+
+impl From<fmt::Error> for HttpSigError {
+    fn from(e: fmt::Error) -> Self {
+        HttpSigError::Fmt(e)
+    }
+}
+
+impl From<httparse::Error> for HttpSigError {
+    fn from(e: httparse::Error) -> Self {
+        HttpSigError::MalformedRequest(e)
+    }
+}
+
+impl From<http::Error> for HttpSigError {
+    fn from(e: http::Error) -> Self {
+        HttpSigError::Http(e)
+    }
+}
+
+impl From<http::uri::InvalidUri> for HttpSigError {
+    fn from(e: http::uri::InvalidUri) -> Self {
+        HttpSigError::InvalidUri(e)
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl From<reqwest::Error> for HttpSigError {
+    fn from(e: reqwest::Error) -> Self {
+        HttpSigError::Reqwest(e)
+    }
+}
+
+#[cfg(any(feature = "jwk", feature = "json"))]
+impl From<serde_json::Error> for HttpSigError {
+    fn from(e: serde_json::Error) -> Self {
+        HttpSigError::Json(e)
+    }
+}
+
+impl From<httpdate::Error> for HttpSigError {
+    fn from(e: httpdate::Error) -> Self {
+        HttpSigError::InvalidDate(e)
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{add_digest_header, add_signature_header_with_params, HttpSigError, SignatureParams};
+use http::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE};
+use openssl::pkey::{HasPrivate, PKeyRef};
+use std::fmt::Write as _;
+
+// Serializes the fields as `application/x-www-form-urlencoded`, sorted by name so the same fields
+// always produce the same body. Fields with the same name keep their order.
+pub fn encode_form(fields: &[(&str, &str)]) -> String {
+    let mut fields = fields.to_vec();
+    fields.sort_by_key(|&(name, _)| name);
+
+    let mut output = String::new();
+    for (i, (name, value)) in fields.into_iter().enumerate() {
+        if i > 0 {
+            output.push('&');
+        }
+        write_form_component(&mut output, name);
+        output.push('=');
+        write_form_component(&mut output, value);
+    }
+    output
+}
+
+// https://url.spec.whatwg.org/#urlencoded-serializing
+fn write_form_component(output: &mut String, input: &str) {
+    for &byte in input.as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"*-._".contains(&byte) {
+            output.push(byte as char);
+        } else if byte == b' ' {
+            output.push('+');
+        } else {
+            let _ = write!(output, "%{:02X}", byte);
+        }
+    }
+}
+
+// Replaces the body with the encoded fields, sets `Content-Type`, `Content-Length` and `Digest`,
+// then signs the request. If `params.headers` is unset, all of those are signed.
+pub fn sign_form_request(
+    request: &mut http::Request<Vec<u8>>,
+    fields: &[(&str, &str)],
+    params: &SignatureParams,
+    private_key: &PKeyRef<impl HasPrivate>,
+) -> Result<(), HttpSigError> {
+    let body = encode_form(fields).into_bytes();
+    let content_length = body.len();
+    *request.body_mut() = body;
+
+    let headers = request.headers_mut();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/x-www-form-urlencoded"),
+    );
+    headers.insert(CONTENT_LENGTH, HeaderValue::from(content_length));
+
+    add_digest_header(request)?;
+    add_signature_header_with_params(request, params, private_key)
+}
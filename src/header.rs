@@ -0,0 +1,17 @@
+use http::header::HeaderName;
+use std::sync::LazyLock;
+
+pub const SIGNATURE_HEADER: &str = "signature";
+// Used alongside `Signature` by RFC 9421
+pub const SIGNATURE_INPUT_HEADER: &str = "signature-input";
+pub const AUTHORIZATION_HEADER: &str = "authorization";
+
+// Header names are case-insensitive. `HeaderMap` lowercases them, so `Signature` and `signature`
+// are the same entry whether they're looked up by `&str` or `HeaderName`.
+//
+// `HeaderName::from_static` isn't a `const fn` in this version of `http`, so these are built on
+// first use rather than being `const`. Pass `&*SIGNATURE_HEADER_NAME` to `HeaderMap` methods.
+pub static SIGNATURE_HEADER_NAME: LazyLock<HeaderName> =
+    LazyLock::new(|| HeaderName::from_static(SIGNATURE_HEADER));
+pub static SIGNATURE_INPUT_HEADER_NAME: LazyLock<HeaderName> =
+    LazyLock::new(|| HeaderName::from_static(SIGNATURE_INPUT_HEADER));
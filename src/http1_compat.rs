@@ -0,0 +1,75 @@
+// Signing and verifying `http` 1.x requests and responses, e.g. from hyper 1 or axum, with
+// `sign_message` and `verify_message`:
+//
+//     let mut request = http1::Request::post("https://example.com/inbox").body(body)?;
+//     httpsig::sign_message(&mut request, &params, &private_key)?;
+//
+// The rest of the crate uses `http` 0.1, so the headers are copied first.
+
+use crate::{HttpSigError, SignableMessage};
+
+impl<T> SignableMessage for http1::Request<T> {
+    fn method(&self) -> Option<&str> {
+        Some(self.method().as_str())
+    }
+
+    fn uri_target(&self) -> Option<&str> {
+        // Like `request_target`, an empty query is signed as no query
+        Some(match self.uri().path_and_query() {
+            Some(path_and_query) if path_and_query.query() == Some("") => path_and_query.path(),
+            Some(path_and_query) => path_and_query.as_str(),
+            None => self.uri().path(),
+        })
+    }
+
+    fn headers_iter(&self) -> Box<dyn Iterator<Item = (&str, &[u8])> + '_> {
+        Box::new(
+            self.headers()
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_bytes())),
+        )
+    }
+
+    fn set_header(&mut self, name: &'static str, value: &str) -> Result<(), HttpSigError> {
+        set_header(self.headers_mut(), name, value)
+    }
+}
+
+impl<T> SignableMessage for http1::Response<T> {
+    fn method(&self) -> Option<&str> {
+        None
+    }
+
+    fn uri_target(&self) -> Option<&str> {
+        None
+    }
+
+    fn headers_iter(&self) -> Box<dyn Iterator<Item = (&str, &[u8])> + '_> {
+        Box::new(
+            self.headers()
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_bytes())),
+        )
+    }
+
+    fn set_header(&mut self, name: &'static str, value: &str) -> Result<(), HttpSigError> {
+        set_header(self.headers_mut(), name, value)
+    }
+}
+
+// Validated as an `http` 0.1 name and value, so the error fits `HttpSigError`. Both versions
+// accept the same bytes.
+fn set_header(
+    headers: &mut http1::HeaderMap,
+    name: &'static str,
+    value: &str,
+) -> Result<(), HttpSigError> {
+    let name = http::header::HeaderName::from_bytes(name.as_bytes()).map_err(http::Error::from)?;
+    let name = http1::header::HeaderName::from_bytes(name.as_str().as_bytes())
+        .expect("header name is valid in http 0.1");
+    let value = http::HeaderValue::from_str(value)?;
+    let value = http1::HeaderValue::from_bytes(value.as_bytes())
+        .expect("header value is valid in http 0.1");
+    headers.insert(name, value);
+    Ok(())
+}
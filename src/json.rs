@@ -0,0 +1,40 @@
+use crate::{add_digest_header, add_signature_header_with_params, HttpSigError, SignatureParams};
+use http::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE};
+use openssl::pkey::{HasPrivate, PKeyRef};
+use serde::Serialize;
+
+// Builds a signed request from `parts` with `body` serialized as JSON, setting `Content-Length`
+// and `Digest` before signing. If `params.headers` is unset, all of those are signed.
+//
+// `Content-Type` is set to `content_type` if given. Otherwise a `Content-Type` already in `parts`
+// is kept (e.g. `application/activity+json`), falling back to `application/json`.
+pub fn sign_request_with_body<B: Serialize + ?Sized>(
+    mut parts: http::request::Parts,
+    body: &B,
+    content_type: Option<&str>,
+    params: &SignatureParams,
+    private_key: &PKeyRef<impl HasPrivate>,
+) -> Result<http::Request<Vec<u8>>, HttpSigError> {
+    let body = serde_json::to_vec(body)?;
+
+    match content_type {
+        Some(content_type) => {
+            parts.headers.insert(CONTENT_TYPE, content_type.parse()?);
+        }
+        None => {
+            parts
+                .headers
+                .entry(CONTENT_TYPE)
+                .expect("CONTENT_TYPE is a valid header name")
+                .or_insert(HeaderValue::from_static("application/json"));
+        }
+    }
+    parts
+        .headers
+        .insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+
+    let mut request = http::Request::from_parts(parts, body);
+    add_digest_header(&mut request)?;
+    add_signature_header_with_params(&mut request, params, private_key)?;
+    Ok(request)
+}
@@ -0,0 +1,121 @@
+// Detached JWS (RFC 7515 Appendix F) over a signing string, for bridging to systems that verify
+// JWS rather than HTTP signatures. This isn't part of the HTTP signatures spec: the JWS payload is
+// the signing string, which the verifier has to rebuild from the request itself.
+
+use crate::{write_signing_string, Base64Alphabet, HttpSigError, RequestHead};
+use base64::Engine as _;
+use openssl::bn::BigNumRef;
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{HasPrivate, Id, PKeyRef};
+use openssl::rsa::Padding;
+use openssl::sign::{RsaPssSaltlen, Signer};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JwsAlgorithm {
+    Rs256,
+    Rs384,
+    Rs512,
+    Ps256,
+    Ps384,
+    Ps512,
+    Es256,
+    Es384,
+}
+
+impl JwsAlgorithm {
+    // The `alg` header value
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JwsAlgorithm::Rs256 => "RS256",
+            JwsAlgorithm::Rs384 => "RS384",
+            JwsAlgorithm::Rs512 => "RS512",
+            JwsAlgorithm::Ps256 => "PS256",
+            JwsAlgorithm::Ps384 => "PS384",
+            JwsAlgorithm::Ps512 => "PS512",
+            JwsAlgorithm::Es256 => "ES256",
+            JwsAlgorithm::Es384 => "ES384",
+        }
+    }
+
+    fn digest(self) -> MessageDigest {
+        match self {
+            JwsAlgorithm::Rs256 | JwsAlgorithm::Ps256 | JwsAlgorithm::Es256 => {
+                MessageDigest::sha256()
+            }
+            JwsAlgorithm::Rs384 | JwsAlgorithm::Ps384 | JwsAlgorithm::Es384 => {
+                MessageDigest::sha384()
+            }
+            JwsAlgorithm::Rs512 | JwsAlgorithm::Ps512 => MessageDigest::sha512(),
+        }
+    }
+
+    fn key_id(self) -> Id {
+        match self {
+            JwsAlgorithm::Es256 | JwsAlgorithm::Es384 => Id::EC,
+            _ => Id::RSA,
+        }
+    }
+}
+
+// Returns `<header>..<signature>`, with `{"alg":"..."}` as the protected header
+pub fn compute_detached_jws(
+    signing_string: &[u8],
+    private_key: &PKeyRef<impl HasPrivate>,
+    alg: JwsAlgorithm,
+) -> Result<String, HttpSigError> {
+    if private_key.id() != alg.key_id() {
+        return Err(HttpSigError::IncompatibleKey {
+            algorithm: alg.as_str().to_owned(),
+        });
+    }
+
+    let header = format!(r#"{{"alg":"{}"}}"#, alg.as_str());
+    let base64url = Base64Alphabet::UrlSafeNoPad.engine();
+    let mut output = base64url.encode(&header);
+
+    let mut signing_input = output.clone();
+    signing_input.push('.');
+    base64url.encode_string(signing_string, &mut signing_input);
+
+    let mut signer = Signer::new(alg.digest(), private_key)?;
+    if let JwsAlgorithm::Ps256 | JwsAlgorithm::Ps384 | JwsAlgorithm::Ps512 = alg {
+        signer.set_rsa_padding(Padding::PKCS1_PSS)?;
+        signer.set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)?;
+    }
+    signer.update(signing_input.as_bytes())?;
+    let mut signature = signer.sign_to_vec()?;
+
+    // openssl produces DER, but JWS wants the fixed-size `r || s`
+    if let JwsAlgorithm::Es256 | JwsAlgorithm::Es384 = alg {
+        let len = if alg == JwsAlgorithm::Es256 { 32 } else { 48 };
+        let ecdsa = EcdsaSig::from_der(&signature)?;
+        signature = fixed_size(ecdsa.r(), len)?;
+        signature.extend(fixed_size(ecdsa.s(), len)?);
+    }
+
+    output.push_str("..");
+    base64url.encode_string(&signature, &mut output);
+
+    Ok(output)
+}
+
+// Signs the same signing string as `add_signature_header_with_params` would for `headers`
+pub fn compute_detached_jws_for_request<T>(
+    request: &http::Request<T>,
+    headers: Option<&[String]>,
+    private_key: &PKeyRef<impl HasPrivate>,
+    alg: JwsAlgorithm,
+) -> Result<String, HttpSigError> {
+    let mut signing_string = Vec::new();
+    write_signing_string(
+        &mut signing_string,
+        &RequestHead::new(request.method(), request.uri(), request.headers()),
+        headers,
+    )?;
+    compute_detached_jws(&signing_string, private_key, alg)
+}
+
+fn fixed_size(n: &BigNumRef, len: i32) -> Result<Vec<u8>, HttpSigError> {
+    Ok(n.to_vec_padded(len)?)
+}
@@ -0,0 +1,108 @@
+use crate::keys::compute_thumbprint;
+use crate::HttpSigError;
+use openssl::pkey::{PKey, Public};
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+
+// Looks up the public key for a signature's `keyId`. `Ok(None)` means the key isn't known.
+pub trait KeyResolver {
+    fn resolve(&self, key_id: &str) -> Result<Option<PKey<Public>>, HttpSigError>;
+}
+
+impl<R: KeyResolver + ?Sized> KeyResolver for &R {
+    fn resolve(&self, key_id: &str) -> Result<Option<PKey<Public>>, HttpSigError> {
+        (**self).resolve(key_id)
+    }
+}
+
+impl<R: KeyResolver + ?Sized> KeyResolver for Arc<R> {
+    fn resolve(&self, key_id: &str) -> Result<Option<PKey<Public>>, HttpSigError> {
+        (**self).resolve(key_id)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryKeyStore {
+    keys: HashMap<String, PKey<Public>>,
+}
+
+impl InMemoryKeyStore {
+    pub fn new() -> Self {
+        InMemoryKeyStore::default()
+    }
+
+    // Returns the key previously stored under `key_id`, if any
+    pub fn insert(&mut self, key_id: impl Into<String>, key: PKey<Public>) -> Option<PKey<Public>> {
+        self.keys.insert(key_id.into(), key)
+    }
+
+    pub fn remove(&mut self, key_id: &str) -> Option<PKey<Public>> {
+        self.keys.remove(key_id)
+    }
+
+    pub fn get(&self, key_id: &str) -> Option<&PKey<Public>> {
+        self.keys.get(key_id)
+    }
+}
+
+impl KeyResolver for InMemoryKeyStore {
+    fn resolve(&self, key_id: &str) -> Result<Option<PKey<Public>>, HttpSigError> {
+        Ok(self.keys.get(key_id).cloned())
+    }
+}
+
+// Keys indexed by their SPKI thumbprint (see `keys::compute_thumbprint`), for signers that use the
+// thumbprint as the `keyId`
+#[derive(Clone, Debug, Default)]
+pub struct ThumbprintKeyStore {
+    keys: HashMap<String, PKey<Public>>,
+}
+
+impl ThumbprintKeyStore {
+    pub fn new() -> Self {
+        ThumbprintKeyStore::default()
+    }
+
+    // Returns the key's thumbprint
+    pub fn insert(&mut self, key: PKey<Public>) -> Result<String, HttpSigError> {
+        let thumbprint = compute_thumbprint(&key)?;
+        self.keys.insert(thumbprint.clone(), key);
+        Ok(thumbprint)
+    }
+
+    pub fn remove(&mut self, thumbprint: &str) -> Option<PKey<Public>> {
+        self.keys.remove(thumbprint)
+    }
+
+    pub fn get(&self, thumbprint: &str) -> Option<&PKey<Public>> {
+        self.keys.get(thumbprint)
+    }
+}
+
+impl KeyResolver for ThumbprintKeyStore {
+    fn resolve(&self, key_id: &str) -> Result<Option<PKey<Public>>, HttpSigError> {
+        Ok(self.keys.get(key_id).cloned())
+    }
+}
+
+// Used by `global_verify`, for applications with a single key store that don't want to pass it
+// around. Empty until `set_global_key_store` is called.
+static GLOBAL_KEY_STORE: RwLock<Option<InMemoryKeyStore>> = RwLock::new(None);
+
+// Replaces the key store used by `global_verify`
+pub fn set_global_key_store(store: InMemoryKeyStore) {
+    *GLOBAL_KEY_STORE
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(store);
+}
+
+pub(crate) struct GlobalKeyStore;
+
+impl KeyResolver for GlobalKeyStore {
+    fn resolve(&self, key_id: &str) -> Result<Option<PKey<Public>>, HttpSigError> {
+        let store = GLOBAL_KEY_STORE
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        Ok(store.as_ref().and_then(|store| store.get(key_id).cloned()))
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{Base64Alphabet, HttpSigError};
+use base64::Engine as _;
+use openssl::hash::{hash, MessageDigest};
+use openssl::pkey::{HasPublic, PKey, PKeyRef, Private, Public};
+use openssl::rsa::Rsa;
+
+// Loads an unencrypted PEM-encoded private key, e.g. a `BEGIN PRIVATE KEY` or
+// `BEGIN RSA PRIVATE KEY` block. Encrypted keys are an error rather than openssl prompting for a
+// passphrase on the terminal; use `private_key_from_pem_passphrase` for those.
+pub fn private_key_from_pem(pem: &[u8]) -> Result<PKey<Private>, HttpSigError> {
+    Ok(PKey::private_key_from_pem_callback(pem, |_| Ok(0))?)
+}
+
+// Loads a PEM-encoded public key, e.g. a `BEGIN PUBLIC KEY` block
+pub fn public_key_from_pem(pem: &[u8]) -> Result<PKey<Public>, HttpSigError> {
+    Ok(PKey::public_key_from_pem(pem)?)
+}
+
+// Loads a DER-encoded private key, either PKCS #8 or (for RSA keys) PKCS #1
+pub fn private_key_from_der(der: &[u8]) -> Result<PKey<Private>, HttpSigError> {
+    PKey::private_key_from_pkcs8(der)
+        .or_else(|_| Rsa::private_key_from_der(der).and_then(PKey::from_rsa))
+        .map_err(|_| HttpSigError::UnsupportedKeyFormat)
+}
+
+// Loads a DER-encoded public key, either a SubjectPublicKeyInfo or (for RSA keys) PKCS #1
+pub fn public_key_from_der(der: &[u8]) -> Result<PKey<Public>, HttpSigError> {
+    PKey::public_key_from_der(der)
+        .or_else(|_| Rsa::public_key_from_der_pkcs1(der).and_then(PKey::from_rsa))
+        .map_err(|_| HttpSigError::UnsupportedKeyFormat)
+}
+
+// Loads a PEM-encoded private key that is encrypted with `passphrase`, e.g. a
+// `BEGIN ENCRYPTED PRIVATE KEY` block
+pub fn private_key_from_pem_passphrase(
+    pem: &[u8],
+    passphrase: &[u8],
+) -> Result<PKey<Private>, HttpSigError> {
+    Ok(PKey::private_key_from_pem_passphrase(pem, passphrase)?)
+}
+
+// The base64-encoded SHA-256 digest of the DER-encoded SubjectPublicKeyInfo, the same form used
+// for `pin-sha256` in HTTP public key pinning
+pub fn compute_thumbprint(key: &PKeyRef<impl HasPublic>) -> Result<String, HttpSigError> {
+    let spki = key.public_key_to_der()?;
+    let thumbprint = hash(MessageDigest::sha256(), &spki)?;
+    Ok(Base64Alphabet::Standard.engine().encode(thumbprint))
+}
+
+// Loads an RSA (`n`, `e`), EC (`crv`, `x`, `y`) or Ed25519 (`crv`, `x`) public key from a JWK.
+// Other fields, like `kid` and `use`, are ignored.
+// https://www.rfc-editor.org/rfc/rfc7518.html#section-6
+#[cfg(feature = "jwk")]
+pub fn public_key_from_jwk(jwk_json: &str) -> Result<PKey<Public>, HttpSigError> {
+    use openssl::bn::BigNum;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::pkey::Id;
+
+    let invalid = |reason: String| HttpSigError::InvalidJwk { reason };
+    let jwk: serde_json::Value = serde_json::from_str(jwk_json)?;
+    let field = |name: &str| {
+        jwk.get(name)
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| invalid(format!("missing '{}'", name)))
+    };
+    // Base64url without padding, as in JWS
+    let bytes = |name: &str| {
+        Base64Alphabet::UrlSafeNoPad
+            .engine()
+            .decode(field(name)?)
+            .map_err(|e| invalid(format!("'{}' is not base64url: {}", name, e)))
+    };
+    let big_num = |name: &str| Ok::<_, HttpSigError>(BigNum::from_slice(&bytes(name)?)?);
+
+    match field("kty")? {
+        "RSA" => {
+            let rsa = Rsa::from_public_components(big_num("n")?, big_num("e")?)?;
+            Ok(PKey::from_rsa(rsa)?)
+        }
+        "EC" => {
+            let curve = match field("crv")? {
+                "P-256" => Nid::X9_62_PRIME256V1,
+                "P-384" => Nid::SECP384R1,
+                "P-521" => Nid::SECP521R1,
+                crv => return Err(invalid(format!("unsupported curve '{}'", crv))),
+            };
+            let group = EcGroup::from_curve_name(curve)?;
+            let (x, y) = (big_num("x")?, big_num("y")?);
+            let ec_key = EcKey::from_public_key_affine_coordinates(&group, &x, &y)?;
+            Ok(PKey::from_ec_key(ec_key)?)
+        }
+        "OKP" => match field("crv")? {
+            "Ed25519" => Ok(PKey::public_key_from_raw_bytes(&bytes("x")?, Id::ED25519)?),
+            crv => Err(invalid(format!("unsupported curve '{}'", crv))),
+        },
+        kty => Err(invalid(format!("unsupported key type '{}'", kty))),
+    }
+}
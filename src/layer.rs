@@ -0,0 +1,137 @@
+use crate::{
+    add_digest_header, add_signature_header_with_params, compute_rfc3230_digest, sign_message,
+    DigestAlgorithm, HttpSigError, SignableMessage, SignatureParams,
+};
+use openssl::pkey::{PKey, Private};
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+// A `tower::Layer` that signs each request before passing it to the inner service. It signs both
+// `http` 0.1 and `http` 1.x requests, so it can be stacked with tower-http's layers around a hyper
+// 1 client, as in `examples/tower_signing.rs`.
+//
+// The body must already be buffered (`B: AsRef<[u8]>`) so that a `Digest` header can be computed
+// from it. Streaming bodies should be collected before reaching this layer.
+#[derive(Clone)]
+pub struct SigningLayer {
+    params: Arc<SignatureParams>,
+    private_key: Arc<PKey<Private>>,
+    digest: bool,
+}
+
+impl SigningLayer {
+    pub fn new(params: SignatureParams, private_key: PKey<Private>) -> Self {
+        SigningLayer {
+            params: Arc::new(params),
+            private_key: Arc::new(private_key),
+            digest: false,
+        }
+    }
+
+    // Also sets the `Digest` header from the body before signing
+    pub fn with_digest(mut self) -> Self {
+        self.digest = true;
+        self
+    }
+
+    fn sign<B: AsRef<[u8]>>(&self, request: &mut http::Request<B>) -> Result<(), HttpSigError> {
+        if self.digest {
+            add_digest_header(request)?;
+        }
+        add_signature_header_with_params(request, &self.params, &self.private_key)
+    }
+
+    fn sign_http1<B: AsRef<[u8]>>(
+        &self,
+        request: &mut http1::Request<B>,
+    ) -> Result<(), HttpSigError> {
+        if self.digest {
+            let digest = compute_rfc3230_digest(request.body().as_ref(), DigestAlgorithm::Sha256)?;
+            request.set_header("digest", &digest)?;
+        }
+        sign_message(request, &self.params, &self.private_key)
+    }
+}
+
+impl<S> Layer<S> for SigningLayer {
+    type Service = SigningService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SigningService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SigningService<S> {
+    inner: S,
+    layer: SigningLayer,
+}
+
+impl<S, B> Service<http::Request<B>> for SigningService<S>
+where
+    S: Service<http::Request<B>>,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    B: AsRef<[u8]>,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        match self.layer.sign(&mut request) {
+            Ok(()) => call_inner(&mut self.inner, request),
+            Err(e) => Box::pin(async move { Err(e.into()) }),
+        }
+    }
+}
+
+impl<S, B> Service<http1::Request<B>> for SigningService<S>
+where
+    S: Service<http1::Request<B>>,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    B: AsRef<[u8]>,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut request: http1::Request<B>) -> Self::Future {
+        match self.layer.sign_http1(&mut request) {
+            Ok(()) => call_inner(&mut self.inner, request),
+            Err(e) => Box::pin(async move { Err(e.into()) }),
+        }
+    }
+}
+
+fn call_inner<S, R>(
+    inner: &mut S,
+    request: R,
+) -> Pin<Box<dyn Future<Output = Result<S::Response, BoxError>> + Send>>
+where
+    S: Service<R>,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+{
+    let response = inner.call(request);
+    Box::pin(async move { response.await.map_err(Into::into) })
+}
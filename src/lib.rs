@@ -1,130 +1,1273 @@
+mod activitypub;
+#[cfg(feature = "actix-web")]
+mod actix;
+#[cfg(feature = "axum")]
+pub mod axum;
+mod body_digest;
+#[cfg(feature = "reqwest")]
+mod client;
+mod error;
+mod form;
+mod header;
+#[cfg(feature = "http1")]
+mod http1_compat;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "jws")]
+pub mod jws;
+mod key_store;
+pub mod keys;
+#[cfg(feature = "tower-http")]
+mod layer;
+mod logger;
+mod message;
+mod nonce;
+mod options;
+pub mod parse;
+mod policy;
+mod request;
+#[cfg(feature = "http-resolver")]
+pub mod resolver;
+mod reusable;
+pub mod rfc9421;
+mod structured_field;
+mod template;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+mod typed_request;
+#[cfg(feature = "warp")]
+pub mod warp;
+
+pub use crate::activitypub::{sign_activitypub_request, sign_activitypub_request_at};
+#[cfg(feature = "actix-web")]
+pub use crate::actix::{HttpSigMiddleware, HttpSigMiddlewareService};
+pub use crate::body_digest::{
+    add_content_digest_header, add_digest_header, compute_rfc3230_digest, compute_rfc9530_digest,
+    verify_digest_header, DigestAlgorithm, DigestFormat, DigestVerifier,
+};
+#[cfg(feature = "reqwest")]
+pub use crate::client::{sign_reqwest_request, SigningClient, SigningRequestBuilder};
+pub use crate::error::{HttpSigError, VerificationError};
+pub use crate::form::{encode_form, sign_form_request};
+pub use crate::header::{
+    AUTHORIZATION_HEADER, SIGNATURE_HEADER, SIGNATURE_HEADER_NAME, SIGNATURE_INPUT_HEADER,
+    SIGNATURE_INPUT_HEADER_NAME,
+};
+#[cfg(feature = "json")]
+pub use crate::json::sign_request_with_body;
+pub use crate::key_store::{
+    set_global_key_store, InMemoryKeyStore, KeyResolver, ThumbprintKeyStore,
+};
+#[cfg(feature = "tower-http")]
+pub use crate::layer::{SigningLayer, SigningService};
+#[cfg(feature = "audit-log")]
+pub use crate::logger::JsonSignatureLogger;
+pub use crate::logger::SignatureLogger;
+pub use crate::message::{
+    sign_message, verify_message, verify_message_with_options, SignableMessage,
+};
+pub use crate::nonce::{verify_request_with_nonce_store, InMemoryNonceStore, NonceStore};
+pub use crate::options::{
+    Base64Alphabet, DraftVersion, KeyIdTransform, OnParseError, PssSaltLen, SignatureParams,
+    VerificationOptions,
+};
+pub use crate::parse::{
+    parse_signature_parts, signature_headers_equivalent, ParseError, SignaturePreview,
+};
+pub use crate::policy::{
+    verify_request_with_policy, verify_request_with_signature_policy, AllowedAlgorithmsPolicy,
+    MaxAgePolicy, PolicyChain, PolicyContext, PolicyViolation, RequiredHeadersPolicy,
+    RequiredKeyIdPrefixPolicy, SignaturePolicy, SignedHeaders,
+};
+pub use crate::request::{parse_request, LineFolding};
+pub use crate::reusable::{ReusableSigner, ReusableVerifier};
+pub use crate::rfc9421::compute_rfc9421_signature_base;
+pub use crate::template::{PseudoHeaderValues, SigningStringTemplate};
+pub use crate::typed_request::{SignedRequest, VerifiedRequest};
+
+use base64::Engine as _;
+use http::header::HeaderValue;
 use openssl::hash::MessageDigest;
-use openssl::pkey::{HasPrivate, HasPublic, PKeyRef};
+use openssl::pkey::{HasPrivate, HasPublic, Id, PKey, PKeyRef};
+use openssl::rsa::Padding;
 use openssl::sign::{Signer, Verifier};
-use std::error::Error;
-use std::fmt::Write as _;
+use std::convert::TryFrom;
+use std::fmt::{self, Write as _};
 use std::io::Write as _;
+use std::ops::Range;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-pub fn verify_request<'a, T>(
+pub fn verify_request<T>(
     request: &http::Request<T>,
     digest: MessageDigest,
     public_key: &PKeyRef<impl HasPublic>,
-) -> Result<bool, Box<dyn Error + Send + Sync>> {
-    if let Some(signature) = request.headers().get("signature") {
-        if let Some(parts) = parse_signature_parts(signature.to_str()?) {
-            verify_signature_parts(request, &parts, digest, public_key)
-        } else {
-            Ok(false)
+) -> Result<bool, HttpSigError> {
+    verify_request_with_options(request, digest, public_key, &VerificationOptions::default())
+}
+
+// Like `verify_request`, loading the public key from PEM (`BEGIN PUBLIC KEY`) first
+pub fn verify_request_pem<T>(
+    request: &http::Request<T>,
+    digest: MessageDigest,
+    public_pem: &[u8],
+) -> Result<bool, HttpSigError> {
+    let public_key = PKey::public_key_from_pem(public_pem)?;
+    verify_request(request, digest, &public_key)
+}
+
+// Like `verify_request`, loading a DER-encoded SubjectPublicKeyInfo first
+pub fn verify_request_der<T>(
+    request: &http::Request<T>,
+    digest: MessageDigest,
+    public_der: &[u8],
+) -> Result<bool, HttpSigError> {
+    let public_key = PKey::public_key_from_der(public_der)?;
+    verify_request(request, digest, &public_key)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "verify_request", skip_all)
+)]
+pub fn verify_request_with_options<T>(
+    request: &http::Request<T>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+    options: &VerificationOptions,
+) -> Result<bool, HttpSigError> {
+    let head = RequestHead::new(request.method(), request.uri(), request.headers());
+    let mut scratch = Scratch::default();
+    match find_signature_in_request(request) {
+        Ok(parts) => {
+            let verified = verify_signature_parts_for_target(
+                &head,
+                &parts,
+                digest,
+                public_key,
+                options,
+                &mut scratch,
+            );
+            if let Some(logger) = &options.logger {
+                let mismatch;
+                let outcome = match &verified {
+                    Ok(true) => Ok(()),
+                    Ok(false) => {
+                        mismatch = mismatch_reason(&head, &parts, options, &mut scratch);
+                        Err(&mismatch)
+                    }
+                    Err(e) => Err(e),
+                };
+                logger::log_verification(&**logger, request, Some(&parts), outcome);
+            }
+            verified
+        }
+        Err(e) => {
+            // Unsigned requests aren't logged, but unparseable signatures are
+            if let Some(logger) = &options.logger {
+                if !matches!(e, HttpSigError::MissingSignature) {
+                    logger::log_verification(&**logger, request, None, Err(&e));
+                }
+            }
+            options.unparsed_signature(e)
         }
+    }
+}
+
+// Like `verify_request`, but returns which key, algorithm and headers were verified, for audit logs
+pub fn verify_request_audit<T>(
+    request: &http::Request<T>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+) -> Result<Option<VerifiedInfo>, HttpSigError> {
+    let parts = match find_signature_in_request(request) {
+        Ok(parts) => parts,
+        Err(HttpSigError::MissingSignature)
+        | Err(HttpSigError::InvalidSignatureHeader)
+        | Err(HttpSigError::Parse(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    if verify_signature_parts(request, &parts, digest, public_key)? {
+        Ok(Some(VerifiedInfo::from(&parts)))
     } else {
-        Ok(false)
+        Ok(None)
     }
 }
 
-pub fn verify_signature_parts<'a, T>(
+// Verifies each request like `verify_request_strict`, sharing buffers between them. Each request
+// gets the details of its signature, or a `VerificationError` saying why it was rejected, and a
+// rejected request doesn't stop the others from being checked. The only outer error is a key that
+// can't verify with `digest` at all, which is checked before any of the requests.
+//
+// Like `sign_requests`, each request gets its own OpenSSL `Verifier`, since one can't be reset.
+pub fn verify_requests<T>(
+    requests: &[http::Request<T>],
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+) -> Result<Vec<Result<VerificationResult, VerificationError>>, HttpSigError> {
+    Verifier::new(digest, public_key)?;
+
+    let options = VerificationOptions::default();
+    let mut scratch = Scratch::default();
+    let results = requests
+        .iter()
+        .map(|request| verify_strict(request, digest, public_key, &options, &mut scratch))
+        .collect();
+    Ok(results)
+}
+
+// What the verifier did with a request's signature, for diagnosing signatures that don't verify.
+// This exposes the signed header values, so shouldn't be logged in production.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifyDebug {
+    pub verified: bool,
+    // The signing string rebuilt from the request
+    pub signing_string: Vec<u8>,
+    // The signature's `headers` parameter, or `date` if it had none
+    pub declared_headers: String,
+}
+
+// Like `verify_request`, but also returns the signing string that was checked. Fails if the
+// request has no signature, or if a signed header is missing (so there is no signing string).
+pub fn verify_request_debug<T>(
+    request: &http::Request<T>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+) -> Result<VerifyDebug, HttpSigError> {
+    let parts = find_signature_in_request(request)?;
+    let declared_headers = parts.headers.unwrap_or("date");
+    let mut signing_string = Vec::new();
+    write_verification_string(
+        &mut signing_string,
+        &RequestHead::new(request.method(), request.uri(), request.headers())
+            .with_params(&parts, DraftVersion::default()),
+        declared_headers,
+    )?;
+
+    Ok(VerifyDebug {
+        verified: verify_signature_parts(request, &parts, digest, public_key)?,
+        signing_string,
+        declared_headers: declared_headers.to_owned(),
+    })
+}
+
+// Looks for the signature in the `Signature` header first, falling back to an `Authorization`
+// header using the `Signature` auth scheme.
+pub fn find_signature_in_request<T>(
+    request: &http::Request<T>,
+) -> Result<SignatureParts<'_>, HttpSigError> {
+    find_signature_in_headers(request.headers())
+}
+
+fn find_signature_in_headers(
+    headers: &http::HeaderMap,
+) -> Result<SignatureParts<'_>, HttpSigError> {
+    Ok(parse_signature_parts(signature_header_value(headers)?)?)
+}
+
+// Just the `keyId` of the request's signature, e.g. to pick which verifier to send the request to.
+// Only scans for `keyId` rather than parsing the whole header, so the header may still turn out to
+// be malformed when it's verified.
+pub fn key_id_from_request<T>(request: &http::Request<T>) -> Option<&str> {
+    parse::key_id(signature_header_value(request.headers()).ok()?)
+}
+
+// Everything but the signature itself, e.g. to log which key and headers a request claims to be
+// signed with. The signature isn't decoded, so it may not even be valid base64.
+pub fn preview_signature<T>(request: &http::Request<T>) -> Result<SignaturePreview, HttpSigError> {
+    let parts = find_signature_in_headers(request.headers())?;
+    Ok(SignaturePreview::from(&parts))
+}
+
+// The parameters of the `Signature` header, or of an `Authorization: Signature` header
+fn signature_header_value(headers: &http::HeaderMap) -> Result<&str, HttpSigError> {
+    // Other headers can contain arbitrary bytes, but a signature is always ASCII
+    let signature = if let Some(signature) = headers.get(SIGNATURE_HEADER) {
+        signature
+            .to_str()
+            .map_err(|_| HttpSigError::InvalidSignatureHeader)?
+    } else if let Some(authorization) = headers.get(AUTHORIZATION_HEADER) {
+        // Only the scheme is checked before decoding, since other schemes' credentials are opaque
+        let authorization = authorization.as_bytes();
+        match authorization.iter().position(|&c| c == b' ') {
+            Some(index) if authorization[..index].eq_ignore_ascii_case(b"signature") => {
+                std::str::from_utf8(&authorization[index + 1..])
+                    .map_err(|_| HttpSigError::InvalidSignatureHeader)?
+                    .trim_start()
+            }
+            _ => return Err(HttpSigError::MissingSignature),
+        }
+    } else {
+        return Err(HttpSigError::MissingSignature);
+    };
+
+    Ok(signature)
+}
+
+// Like `verify_request_with_options`, but says why a request was rejected instead of returning
+// `Ok(false)`. Also rejects signatures whose `algorithm` is for a different type of key.
+pub fn verify_request_strict<T>(
     request: &http::Request<T>,
-    parts: &SignatureParts<'a>,
     digest: MessageDigest,
     public_key: &PKeyRef<impl HasPublic>,
-) -> Result<bool, Box<dyn Error + Send + Sync>> {
-    let signature = base64::decode(parts.signature)?;
+    options: &VerificationOptions,
+) -> Result<(), VerificationError> {
+    verify_strict(
+        request,
+        digest,
+        public_key,
+        options,
+        &mut Scratch::default(),
+    )
+    .map(|_| ())
+}
+
+fn verify_strict<T>(
+    request: &http::Request<T>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+    options: &VerificationOptions,
+    scratch: &mut Scratch,
+) -> Result<VerificationResult, VerificationError> {
+    let parts = find_signature_in_request(request)?;
+    if let Some(declared) = parts.algorithm {
+        check_algorithm(declared, public_key)?;
+    }
+
+    let head = RequestHead::new(request.method(), request.uri(), request.headers());
+    if verify_signature_parts_for_target(&head, &parts, digest, public_key, options, scratch)? {
+        return Ok(VerificationResult::from(&parts));
+    }
+
+    Err(mismatch_reason(&head, &parts, options, scratch).into())
+}
+
+// Why a signature that `verify_signature_parts_for_target` returned `Ok(false)` for didn't verify.
+// A missing covered header is reported as a plain mismatch there, so check for one here.
+fn mismatch_reason(
+    head: &RequestHead<'_>,
+    parts: &SignatureParts<'_>,
+    options: &VerificationOptions,
+    scratch: &mut Scratch,
+) -> HttpSigError {
+    scratch.signing_string.clear();
+    match write_verification_string(
+        &mut scratch.signing_string,
+        &head.with_params(parts, options.draft_version),
+        parts.headers.unwrap_or("date"),
+    ) {
+        Ok(()) => HttpSigError::InvalidSignature,
+        Err(e) => e,
+    }
+}
+
+// `hs2019` and unrecognized algorithms don't say which type of key to use
+fn check_algorithm(
+    declared: &str,
+    public_key: &PKeyRef<impl HasPublic>,
+) -> Result<(), VerificationError> {
+    let expected = match declared.split('-').next() {
+        Some("rsa") => Id::RSA,
+        Some("ecdsa") => Id::EC,
+        Some("dsa") => Id::DSA,
+        Some("hmac") => Id::HMAC,
+        Some("ed25519") => Id::ED25519,
+        _ => return Ok(()),
+    };
+
+    if public_key.id() == expected {
+        return Ok(());
+    }
+
+    let key_type = match public_key.id() {
+        Id::RSA => "rsa".to_owned(),
+        Id::EC => "ec".to_owned(),
+        Id::DSA => "dsa".to_owned(),
+        Id::HMAC => "hmac".to_owned(),
+        Id::ED25519 => "ed25519".to_owned(),
+        id => format!("{:?}", id),
+    };
+    Err(VerificationError::AlgorithmMismatch {
+        declared: declared.to_owned(),
+        key_type,
+    })
+}
+
+// Verifies a request that has been split up with `into_parts`, e.g. before reading the body
+pub fn verify_request_parts(
+    parts: &http::request::Parts,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+) -> Result<VerificationResult, HttpSigError> {
+    let signature = find_signature_in_headers(&parts.headers)?;
+    let verified = verify_signature_parts_for_target(
+        &RequestHead::new(&parts.method, &parts.uri, &parts.headers),
+        &signature,
+        digest,
+        public_key,
+        &VerificationOptions::default(),
+        &mut Scratch::default(),
+    )?;
+
+    if verified {
+        Ok(VerificationResult::from(&signature))
+    } else {
+        Err(HttpSigError::InvalidSignature)
+    }
+}
+
+// Like `verify_request_with_resolver`, using the key store from `set_global_key_store`. Fails with
+// `UnknownKey` if no global key store has been set.
+pub fn global_verify<T>(request: &http::Request<T>) -> Result<VerificationResult, HttpSigError> {
+    verify_request_with_resolver(request, &key_store::GlobalKeyStore)
+}
+
+// Resolves the key for the request's `keyId` and verifies the signature with it, using SHA-256,
+// which is what most implementations use
+pub fn verify_request_with_resolver<T>(
+    request: &http::Request<T>,
+    resolver: &impl KeyResolver,
+) -> Result<VerificationResult, HttpSigError> {
+    verify_request_with_resolver_and_digest(request, resolver, MessageDigest::sha256())
+}
+
+// Like `verify_request_with_resolver`, with the digest the key's owner signs with. The digest isn't
+// taken from the signature's `algorithm`, since the sender could use it to pick a weaker hash. If
+// `algorithm` names a hash, it has to be `digest`, or this fails with `DigestMismatch`.
+pub fn verify_request_with_resolver_and_digest<T>(
+    request: &http::Request<T>,
+    resolver: &impl KeyResolver,
+    digest: MessageDigest,
+) -> Result<VerificationResult, HttpSigError> {
+    verify_request_with_resolver_and_options(
+        request,
+        resolver,
+        digest,
+        &VerificationOptions::default(),
+    )
+}
+
+// Like `verify_request_with_resolver_and_digest`, also checking `options`. The default options
+// don't look at when the request was signed, so a captured request verifies forever; set
+// `max_clock_skew` or `max_signature_age` to stop it being replayed.
+pub fn verify_request_with_resolver_and_options<T>(
+    request: &http::Request<T>,
+    resolver: &impl KeyResolver,
+    digest: MessageDigest,
+    options: &VerificationOptions,
+) -> Result<VerificationResult, HttpSigError> {
+    let parts = find_signature_in_request(request)?;
+    let public_key = resolver
+        .resolve(parts.key_id)?
+        .ok_or_else(|| HttpSigError::UnknownKey {
+            key_id: parts.key_id.to_owned(),
+        })?;
+
+    if let Some(algorithm) = parts.algorithm {
+        if digest_for_algorithm(algorithm).is_some_and(|declared| declared != digest) {
+            return Err(HttpSigError::DigestMismatch {
+                algorithm: algorithm.to_owned(),
+            });
+        }
+    }
+
+    if verify_signature_parts_with_options(request, &parts, digest, &public_key, options)? {
+        Ok(VerificationResult::from(&parts))
+    } else {
+        Err(HttpSigError::InvalidSignature)
+    }
+}
+
+// The hash named by e.g. `rsa-sha256`. `hs2019` and unrecognized algorithms don't name one.
+fn digest_for_algorithm(algorithm: &str) -> Option<MessageDigest> {
+    match algorithm.rsplit('-').next() {
+        Some("sha1") => Some(MessageDigest::sha1()),
+        Some("sha256") => Some(MessageDigest::sha256()),
+        Some("sha384") => Some(MessageDigest::sha384()),
+        Some("sha512") => Some(MessageDigest::sha512()),
+        _ => None,
+    }
+}
+
+pub fn verify_signature_parts<T>(
+    request: &http::Request<T>,
+    parts: &SignatureParts<'_>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+) -> Result<bool, HttpSigError> {
+    verify_signature_parts_with_options(
+        request,
+        parts,
+        digest,
+        public_key,
+        &VerificationOptions::default(),
+    )
+}
+
+pub fn verify_signature_parts_with_options<T>(
+    request: &http::Request<T>,
+    parts: &SignatureParts<'_>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+    options: &VerificationOptions,
+) -> Result<bool, HttpSigError> {
+    verify_signature_parts_with_buffers(
+        request,
+        parts,
+        digest,
+        public_key,
+        options,
+        &mut VerificationBuffers::new(),
+    )
+}
+
+// Like `verify_signature_parts_with_options`, reusing `buffers` for the signing string so that
+// verifying doesn't allocate once they've grown large enough. Signatures of up to 512 bytes (i.e.
+// RSA-4096) are decoded on the stack.
+pub fn verify_signature_parts_with_buffers<T>(
+    request: &http::Request<T>,
+    parts: &SignatureParts<'_>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+    options: &VerificationOptions,
+    buffers: &mut VerificationBuffers,
+) -> Result<bool, HttpSigError> {
+    verify_signature_parts_for_target(
+        &RequestHead::new(request.method(), request.uri(), request.headers()),
+        parts,
+        digest,
+        public_key,
+        options,
+        &mut buffers.scratch,
+    )
+}
+
+// Like `verify_signature_parts`, but for RSA-PSS signatures with the given salt length
+pub fn verify_signature_pss<T>(
+    request: &http::Request<T>,
+    parts: &SignatureParts<'_>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+    salt_len: PssSaltLen,
+) -> Result<bool, HttpSigError> {
+    if public_key.id() != Id::RSA {
+        return Err(HttpSigError::IncompatibleKey {
+            algorithm: "rsa-pss".to_owned(),
+        });
+    }
+
+    let options = VerificationOptions {
+        rsa_pss_salt_len: Some(salt_len),
+        ..VerificationOptions::default()
+    };
+    verify_signature_parts_with_options(request, parts, digest, public_key, &options)
+}
+
+// For servers behind a proxy that rewrites the path: verifies the request as if `(request-target)`
+// were `method` and `path` (including the query, e.g. reconstructed from `X-Forwarded-*` headers)
+// rather than the request's own method and URI.
+pub fn verify_request_with_target<T>(
+    request: &http::Request<T>,
+    method: &http::Method,
+    path: &str,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+) -> Result<bool, HttpSigError> {
+    match find_signature_in_request(request) {
+        Ok(parts) => verify_signature_parts_for_target(
+            &RequestHead {
+                request_line: Some((method, path)),
+                headers: request.headers(),
+                nonce: None,
+                created: None,
+                expires: None,
+            },
+            &parts,
+            digest,
+            public_key,
+            &VerificationOptions::default(),
+            &mut Scratch::default(),
+        ),
+        Err(HttpSigError::MissingSignature)
+        | Err(HttpSigError::InvalidSignatureHeader)
+        | Err(HttpSigError::Parse(_)) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+// Like `verify_request`, for a signature that didn't arrive in the request's headers, e.g. one
+// passed along separately by a proxy. `signature_header` is the value of a `Signature` header.
+pub fn verify_request_with_signature<T>(
+    request: &http::Request<T>,
+    signature_header: &str,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+) -> Result<bool, HttpSigError> {
+    match parse_signature_parts(signature_header) {
+        Ok(parts) => verify_signature_parts_with_options(
+            request,
+            &parts,
+            digest,
+            public_key,
+            &VerificationOptions::default(),
+        ),
+        Err(_) => Ok(false),
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "verify_signature_parts",
+        skip_all,
+        fields(
+            key_id = parts.key_id,
+            algorithm = parts.algorithm,
+            signed_headers = parts.headers.unwrap_or("date"),
+            verification_result = tracing::field::Empty,
+        )
+    )
+)]
+pub(crate) fn verify_signature_parts_for_target(
+    head: &RequestHead<'_>,
+    parts: &SignatureParts<'_>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+    options: &VerificationOptions,
+    scratch: &mut Scratch,
+) -> Result<bool, HttpSigError> {
+    let result = verify_signature_parts_inner(head, parts, digest, public_key, options, scratch);
+
+    #[cfg(feature = "tracing")]
+    {
+        if let Ok(verified) = result {
+            tracing::Span::current().record("verification_result", verified);
+        }
+    }
+
+    result
+}
+
+fn verify_signature_parts_inner(
+    head: &RequestHead<'_>,
+    parts: &SignatureParts<'_>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+    options: &VerificationOptions,
+    scratch: &mut Scratch,
+) -> Result<bool, HttpSigError> {
+    let signed_headers = parts.headers.unwrap_or("date");
+    let count = signed_headers.split(' ').count();
+    if count > options.max_signed_headers {
+        return Err(HttpSigError::TooManySignedHeaders {
+            count,
+            max: options.max_signed_headers,
+        });
+    }
+
+    let now = || options.now.unwrap_or_else(SystemTime::now);
+    // `(created)` is only signed as the `created` parameter in draft 12
+    let created = match options.draft_version {
+        DraftVersion::Draft12 => parts.created,
+        _ => None,
+    };
+    if let Some(expires) = parts.expires {
+        check_expires(expires, now())?;
+    }
+    if let Some(max_age) = options.max_signature_age {
+        let created = signed_timestamp(head.headers, signed_headers, created)?;
+        check_signature_age(created, max_age, now())?;
+    }
+    if let Some(max_clock_skew) = options.max_clock_skew {
+        let date = signed_timestamp(head.headers, signed_headers, created)?;
+        check_date(date, max_clock_skew, now())?;
+    }
+
+    if let Some(expected_host) = &options.expected_host {
+        check_host(head.headers, signed_headers, expected_host)?;
+    }
+    if options.draft_version == DraftVersion::Draft12 {
+        check_pseudo_headers(signed_headers, parts.algorithm)?;
+    }
+
+    let mut stack = [0; STACK_DECODE_LEN];
+    let signature = decode_signature_buf(
+        parts.signature,
+        options.base64_alphabet,
+        &mut stack,
+        &mut scratch.signature,
+    )?;
+
+    if options.check_signature_length {
+        if let Some(expected) = expected_signature_len(public_key) {
+            if signature.len() != expected {
+                return Err(HttpSigError::SignatureLengthMismatch {
+                    expected,
+                    actual: signature.len(),
+                });
+            }
+        }
+    }
+
+    let to_verify = &mut scratch.signing_string;
+    to_verify.clear();
+    let head = head.with_params(parts, options.draft_version);
+    match write_verification_string(to_verify, &head, signed_headers) {
+        Ok(()) => {}
+        Err(HttpSigError::MissingHeader { .. }) => return Ok(false),
+        Err(e) => return Err(e),
+    }
 
     let mut verifier = Verifier::new(digest, public_key)?;
-    let mut to_verify: Vec<u8> = Vec::new();
+    if let Some(salt_len) = options.rsa_pss_salt_len {
+        if public_key.id() == Id::RSA {
+            verifier.set_rsa_padding(Padding::PKCS1_PSS)?;
+            verifier.set_rsa_pss_saltlen(salt_len.for_verifying())?;
+        }
+    }
+    verifier.update(to_verify)?;
 
-    for header_name in parts.headers.unwrap_or("date").split(' ') {
-        if header_name == "(request-target)" {
-            write!(
-                &mut to_verify,
-                "(request-target): {} {}\n",
-                request.method().as_str().to_ascii_lowercase(),
-                request.uri()
-            )?;
-        } else if let Some(header_value) = request.headers().get(header_name) {
-            write!(
-                &mut to_verify,
-                "{}: {}\n",
-                header_name,
-                header_value.to_str()?
-            )?;
+    Ok(verifier.verify(signature)?)
+}
+
+// Room for the base64 decoder to write a signature of up to `STACK_SIGNATURE_LEN` bytes, since it
+// decodes 8 characters (6 bytes) at a time
+const STACK_DECODE_LEN: usize = (STACK_SIGNATURE_LEN / 6 + 1) * 6;
+
+// Decodes into `stack` if the signature fits, and `heap` otherwise. If no alphabet is given, the
+// signature is decoded as standard base64, falling back to URL-safe base64.
+fn decode_signature_buf<'b>(
+    encoded: &str,
+    alphabet: Option<Base64Alphabet>,
+    stack: &'b mut [u8],
+    heap: &'b mut Vec<u8>,
+) -> Result<&'b [u8], base64::DecodeError> {
+    // More than the 3 bytes per 4 characters `decode_slice_unchecked` needs
+    let on_stack = encoded.len().div_ceil(8) * 6 <= stack.len();
+    let decode = |alphabet: Base64Alphabet, stack: &mut [u8], heap: &mut Vec<u8>| {
+        if on_stack {
+            alphabet.engine().decode_slice_unchecked(encoded, stack)
         } else {
-            return Ok(false);
+            heap.clear();
+            alphabet
+                .engine()
+                .decode_vec(encoded, heap)
+                .map(|()| heap.len())
+        }
+    };
+
+    let len = match alphabet {
+        Some(alphabet) => decode(alphabet, stack, heap)?,
+        None => decode(Base64Alphabet::Standard, stack, heap)
+            .or_else(|e| decode(Base64Alphabet::UrlSafe, stack, heap).map_err(|_| e))?,
+    };
+
+    Ok(if on_stack {
+        &stack[..len]
+    } else {
+        &heap[..len]
+    })
+}
+
+// Rebuilds the signing string the signer should have used. Fails with `MissingHeader` if one of
+// the signed headers isn't in the request.
+fn write_verification_string(
+    to_verify: &mut Vec<u8>,
+    head: &RequestHead<'_>,
+    signed_headers: &str,
+) -> Result<(), HttpSigError> {
+    match write_signed_headers(to_verify, head, signed_headers.split(' ')) {
+        // Usually the request was re-sent or proxied with `Transfer-Encoding: chunked`, which is
+        // worth distinguishing from a plain signature mismatch
+        Err(HttpSigError::MissingHeader { name }) if name == "content-length" => {
+            Err(HttpSigError::MissingContentLength)
         }
+        result => result,
     }
+}
+
+// Writes `name: value` for each header, joined by newlines. An empty list is treated like a
+// missing `headers` parameter, which means just `date`.
+fn write_signed_headers<'h>(
+    out: &mut Vec<u8>,
+    head: &RequestHead<'_>,
+    header_names: impl IntoIterator<Item = &'h str>,
+) -> Result<(), HttpSigError> {
+    let mut header_names = header_names
+        .into_iter()
+        .filter(|header_name| !header_name.is_empty())
+        .peekable();
+    let default = match header_names.peek() {
+        Some(_) => None,
+        None => Some("date"),
+    };
+
+    for (i, header_name) in header_names.chain(default).enumerate() {
+        if i > 0 {
+            out.push(b'\n');
+        }
 
-    // `pop` to remove the trailing newline. If it returns `None`, there were no headers, so we
-    // should default to checking the `date` header.
-    if to_verify.pop().is_none() {
-        if let Some(date) = request.headers().get("date") {
-            write!(&mut to_verify, "date: {}", date.to_str()?)?;
+        // The signing string uses the lowercased header name, whatever case the `headers`
+        // parameter lists it in
+        // https://tools.ietf.org/html/draft-cavage-http-signatures-10#section-2.3
+        let header_name = header_name.to_ascii_lowercase();
+
+        if header_name == "(request-target)" {
+            write_request_target(out, head)?;
+        } else if header_name == "(nonce)" {
+            match head.nonce {
+                Some(nonce) => write!(out, "(nonce): {}", nonce)?,
+                None => return Err(HttpSigError::MissingHeader { name: header_name }),
+            }
+        } else if header_name == "(created)" || header_name == "(expires)" {
+            let timestamp = if header_name == "(created)" {
+                head.created
+            } else {
+                head.expires
+            };
+            match timestamp {
+                Some(timestamp) => write!(out, "{}: {}", header_name, timestamp)?,
+                None => return Err(HttpSigError::MissingHeader { name: header_name }),
+            }
+        } else if head.headers.contains_key(header_name.as_str()) {
+            write!(out, "{}: ", header_name)?;
+            write_header_values(out, head.headers, &header_name);
         } else {
-            return Ok(false);
+            return Err(HttpSigError::MissingHeader { name: header_name });
+        }
+    }
+
+    Ok(())
+}
+
+// https://tools.ietf.org/html/draft-cavage-http-signatures-12#section-2.3
+fn check_pseudo_headers(signed_headers: &str, algorithm: Option<&str>) -> Result<(), HttpSigError> {
+    let algorithm = match algorithm {
+        Some(algorithm)
+            if matches!(algorithm.split('-').next(), Some("rsa" | "hmac" | "ecdsa")) =>
+        {
+            algorithm
+        }
+        _ => return Ok(()),
+    };
+
+    match signed_headers.split(' ').find(|name| {
+        name.eq_ignore_ascii_case("(created)") || name.eq_ignore_ascii_case("(expires)")
+    }) {
+        Some(name) => Err(HttpSigError::PseudoHeaderNotAllowed {
+            name: name.to_ascii_lowercase(),
+            algorithm: algorithm.to_owned(),
+        }),
+        None => Ok(()),
+    }
+}
+
+fn check_expires(expires: u64, now: SystemTime) -> Result<(), HttpSigError> {
+    let expires = UNIX_EPOCH + Duration::from_secs(expires);
+    if expires < now {
+        return Err(HttpSigError::SignatureExpired {
+            expired_at: expires,
+        });
+    }
+    Ok(())
+}
+
+fn check_signature_age(
+    created: SystemTime,
+    max_age: Duration,
+    now: SystemTime,
+) -> Result<(), HttpSigError> {
+    match now.duration_since(created) {
+        Ok(age) if age > max_age => Err(HttpSigError::SignatureTooOld { created, max_age }),
+        _ => Ok(()),
+    }
+}
+
+// When the request was signed: its `Date` header, or the `created` parameter if the signature
+// covers `(created)` instead. Whichever is used has to be signed, since otherwise it could be
+// changed freely when the request is replayed.
+//
+// Accepts all three date formats allowed by HTTP/1.1 (IMF-fixdate, RFC 850, and asctime)
+// https://tools.ietf.org/html/rfc7231#section-7.1.1.1
+fn signed_timestamp(
+    headers: &http::HeaderMap,
+    signed_headers: &str,
+    created: Option<u64>,
+) -> Result<SystemTime, HttpSigError> {
+    let signs = |name: &str| {
+        signed_headers
+            .split(' ')
+            .any(|signed| signed.eq_ignore_ascii_case(name))
+    };
+
+    if signs("date") {
+        match headers.get("date") {
+            // A value that isn't ASCII can't be a date either
+            Some(date) => Ok(httpdate::parse_http_date(&String::from_utf8_lossy(
+                date.as_bytes(),
+            ))?),
+            None => Err(HttpSigError::MissingHeader {
+                name: "date".to_owned(),
+            }),
+        }
+    } else {
+        match created {
+            Some(created) if signs("(created)") => Ok(UNIX_EPOCH + Duration::from_secs(created)),
+            _ => Err(HttpSigError::DateNotSigned),
         }
     }
+}
 
-    verifier.update(&to_verify)?;
+fn check_date(
+    date: SystemTime,
+    max_clock_skew: Duration,
+    now: SystemTime,
+) -> Result<(), HttpSigError> {
+    let skew = match now.duration_since(date) {
+        Ok(skew) => skew,
+        Err(e) => e.duration(),
+    };
 
-    Ok(verifier.verify(&signature)?)
+    if skew > max_clock_skew {
+        Err(HttpSigError::ClockSkew { skew })
+    } else {
+        Ok(())
+    }
+}
+
+fn check_host(
+    headers: &http::HeaderMap,
+    signed_headers: &str,
+    expected_host: &str,
+) -> Result<(), HttpSigError> {
+    let host = match headers.get(http::header::HOST) {
+        Some(host) => trim_ows(host.as_bytes()),
+        None => {
+            return Err(HttpSigError::MissingHeader {
+                name: "host".to_owned(),
+            })
+        }
+    };
+
+    if !host.eq_ignore_ascii_case(expected_host.as_bytes()) {
+        return Err(HttpSigError::HostMismatch {
+            expected: expected_host.to_owned(),
+            actual: String::from_utf8_lossy(host).into_owned(),
+        });
+    }
+
+    if !signed_headers
+        .split(' ')
+        .any(|name| name.eq_ignore_ascii_case("host"))
+    {
+        return Err(HttpSigError::HostNotSigned);
+    }
+
+    Ok(())
+}
+
+// Only RSA signatures have a fixed size. ECDSA and DSA signatures are DER-encoded and vary in
+// length, so those are left to OpenSSL.
+fn expected_signature_len(public_key: &PKeyRef<impl HasPublic>) -> Option<usize> {
+    match public_key.id() {
+        Id::RSA => Some(public_key.size()),
+        _ => None,
+    }
+}
+
+// Details of a signature that verified successfully
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerificationResult {
+    pub key_id: String,
+    pub algorithm: Option<String>,
+    // Lowercased, in the order they were signed
+    pub signed_headers: Vec<String>,
+}
+
+pub type VerifiedInfo = VerificationResult;
+
+impl From<&SignatureParts<'_>> for VerificationResult {
+    fn from(parts: &SignatureParts<'_>) -> Self {
+        VerificationResult {
+            key_id: parts.key_id.to_owned(),
+            algorithm: parts.algorithm.map(str::to_owned),
+            signed_headers: parts
+                .headers
+                .unwrap_or("date")
+                .split(' ')
+                .map(str::to_ascii_lowercase)
+                .collect(),
+        }
+    }
+}
+
+// Verifies against the global key store, like `global_verify`
+impl<T> TryFrom<&http::Request<T>> for VerificationResult {
+    type Error = HttpSigError;
+
+    fn try_from(request: &http::Request<T>) -> Result<Self, Self::Error> {
+        global_verify(request)
+    }
 }
 
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
 pub struct SignatureParts<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub headers: Option<&'a str>,
     pub key_id: &'a str,
     pub signature: &'a str,
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub algorithm: Option<&'a str>,
+    // Unix timestamps from the `created` and `expires` parameters
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub created: Option<u64>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub expires: Option<u64>,
+    // A value the verifier can use to reject replays, see `verify_request_with_nonce_store`
+    #[cfg_attr(
+        feature = "serde",
+        serde(borrow, default, skip_serializing_if = "Option::is_none")
+    )]
+    pub nonce: Option<&'a str>,
+    // Passed through unchanged, for the signer's own use
+    #[cfg_attr(
+        feature = "serde",
+        serde(borrow, default, skip_serializing_if = "Option::is_none")
+    )]
+    pub opaque: Option<&'a str>,
 }
 
-pub fn parse_signature_parts<'a>(signature_string: &'a str) -> Option<SignatureParts<'a>> {
-    let mut headers = None;
-    let mut key_id = None;
-    let mut algorithm = None;
-    let mut signature = None;
-
-    for part in signature_string.split(',') {
-        let mut kv = part.splitn(2, '=');
+// `SignatureParts` that doesn't borrow from the header it was parsed from
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct OwnedSignatureParts {
+    pub headers: Option<String>,
+    pub key_id: String,
+    pub signature: String,
+    pub algorithm: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub created: Option<u64>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub expires: Option<u64>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub nonce: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub opaque: Option<String>,
+}
 
-        if let (Some(key), Some(value)) = (kv.next(), kv.next()) {
-            if !(value.starts_with('"') && value.ends_with('"')) {
-                return None;
-            }
+impl OwnedSignatureParts {
+    pub fn as_parts(&self) -> SignatureParts<'_> {
+        SignatureParts {
+            headers: self.headers.as_deref(),
+            key_id: &self.key_id,
+            signature: &self.signature,
+            algorithm: self.algorithm.as_deref(),
+            created: self.created,
+            expires: self.expires,
+            nonce: self.nonce.as_deref(),
+            opaque: self.opaque.as_deref(),
+        }
+    }
+}
 
-            let value = value.trim_start_matches('"').trim_end_matches('"');
+impl From<&SignatureParts<'_>> for OwnedSignatureParts {
+    fn from(parts: &SignatureParts<'_>) -> Self {
+        OwnedSignatureParts {
+            headers: parts.headers.map(str::to_owned),
+            key_id: parts.key_id.to_owned(),
+            signature: parts.signature.to_owned(),
+            algorithm: parts.algorithm.map(str::to_owned),
+            created: parts.created,
+            expires: parts.expires,
+            nonce: parts.nonce.map(str::to_owned),
+            opaque: parts.opaque.map(str::to_owned),
+        }
+    }
+}
 
-            match key {
-                "headers" => headers = Some(value),
-                "keyId" => key_id = Some(value),
-                "algorithm" => algorithm = Some(value),
-                "signature" => signature = Some(value),
-                _ => {}
-            }
-        } else {
-            return None;
+// Serializes back into the form accepted by `parse_signature_parts`
+impl fmt::Display for SignatureParts<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "keyId=\"{}\"", self.key_id)?;
+        if let Some(algorithm) = self.algorithm {
+            write!(f, ",algorithm=\"{}\"", algorithm)?;
+        }
+        if let Some(created) = self.created {
+            write!(f, ",created={}", created)?;
+        }
+        if let Some(expires) = self.expires {
+            write!(f, ",expires={}", expires)?;
+        }
+        if let Some(nonce) = self.nonce {
+            write!(f, ",nonce=\"{}\"", nonce)?;
+        }
+        if let Some(opaque) = self.opaque {
+            write!(f, ",opaque=\"{}\"", opaque)?;
         }
+        if let Some(headers) = self.headers {
+            write!(f, ",headers=\"{}\"", headers)?;
+        }
+        write!(f, ",signature=\"{}\"", self.signature)
     }
+}
 
-    if let (Some(k), Some(s)) = (key_id, signature) {
-        return Some(SignatureParts {
-            key_id: k,
-            signature: s,
-            headers,
-            algorithm,
-        });
-    } else {
-        return None;
+impl TryFrom<&SignatureParts<'_>> for HeaderValue {
+    type Error = HttpSigError;
+
+    fn try_from(parts: &SignatureParts<'_>) -> Result<Self, Self::Error> {
+        Ok(parts.to_string().parse()?)
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureLocation {
+    // `Signature: keyId="...",...`
+    SignatureHeader,
+    // `Authorization: Signature keyId="...",...`
+    AuthorizationHeader,
+}
+
 pub fn add_signature_header<T>(
     request: &mut http::Request<T>,
     key_id: &str,
     digest: MessageDigest,
     private_key: &PKeyRef<impl HasPrivate>,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
-    request.headers_mut().remove("signature");
+) -> Result<(), HttpSigError> {
+    add_signature_with_location(
+        request,
+        key_id,
+        digest,
+        private_key,
+        SignatureLocation::SignatureHeader,
+    )
+}
+
+// Like `add_signature_header`, loading the private key from PEM first
+pub fn add_signature_header_pem<T>(
+    request: &mut http::Request<T>,
+    key_id: &str,
+    digest: MessageDigest,
+    private_pem: &[u8],
+) -> Result<(), HttpSigError> {
+    let private_key = PKey::private_key_from_pem(private_pem)?;
+    add_signature_header(request, key_id, digest, &private_key)
+}
+
+// Like `add_signature_header`, loading a DER-encoded private key first
+pub fn add_signature_header_der<T>(
+    request: &mut http::Request<T>,
+    key_id: &str,
+    digest: MessageDigest,
+    private_der: &[u8],
+) -> Result<(), HttpSigError> {
+    let private_key = PKey::private_key_from_der(private_der)?;
+    add_signature_header(request, key_id, digest, &private_key)
+}
+
+pub fn add_signature_with_location<T>(
+    request: &mut http::Request<T>,
+    key_id: &str,
+    digest: MessageDigest,
+    private_key: &PKeyRef<impl HasPrivate>,
+    location: SignatureLocation,
+) -> Result<(), HttpSigError> {
+    let mut params = SignatureParams::new(key_id, digest);
+    params.location = location;
+
+    add_signature_header_with_params(request, &params, private_key)
+}
+
+pub fn add_signature_header_with_params<T>(
+    request: &mut http::Request<T>,
+    params: &SignatureParams,
+    private_key: &PKeyRef<impl HasPrivate>,
+) -> Result<(), HttpSigError> {
+    sign_request(
+        request,
+        params,
+        private_key,
+        &mut String::new(),
+        &mut Scratch::default(),
+    )
+}
+
+// Signs each request like `add_signature_header_with_params`, sharing the buffers for the signing
+// string and header between them. Stops at the first request that can't be signed, leaving the
+// ones before it signed.
+//
+// Each request still gets its own OpenSSL `Signer`: a `Signer` can't be reset, and one that has
+// already signed keeps the data it was given, so reusing it would sign every earlier request too.
+// Setting one up costs little next to the private key operation itself.
+pub fn sign_requests<T>(
+    requests: &mut [http::Request<T>],
+    params: &SignatureParams,
+    private_key: &PKeyRef<impl HasPrivate>,
+) -> Result<(), HttpSigError> {
+    let mut header = String::new();
+    let mut scratch = Scratch::default();
+    for request in requests {
+        sign_request(request, params, private_key, &mut header, &mut scratch)?;
+    }
+    Ok(())
+}
+
+// Replaces any existing signature in the location given by `params`. `header` is used to build
+// the header value.
+pub(crate) fn sign_request<T>(
+    request: &mut http::Request<T>,
+    params: &SignatureParams,
+    private_key: &PKeyRef<impl HasPrivate>,
+    header: &mut String,
+    scratch: &mut Scratch,
+) -> Result<(), HttpSigError> {
+    header.clear();
+    let header_name = match params.location {
+        SignatureLocation::SignatureHeader => SIGNATURE_HEADER,
+        SignatureLocation::AuthorizationHeader => {
+            header.push_str("Signature ");
+            AUTHORIZATION_HEADER
+        }
+    };
+
+    request.headers_mut().remove(header_name);
+    write_signature_header(
+        header,
+        &RequestHead::new(request.method(), request.uri(), request.headers()),
+        params,
+        private_key,
+        scratch,
+    )?;
+    request
+        .headers_mut()
+        .insert(header_name, HeaderValue::from_str(header)?);
 
-    let header = create_signature_header(&request, key_id, digest, private_key)?;
-    request.headers_mut().insert("signature", header.parse()?);
     Ok(())
 }
 
@@ -134,51 +1277,417 @@ pub fn create_signature_header<T>(
     key_id: &str,
     digest: MessageDigest,
     private_key: &PKeyRef<impl HasPrivate>,
-) -> Result<String, Box<dyn Error + Send + Sync>> {
-    let signature = compute_signature(&request, digest, &private_key)?;
-    let base64_signature = base64::encode(&signature);
+) -> Result<String, HttpSigError> {
+    create_signature_header_with_params(request, &SignatureParams::new(key_id, digest), private_key)
+}
 
+pub fn create_signature_header_with_params<T>(
+    request: &http::Request<T>,
+    params: &SignatureParams,
+    private_key: &PKeyRef<impl HasPrivate>,
+) -> Result<String, HttpSigError> {
     let mut output = String::new();
+    write_signature_header(
+        &mut output,
+        &RequestHead::new(request.method(), request.uri(), request.headers()),
+        params,
+        private_key,
+        &mut Scratch::default(),
+    )?;
+    Ok(output)
+}
 
-    write!(
+// Like `create_signature_header_with_params`, also returning the names in its `headers` parameter,
+// in the order they were signed
+pub fn create_signature_header_with_signed_headers<T>(
+    request: &http::Request<T>,
+    params: &SignatureParams,
+    private_key: &PKeyRef<impl HasPrivate>,
+) -> Result<(String, Vec<String>), HttpSigError> {
+    let mut output = String::new();
+    let headers_range = write_signature_header(
         &mut output,
-        "keyId=\"{}\",headers=\"(request-target)",
-        key_id
+        &RequestHead::new(request.method(), request.uri(), request.headers()),
+        params,
+        private_key,
+        &mut Scratch::default(),
     )?;
-    for (header_name, _) in request.headers() {
-        write!(&mut output, " {}", header_name.as_str())?;
+    let signed_headers = output[headers_range]
+        .split(' ')
+        .filter(|name| !name.is_empty())
+        .map(str::to_owned)
+        .collect();
+    Ok((output, signed_headers))
+}
+
+// Returns where the `headers` parameter's value is in `output`
+fn write_signature_header(
+    output: &mut String,
+    head: &RequestHead<'_>,
+    params: &SignatureParams,
+    private_key: &PKeyRef<impl HasPrivate>,
+    scratch: &mut Scratch,
+) -> Result<Range<usize>, HttpSigError> {
+    let present_headers;
+    let mut headers = params.headers.as_deref();
+    if let (Some(names), true) = (headers, params.skip_missing_headers) {
+        present_headers = names
+            .iter()
+            .filter(|name| match name.to_ascii_lowercase().as_str() {
+                "(request-target)" => head.request_line.is_some(),
+                "(nonce)" => params.nonce.is_some(),
+                name => head.headers.contains_key(name),
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        headers = Some(&present_headers);
     }
 
-    write!(&mut output, "\",signature=\"{}\"", base64_signature)?;
+    scratch.signature.clear();
+    compute_signature_for_headers(
+        &head.with_nonce(params.nonce.as_deref()),
+        headers,
+        params.digest,
+        params.rsa_pss_salt_len,
+        private_key,
+        &mut scratch.signing_string,
+        &mut scratch.signature,
+    )?;
 
-    Ok(output)
+    write!(output, "keyId=\"{}\",", params.signed_key_id())?;
+    if let Some(nonce) = &params.nonce {
+        write!(output, "nonce=\"{}\",", nonce)?;
+    }
+    output.push_str("headers=\"");
+    let headers_start = output.len();
+    match headers {
+        Some(headers) => {
+            for (i, header_name) in headers.iter().enumerate() {
+                if i > 0 {
+                    output.push(' ');
+                }
+                output.push_str(&header_name.to_ascii_lowercase());
+            }
+        }
+        None => {
+            let mut header_names = head.headers.keys().map(|name| name.as_str());
+            let first = match head.request_line {
+                Some(_) => Some("(request-target)"),
+                None => header_names.next(),
+            };
+            if let Some(first) = first {
+                output.push_str(first);
+            }
+            for header_name in header_names {
+                write!(output, " {}", header_name)?;
+            }
+        }
+    }
+
+    let headers_range = headers_start..output.len();
+
+    output.push_str("\",signature=\"");
+    params
+        .base64_alphabet
+        .engine()
+        .encode_string(&scratch.signature, output);
+    output.push('"');
+
+    Ok(headers_range)
 }
 
 pub fn compute_signature<T>(
     request: &http::Request<T>,
     digest: MessageDigest,
     private_key: &PKeyRef<impl HasPrivate>,
-) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
-    let mut signer = Signer::new(digest, private_key)?;
+) -> Result<Vec<u8>, HttpSigError> {
+    let mut signature = Vec::new();
+    compute_signature_into(request, digest, private_key, &mut signature)?;
+    Ok(signature)
+}
+
+// Writes the signature to `out` instead of allocating. Signatures from keys up to RSA-4096 are
+// produced on the stack, so writing into a preallocated buffer doesn't touch the heap.
+pub fn compute_signature_into<T, W: std::io::Write>(
+    request: &http::Request<T>,
+    digest: MessageDigest,
+    private_key: &PKeyRef<impl HasPrivate>,
+    out: &mut W,
+) -> Result<(), HttpSigError> {
+    compute_signature_for_headers(
+        &RequestHead::new(request.method(), request.uri(), request.headers()),
+        None,
+        digest,
+        None,
+        private_key,
+        &mut Vec::new(),
+        out,
+    )
+}
+
+// Like `compute_signature`, but with RSA-PSS padding. The key must be an RSA key.
+pub fn compute_signature_pss<T>(
+    request: &http::Request<T>,
+    digest: MessageDigest,
+    private_key: &PKeyRef<impl HasPrivate>,
+    salt_len: PssSaltLen,
+) -> Result<Vec<u8>, HttpSigError> {
+    if private_key.id() != Id::RSA {
+        return Err(HttpSigError::IncompatibleKey {
+            algorithm: "rsa-pss".to_owned(),
+        });
+    }
 
-    let mut payload_to_sign: Vec<u8> = Vec::new();
-    write!(
-        &mut payload_to_sign,
-        "(request-target): {} {}",
-        request.method().as_str().to_ascii_lowercase(),
-        request.uri()
+    let mut signature = Vec::new();
+    compute_signature_for_headers(
+        &RequestHead::new(request.method(), request.uri(), request.headers()),
+        None,
+        digest,
+        Some(salt_len),
+        private_key,
+        &mut Vec::new(),
+        &mut signature,
     )?;
+    Ok(signature)
+}
+
+// Same as `compute_signature`, but for callers that only have the request head, e.g. when the
+// body hasn't been produced yet. The HTTP version isn't part of the signing string.
+pub fn compute_signature_from_parts(
+    method: &http::Method,
+    uri: &http::Uri,
+    headers: &http::HeaderMap,
+    digest: MessageDigest,
+    private_key: &PKeyRef<impl HasPrivate>,
+) -> Result<Vec<u8>, HttpSigError> {
+    let mut signature = Vec::new();
+    compute_signature_for_headers(
+        &RequestHead::new(method, uri, headers),
+        None,
+        digest,
+        None,
+        private_key,
+        &mut Vec::new(),
+        &mut signature,
+    )?;
+    Ok(signature)
+}
+
+// The signature size of an RSA-4096 key
+const STACK_SIGNATURE_LEN: usize = 512;
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "compute_signature",
+        skip_all,
+        fields(signed_headers = tracing::field::Empty)
+    )
+)]
+fn compute_signature_for_headers<W: std::io::Write>(
+    head: &RequestHead<'_>,
+    headers: Option<&[String]>,
+    digest: MessageDigest,
+    rsa_pss_salt_len: Option<PssSaltLen>,
+    private_key: &PKeyRef<impl HasPrivate>,
+    payload_to_sign: &mut Vec<u8>,
+    out: &mut W,
+) -> Result<(), HttpSigError> {
+    #[cfg(feature = "tracing")]
+    {
+        let signed_headers = match headers {
+            Some(headers) => headers.join(" "),
+            None => head
+                .request_line
+                .map(|_| "(request-target)")
+                .into_iter()
+                .chain(head.headers.keys().map(|name| name.as_str()))
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        tracing::Span::current().record("signed_headers", signed_headers.as_str());
+    }
+
+    payload_to_sign.clear();
+    write_signing_string(payload_to_sign, head, headers)?;
+
+    let mut signer = Signer::new(digest, private_key)?;
+    if let Some(salt_len) = rsa_pss_salt_len {
+        if private_key.id() == Id::RSA {
+            signer.set_rsa_padding(Padding::PKCS1_PSS)?;
+            signer.set_rsa_pss_saltlen(salt_len.for_signing())?;
+        }
+    }
+    signer.update(payload_to_sign)?;
+
+    if signer.len()? <= STACK_SIGNATURE_LEN {
+        let mut buf = [0; STACK_SIGNATURE_LEN];
+        let len = signer.sign(&mut buf)?;
+        out.write_all(&buf[..len])?;
+    } else {
+        out.write_all(&signer.sign_to_vec()?)?;
+    }
+
+    Ok(())
+}
+
+// Builds the string to sign from the given headers, in order. If `headers` is `None`, the
+// `(request-target)` and every header in the request are used.
+pub(crate) fn write_signing_string(
+    payload_to_sign: &mut Vec<u8>,
+    head: &RequestHead<'_>,
+    headers: Option<&[String]>,
+) -> Result<(), HttpSigError> {
+    let headers = match headers {
+        Some(headers) => {
+            return write_signed_headers(payload_to_sign, head, headers.iter().map(String::as_str))
+        }
+        None => head.headers,
+    };
+
+    // Messages without a request line, i.e. responses, just sign their headers
+    let mut first = true;
+    if head.request_line.is_some() {
+        write_request_target(payload_to_sign, head)?;
+        first = false;
+    }
+
+    for header_name in headers.keys() {
+        if !first {
+            payload_to_sign.push(b'\n');
+        }
+        first = false;
 
-    for (header_name, header_value) in request.headers() {
         // HeaderName's `as_str` is guaranteed to be lowercase
-        write!(
-            &mut payload_to_sign,
-            "\n{}: {}",
-            header_name.as_str(),
-            header_value.to_str()?
-        )?;
+        write!(payload_to_sign, "{}: ", header_name.as_str())?;
+        write_header_values(payload_to_sign, headers, header_name.as_str());
+    }
+
+    Ok(())
+}
+
+fn write_request_target(out: &mut Vec<u8>, head: &RequestHead<'_>) -> Result<(), HttpSigError> {
+    match head.request_line {
+        Some((method, target)) => Ok(write!(
+            out,
+            "(request-target): {} {}",
+            method.as_str().to_ascii_lowercase(),
+            target
+        )?),
+        None => Err(HttpSigError::MissingHeader {
+            name: "(request-target)".to_owned(),
+        }),
+    }
+}
+
+// The parts of a request that go into the signing string
+#[derive(Clone, Copy)]
+pub(crate) struct RequestHead<'a> {
+    // The method and target, signed as `(request-target)`. The target is usually
+    // `request_target(uri)`, but can be overridden for requests that went through a proxy. Responses
+    // have neither.
+    pub request_line: Option<(&'a http::Method, &'a str)>,
+    pub headers: &'a http::HeaderMap,
+    // The signature's `nonce` parameter, signed as `(nonce)`
+    pub nonce: Option<&'a str>,
+    // The signature's `created` and `expires` parameters, signed as `(created)` and `(expires)`
+    pub created: Option<u64>,
+    pub expires: Option<u64>,
+}
+
+impl<'a> RequestHead<'a> {
+    pub fn new(method: &'a http::Method, uri: &'a http::Uri, headers: &'a http::HeaderMap) -> Self {
+        RequestHead {
+            request_line: Some((method, request_target(uri))),
+            headers,
+            nonce: None,
+            created: None,
+            expires: None,
+        }
+    }
+
+    pub fn with_nonce(self, nonce: Option<&'a str>) -> Self {
+        RequestHead { nonce, ..self }
+    }
+
+    // Adds the signature parameters that can be signed as pseudo-headers
+    pub fn with_params(self, parts: &SignatureParts<'a>, draft_version: DraftVersion) -> Self {
+        let head = self.with_nonce(parts.nonce);
+        match draft_version {
+            DraftVersion::Draft11 => head,
+            DraftVersion::Draft12 => RequestHead {
+                created: parts.created,
+                expires: parts.expires,
+                ..head
+            },
+        }
+    }
+}
+
+// Buffers used while signing or verifying. `ReusableSigner` and `ReusableVerifier` keep these
+// between requests instead of allocating new ones each time.
+#[derive(Default)]
+pub(crate) struct Scratch {
+    pub signing_string: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+// Buffers for `verify_signature_parts_with_buffers`, to be reused between requests
+#[derive(Default)]
+pub struct VerificationBuffers {
+    scratch: Scratch,
+}
+
+impl VerificationBuffers {
+    pub fn new() -> Self {
+        VerificationBuffers::default()
+    }
+}
+
+// The path and query of the request, which is what the draft calls the `:path` pseudo-header. This
+// drops the scheme and authority from absolute URIs, so clients that build requests with a full URL
+// sign the same value the server sees in the request line. Requests sent to a proxy in absolute
+// form (`GET http://example.com/path HTTP/1.1`) are also signed as `get /path`; to verify a signer
+// that used the whole absolute URI, pass it to `verify_request_with_target`.
+//
+// The `?` is only included with a query, as in `path + "?" + query`, so an empty query (`/foo?`)
+// is signed as `/foo`. The query isn't otherwise normalized, so repeated parameters (`?a=1&a=2`)
+// are signed in the order they were sent, and a request with them reordered won't verify.
+fn request_target(uri: &http::Uri) -> &str {
+    match uri.path_and_query() {
+        Some(path_and_query) if path_and_query.query() == Some("") => path_and_query.path(),
+        Some(path_and_query) => path_and_query.as_str(),
+        None => uri.path(),
+    }
+}
+
+// A header that appears more than once is signed as all of its values, in order, joined by `, `
+// https://tools.ietf.org/html/draft-cavage-http-signatures-10#section-2.3
+//
+// Values are copied byte for byte, since HTTP/1.1 allows bytes outside of ASCII (`obs-text`) and
+// the signer signed whatever was sent.
+pub(crate) fn write_header_values(
+    out: &mut Vec<u8>,
+    header_map: &http::HeaderMap,
+    header_name: &str,
+) {
+    for (i, value) in header_map.get_all(header_name).iter().enumerate() {
+        if i > 0 {
+            out.extend_from_slice(b", ");
+        }
+        out.extend_from_slice(trim_ows(value.as_bytes()));
     }
+}
 
-    signer.update(&payload_to_sign)?;
-    Ok(signer.sign_to_vec()?)
+// Leading and trailing optional whitespace (OWS) in a header value is omitted when signing
+// https://tools.ietf.org/html/draft-cavage-http-signatures-10#section-2.3
+// https://tools.ietf.org/html/rfc7230#section-3.2.4
+fn trim_ows(value: &[u8]) -> &[u8] {
+    let is_ows = |c: &u8| *c == b' ' || *c == b'\t';
+    let start = value.iter().position(|c| !is_ows(c)).unwrap_or(value.len());
+    let end = value
+        .iter()
+        .rposition(|c| !is_ows(c))
+        .map_or(start, |end| end + 1);
+    &value[start..end]
 }
@@ -0,0 +1,97 @@
+use crate::{HttpSigError, SignatureParts, VerificationResult};
+#[cfg(feature = "audit-log")]
+use std::io::Write;
+#[cfg(feature = "audit-log")]
+use std::sync::Mutex;
+#[cfg(feature = "audit-log")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Receives a record of each signature checked by `verify_request_with_options`, e.g. for an audit
+// log, with the error it was rejected for. A signature header that couldn't be parsed is logged
+// without a `result`, even if `OnParseError::TreatAsUnsigned` then ignores it. Requests without
+// a signature header at all aren't logged.
+pub trait SignatureLogger: Send + Sync {
+    fn log_verification(
+        &self,
+        request_id: Option<&str>,
+        result: Option<&VerificationResult>,
+        outcome: Result<(), &HttpSigError>,
+    );
+}
+
+// Writes each verification as a line of JSON:
+//
+//     {"timestamp":1388957500,"request_id":null,"key_id":"Test","signed_headers":["date"],
+//      "algorithm":null,"outcome":"invalid","failure_reason":"signature verification failed"}
+//
+// `timestamp` is in seconds since the Unix epoch. `key_id`, `signed_headers` and `algorithm` are
+// null for a signature header that couldn't be parsed. Errors writing a record are ignored, so that a
+// full disk doesn't stop requests from being verified.
+#[cfg(feature = "audit-log")]
+#[derive(Debug)]
+pub struct JsonSignatureLogger<W> {
+    writer: Mutex<W>,
+}
+
+#[cfg(feature = "audit-log")]
+impl<W: Write + Send> JsonSignatureLogger<W> {
+    pub fn new(writer: W) -> Self {
+        JsonSignatureLogger {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(feature = "audit-log")]
+impl<W: Write + Send> SignatureLogger for JsonSignatureLogger<W> {
+    fn log_verification(
+        &self,
+        request_id: Option<&str>,
+        result: Option<&VerificationResult>,
+        outcome: Result<(), &HttpSigError>,
+    ) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_secs());
+        let record = serde_json::json!({
+            "timestamp": timestamp,
+            "request_id": request_id,
+            "key_id": result.map(|result| &result.key_id),
+            "signed_headers": result.map(|result| &result.signed_headers),
+            "algorithm": result.and_then(|result| result.algorithm.as_ref()),
+            "outcome": if outcome.is_ok() { "valid" } else { "invalid" },
+            "failure_reason": outcome.err().map(ToString::to_string),
+        });
+
+        let mut line = record.to_string();
+        line.push('\n');
+        let mut writer = match self.writer.lock() {
+            Ok(writer) => writer,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let _ = writer.write_all(line.as_bytes());
+        let _ = writer.flush();
+    }
+}
+
+// Logs a signature checked by `verify_request_with_options`. `parts` is `None` if the signature
+// header couldn't be parsed.
+pub(crate) fn log_verification<T>(
+    logger: &dyn SignatureLogger,
+    request: &http::Request<T>,
+    parts: Option<&SignatureParts<'_>>,
+    outcome: Result<(), &HttpSigError>,
+) {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok());
+    let result = parts.map(VerificationResult::from);
+    logger.log_verification(request_id, result.as_ref(), outcome);
+}
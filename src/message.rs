@@ -0,0 +1,188 @@
+// Signing and verifying messages that aren't `http::Request`s, e.g. responses or other crates'
+// request types. The functions taking an `http::Request` read its headers in place, while these
+// copy the message's headers into a `HeaderMap` first.
+
+use crate::{
+    find_signature_in_headers, verify_signature_parts_for_target, write_signature_header,
+    HttpSigError, RequestHead, Scratch, SignatureLocation, SignatureParams, VerificationOptions,
+    AUTHORIZATION_HEADER, SIGNATURE_HEADER,
+};
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{HasPrivate, HasPublic, PKeyRef};
+
+pub trait SignableMessage {
+    // The request method, or `None` for responses, which can't sign `(request-target)`
+    fn method(&self) -> Option<&str>;
+
+    // The path and query, as sent in the request line
+    fn uri_target(&self) -> Option<&str>;
+
+    // Every header, in order. A header with several values appears once for each value.
+    fn headers_iter(&self) -> Box<dyn Iterator<Item = (&str, &[u8])> + '_>;
+
+    // Replaces any existing values of the header. The name can be in any case, and is an error if
+    // it isn't a valid header name.
+    fn set_header(&mut self, name: &'static str, value: &str) -> Result<(), HttpSigError>;
+
+    // The header's values, in order
+    fn header(&self, name: &str) -> Vec<&[u8]> {
+        self.headers_iter()
+            .filter(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+            .collect()
+    }
+}
+
+impl<T> SignableMessage for http::Request<T> {
+    fn method(&self) -> Option<&str> {
+        Some(self.method().as_str())
+    }
+
+    fn uri_target(&self) -> Option<&str> {
+        Some(crate::request_target(self.uri()))
+    }
+
+    fn headers_iter(&self) -> Box<dyn Iterator<Item = (&str, &[u8])> + '_> {
+        Box::new(
+            self.headers()
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_bytes())),
+        )
+    }
+
+    fn set_header(&mut self, name: &'static str, value: &str) -> Result<(), HttpSigError> {
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(http::Error::from)?;
+        self.headers_mut()
+            .insert(name, HeaderValue::from_str(value)?);
+        Ok(())
+    }
+}
+
+impl<T> SignableMessage for http::Response<T> {
+    fn method(&self) -> Option<&str> {
+        None
+    }
+
+    fn uri_target(&self) -> Option<&str> {
+        None
+    }
+
+    fn headers_iter(&self) -> Box<dyn Iterator<Item = (&str, &[u8])> + '_> {
+        Box::new(
+            self.headers()
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_bytes())),
+        )
+    }
+
+    fn set_header(&mut self, name: &'static str, value: &str) -> Result<(), HttpSigError> {
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(http::Error::from)?;
+        self.headers_mut()
+            .insert(name, HeaderValue::from_str(value)?);
+        Ok(())
+    }
+}
+
+// An owned copy of the parts of a message that go into the signing string
+struct MessageHead {
+    request_line: Option<(http::Method, String)>,
+    headers: HeaderMap,
+}
+
+impl MessageHead {
+    // `skip` is left out, like the signature header is removed before signing a request
+    fn new(message: &impl SignableMessage, skip: Option<&str>) -> Result<Self, HttpSigError> {
+        let request_line = match (message.method(), message.uri_target()) {
+            (Some(method), Some(target)) => Some((
+                http::Method::from_bytes(method.as_bytes()).map_err(http::Error::from)?,
+                target.to_owned(),
+            )),
+            _ => None,
+        };
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in message.headers_iter() {
+            if skip.is_some_and(|skip| name.eq_ignore_ascii_case(skip)) {
+                continue;
+            }
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes()).map_err(http::Error::from)?,
+                HeaderValue::from_bytes(value)?,
+            );
+        }
+
+        Ok(MessageHead {
+            request_line,
+            headers,
+        })
+    }
+
+    fn as_head(&self) -> RequestHead<'_> {
+        RequestHead {
+            request_line: self
+                .request_line
+                .as_ref()
+                .map(|(method, target)| (method, target.as_str())),
+            headers: &self.headers,
+            nonce: None,
+            created: None,
+            expires: None,
+        }
+    }
+}
+
+// Like `add_signature_header_with_params`. For responses, `params.headers` can't include
+// `(request-target)`, and if it's unset, all of the headers are signed.
+pub fn sign_message(
+    message: &mut impl SignableMessage,
+    params: &SignatureParams,
+    private_key: &PKeyRef<impl HasPrivate>,
+) -> Result<(), HttpSigError> {
+    let mut header = String::new();
+    let header_name = match params.location {
+        SignatureLocation::SignatureHeader => SIGNATURE_HEADER,
+        SignatureLocation::AuthorizationHeader => {
+            header.push_str("Signature ");
+            AUTHORIZATION_HEADER
+        }
+    };
+
+    let head = MessageHead::new(message, Some(header_name))?;
+    write_signature_header(
+        &mut header,
+        &head.as_head(),
+        params,
+        private_key,
+        &mut Scratch::default(),
+    )?;
+    message.set_header(header_name, &header)
+}
+
+pub fn verify_message(
+    message: &impl SignableMessage,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+) -> Result<bool, HttpSigError> {
+    verify_message_with_options(message, digest, public_key, &VerificationOptions::default())
+}
+
+pub fn verify_message_with_options(
+    message: &impl SignableMessage,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+    options: &VerificationOptions,
+) -> Result<bool, HttpSigError> {
+    let head = MessageHead::new(message, None)?;
+    match find_signature_in_headers(&head.headers) {
+        Ok(parts) => verify_signature_parts_for_target(
+            &head.as_head(),
+            &parts,
+            digest,
+            public_key,
+            options,
+            &mut Scratch::default(),
+        ),
+        Err(e) => options.unparsed_signature(e),
+    }
+}
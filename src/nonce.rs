@@ -0,0 +1,85 @@
+use crate::{
+    find_signature_in_request, verify_signature_parts_with_options, HttpSigError,
+    VerificationOptions,
+};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{HasPublic, PKeyRef};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, PoisonError};
+
+// Remembers the nonces of verified signatures, so that a signature can only be used once
+pub trait NonceStore {
+    // Records the nonce for `key_id`, returning `Ok(false)` if it was already recorded
+    fn insert(&self, key_id: &str, nonce: &str) -> Result<bool, HttpSigError>;
+}
+
+impl<S: NonceStore + ?Sized> NonceStore for &S {
+    fn insert(&self, key_id: &str, nonce: &str) -> Result<bool, HttpSigError> {
+        (**self).insert(key_id, nonce)
+    }
+}
+
+impl<S: NonceStore + ?Sized> NonceStore for Arc<S> {
+    fn insert(&self, key_id: &str, nonce: &str) -> Result<bool, HttpSigError> {
+        (**self).insert(key_id, nonce)
+    }
+}
+
+// Keeps every nonce it has seen, so it only suits tests and short-lived processes. Long-running
+// servers should use a store that forgets nonces once their signatures have expired.
+#[derive(Debug, Default)]
+pub struct InMemoryNonceStore {
+    seen: Mutex<HashSet<(String, String)>>,
+}
+
+impl InMemoryNonceStore {
+    pub fn new() -> Self {
+        InMemoryNonceStore::default()
+    }
+}
+
+impl NonceStore for InMemoryNonceStore {
+    fn insert(&self, key_id: &str, nonce: &str) -> Result<bool, HttpSigError> {
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(seen.insert((key_id.to_owned(), nonce.to_owned())))
+    }
+}
+
+// Like `verify_request_with_options`, but also requires the signature to cover a `(nonce)` that
+// `nonce_store` hasn't seen before. The nonce is only recorded once the signature is verified, so
+// forged requests can't use up nonces.
+pub fn verify_request_with_nonce_store<T>(
+    request: &http::Request<T>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+    options: &VerificationOptions,
+    nonce_store: &impl NonceStore,
+) -> Result<bool, HttpSigError> {
+    let parts = match find_signature_in_request(request) {
+        Ok(parts) => parts,
+        Err(e) => return options.unparsed_signature(e),
+    };
+
+    if !verify_signature_parts_with_options(request, &parts, digest, public_key, options)? {
+        return Ok(false);
+    }
+
+    // Without `(nonce)` in the signed headers, the nonce could be swapped for a fresh one
+    let nonce_signed = parts.headers.is_some_and(|headers| {
+        headers
+            .split(' ')
+            .any(|name| name.eq_ignore_ascii_case("(nonce)"))
+    });
+    let nonce = match parts.nonce {
+        Some(nonce) if nonce_signed => nonce,
+        _ => return Err(HttpSigError::NonceNotSigned),
+    };
+
+    if nonce_store.insert(parts.key_id, nonce)? {
+        Ok(true)
+    } else {
+        Err(HttpSigError::ReplayedNonce {
+            nonce: nonce.to_owned(),
+        })
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{HttpSigError, SignatureLocation, SignatureLogger};
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use openssl::hash::MessageDigest;
+use openssl::sign::RsaPssSaltlen;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Base64Alphabet {
+    #[default]
+    Standard,
+    UrlSafe,
+    StandardNoPad,
+    UrlSafeNoPad,
+}
+
+// Signatures are decoded whether or not they're padded, since signers don't agree on it
+const fn engine(alphabet: &alphabet::Alphabet, pad: bool) -> GeneralPurpose {
+    GeneralPurpose::new(
+        alphabet,
+        GeneralPurposeConfig::new()
+            .with_encode_padding(pad)
+            .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+    )
+}
+
+static STANDARD: GeneralPurpose = engine(&alphabet::STANDARD, true);
+static URL_SAFE: GeneralPurpose = engine(&alphabet::URL_SAFE, true);
+static STANDARD_NO_PAD: GeneralPurpose = engine(&alphabet::STANDARD, false);
+static URL_SAFE_NO_PAD: GeneralPurpose = engine(&alphabet::URL_SAFE, false);
+
+impl Base64Alphabet {
+    pub(crate) fn engine(self) -> &'static GeneralPurpose {
+        match self {
+            Base64Alphabet::Standard => &STANDARD,
+            Base64Alphabet::UrlSafe => &URL_SAFE,
+            Base64Alphabet::StandardNoPad => &STANDARD_NO_PAD,
+            Base64Alphabet::UrlSafeNoPad => &URL_SAFE_NO_PAD,
+        }
+    }
+}
+
+// The revision of the cavage draft that signers are expected to follow. Only verification is
+// affected; `headers` still defaults to `date` in both, as it did before draft 12.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DraftVersion {
+    // Drafts 10 and 11. `(created)` and `(expires)` aren't pseudo-headers, so signatures covering
+    // them don't verify.
+    // https://tools.ietf.org/html/draft-cavage-http-signatures-11#section-2.3
+    Draft11,
+    // Draft 12. `(created)` and `(expires)` are signed as the signature's `created` and `expires`
+    // parameters, and covering them with an `rsa-*`, `hmac-*` or `ecdsa-*` algorithm is an error.
+    // https://tools.ietf.org/html/draft-cavage-http-signatures-12#section-2.3
+    #[default]
+    Draft12,
+}
+
+// What verifying does with a signature header that can't be parsed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnParseError {
+    // Return `Ok(false)`, as for a request with no signature
+    #[default]
+    TreatAsUnsigned,
+    // Return the parse error, e.g. to find misconfigured clients
+    FailClosed,
+}
+
+// The salt length for RSA-PSS signatures. Both sides have to agree on it, and implementations
+// differ in what they use by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PssSaltLen {
+    // The length of the digest, e.g. 32 bytes for SHA-256
+    DigestLength,
+    // The largest salt that fits in the key
+    Maximum,
+    Bytes(u16),
+}
+
+impl PssSaltLen {
+    pub(crate) fn for_signing(self) -> RsaPssSaltlen {
+        match self {
+            PssSaltLen::DigestLength => RsaPssSaltlen::DIGEST_LENGTH,
+            PssSaltLen::Maximum => RsaPssSaltlen::MAXIMUM_LENGTH,
+            PssSaltLen::Bytes(len) => RsaPssSaltlen::custom(len.into()),
+        }
+    }
+
+    pub(crate) fn for_verifying(self) -> RsaPssSaltlen {
+        match self {
+            // When verifying, openssl treats `MAXIMUM_LENGTH` (-2) as "any length", while -3
+            // requires the salt to be exactly the maximum
+            PssSaltLen::Maximum => RsaPssSaltlen::custom(-3),
+            salt_len => salt_len.for_signing(),
+        }
+    }
+}
+
+// Options for creating a signature
+#[derive(Clone)]
+pub struct SignatureParams {
+    pub key_id: String,
+    pub digest: MessageDigest,
+    // The headers to sign, in order. If unset, `(request-target)` and all of the request's headers
+    // are signed.
+    pub headers: Option<Vec<String>>,
+    pub location: SignatureLocation,
+    pub base64_alphabet: Base64Alphabet,
+    // If set, RSA keys sign with PSS padding instead of PKCS #1 v1.5
+    pub rsa_pss_salt_len: Option<PssSaltLen>,
+    // Added as the `nonce` parameter, and signed if `headers` includes `(nonce)`
+    pub nonce: Option<String>,
+    // If set, headers in `headers` that aren't on the request are left out of the signature
+    // instead of being an error
+    pub skip_missing_headers: bool,
+    // If set, the signature's `keyId` is derived from `key_id`. See `with_key_id_transform`.
+    pub key_id_transform: Option<KeyIdTransform>,
+}
+
+pub type KeyIdTransform = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+impl SignatureParams {
+    pub fn new(key_id: impl Into<String>, digest: MessageDigest) -> Self {
+        SignatureParams {
+            key_id: key_id.into(),
+            digest,
+            headers: None,
+            location: SignatureLocation::SignatureHeader,
+            base64_alphabet: Base64Alphabet::default(),
+            rsa_pss_salt_len: None,
+            nonce: None,
+            skip_missing_headers: false,
+            key_id_transform: None,
+        }
+    }
+
+    // Derives the signature's `keyId` from `key_id` when signing, e.g. to sign with an
+    // ActivityPub actor's URL and send `<actor>#main-key`
+    pub fn with_key_id_transform(
+        mut self,
+        transform: impl Fn(&str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.key_id_transform = Some(Arc::new(transform));
+        self
+    }
+
+    // The `keyId` to put in the signature
+    pub(crate) fn signed_key_id(&self) -> Cow<'_, str> {
+        match &self.key_id_transform {
+            Some(transform) => Cow::Owned(transform(&self.key_id)),
+            None => Cow::Borrowed(&self.key_id),
+        }
+    }
+
+    // Signs whichever of `headers` are present on the request, e.g.
+    // `["(request-target)", "host", "date", "digest", "content-type"]` for requests that may not
+    // have a body. The `headers` parameter lists the ones that were actually signed.
+    pub fn sign_present_from(mut self, headers: &[&str]) -> Self {
+        self.headers = Some(headers.iter().map(|&name| name.to_owned()).collect());
+        self.skip_missing_headers = true;
+        self
+    }
+}
+
+impl fmt::Debug for SignatureParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SignatureParams")
+            .field("key_id", &self.key_id)
+            .field("digest", &self.digest.type_())
+            .field("headers", &self.headers)
+            .field("location", &self.location)
+            .field("base64_alphabet", &self.base64_alphabet)
+            .field("rsa_pss_salt_len", &self.rsa_pss_salt_len)
+            .field("nonce", &self.nonce)
+            .field("skip_missing_headers", &self.skip_missing_headers)
+            .field("key_id_transform", &self.key_id_transform.is_some())
+            .finish()
+    }
+}
+
+// Options for verifying a signature
+#[derive(Clone)]
+pub struct VerificationOptions {
+    // If unset, the signature is decoded as standard base64, falling back to URL-safe base64
+    pub base64_alphabet: Option<Base64Alphabet>,
+    // If set, the `Date` header must be signed and within this long of the current time. A draft 12
+    // signature covering `(created)` can have its `created` parameter checked instead.
+    pub max_clock_skew: Option<Duration>,
+    // If set, signatures older than this are rejected. The age comes from the `Date` header, or the
+    // `created` parameter of a draft 12 signature covering `(created)`, and signatures covering
+    // neither are rejected. Signatures with an `expires` parameter in the past are always rejected.
+    pub max_signature_age: Option<Duration>,
+    // The time to check the `Date` header against. Defaults to the system clock.
+    pub now: Option<SystemTime>,
+    // Signatures covering more headers than this are rejected before doing any work, so that a
+    // huge `headers` parameter can't be used to tie up the verifier. Defaults to 64.
+    pub max_signed_headers: usize,
+    // If set, RSA signatures are verified with PSS padding instead of PKCS #1 v1.5
+    pub rsa_pss_salt_len: Option<PssSaltLen>,
+    // The host the request was received on. If set, the signature must cover the `host` header
+    // and it must match (ignoring case), so a signature for one host can't be replayed to another.
+    pub expected_host: Option<String>,
+    pub draft_version: DraftVersion,
+    // Whether a malformed signature header is an error. Requests with no signature at all return
+    // `Ok(false)` either way.
+    pub on_parse_error: OnParseError,
+    // If set, `verify_request_with_options` reports each signature it checks to this logger
+    pub logger: Option<Arc<dyn SignatureLogger>>,
+    // If set, an RSA signature whose length doesn't match the key is reported as
+    // `SignatureLengthMismatch` instead of failing verification, to help diagnose signers using
+    // the wrong key. Off by default.
+    pub check_signature_length: bool,
+}
+
+impl Default for VerificationOptions {
+    fn default() -> Self {
+        VerificationOptions {
+            base64_alphabet: None,
+            max_clock_skew: None,
+            max_signature_age: None,
+            now: None,
+            max_signed_headers: 64,
+            rsa_pss_salt_len: None,
+            expected_host: None,
+            draft_version: DraftVersion::default(),
+            on_parse_error: OnParseError::default(),
+            logger: None,
+            check_signature_length: false,
+        }
+    }
+}
+
+impl VerificationOptions {
+    // The result of verifying a request whose signature couldn't be found or parsed
+    pub(crate) fn unparsed_signature(&self, e: HttpSigError) -> Result<bool, HttpSigError> {
+        match e {
+            HttpSigError::MissingSignature => Ok(false),
+            HttpSigError::InvalidSignatureHeader | HttpSigError::Parse(_)
+                if self.on_parse_error == OnParseError::TreatAsUnsigned =>
+            {
+                Ok(false)
+            }
+            e => Err(e),
+        }
+    }
+}
+
+impl fmt::Debug for VerificationOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VerificationOptions")
+            .field("base64_alphabet", &self.base64_alphabet)
+            .field("max_clock_skew", &self.max_clock_skew)
+            .field("max_signature_age", &self.max_signature_age)
+            .field("now", &self.now)
+            .field("max_signed_headers", &self.max_signed_headers)
+            .field("rsa_pss_salt_len", &self.rsa_pss_salt_len)
+            .field("expected_host", &self.expected_host)
+            .field("draft_version", &self.draft_version)
+            .field("on_parse_error", &self.on_parse_error)
+            .field("logger", &self.logger.is_some())
+            .field("check_signature_length", &self.check_signature_length)
+            .finish()
+    }
+}
@@ -0,0 +1,273 @@
+// Parsing of the `Signature` header (and the parameters of `Authorization: Signature`), e.g.
+// `keyId="Test",algorithm="rsa-sha256",headers="(request-target) host date",signature="..."`
+// https://tools.ietf.org/html/draft-cavage-http-signatures-10#section-2.1
+
+use crate::{Base64Alphabet, SignatureParts};
+use base64::Engine as _;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    // A quoted value with no closing quote, e.g. `keyId="Test`
+    UnterminatedQuote,
+    // A parameter that should be a quoted string isn't, e.g. `keyId=Test`
+    UnquotedValue {
+        key: String,
+    },
+    // `created` or `expires` isn't a Unix timestamp
+    InvalidInteger {
+        key: String,
+    },
+    // A parameter with no `=`
+    MissingEquals {
+        key: String,
+    },
+    MissingKeyId,
+    MissingSignature,
+    // `signature` isn't valid base64. Only returned by `decode_signature`, since the base64
+    // alphabet isn't known until verifying.
+    InvalidBase64 {
+        field: String,
+        source: base64::DecodeError,
+    },
+    // A parameter with nothing before the `=`, e.g. `="Test"`
+    EmptyFieldName,
+    // The same parameter appears more than once, so it's ambiguous which one was meant
+    DuplicateField {
+        key: String,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnterminatedQuote => {
+                write!(f, "signature header has an unterminated quote")
+            }
+            ParseError::UnquotedValue { key } => {
+                write!(f, "signature header field '{}' is not quoted", key)
+            }
+            ParseError::InvalidInteger { key } => {
+                write!(f, "signature header field '{}' is not an integer", key)
+            }
+            ParseError::MissingEquals { key } => {
+                write!(f, "signature header field '{}' has no value", key)
+            }
+            ParseError::MissingKeyId => {
+                write!(f, "signature header is missing required 'keyId' field")
+            }
+            ParseError::MissingSignature => {
+                write!(f, "signature header is missing required 'signature' field")
+            }
+            ParseError::InvalidBase64 { field, source } => {
+                write!(
+                    f,
+                    "signature header field '{}' is not base64: {}",
+                    field, source
+                )
+            }
+            ParseError::EmptyFieldName => write!(f, "signature header has an empty field name"),
+            ParseError::DuplicateField { key } => {
+                write!(f, "signature header field '{}' appears more than once", key)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::InvalidBase64 { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Unknown parameters are ignored. Some implementations separate parameters with `, ` rather than
+// `,`, so whitespace around each parameter is allowed, and some end the header with `;` or
+// `;`-delimited parameters, which are ignored.
+pub fn parse_signature_parts(signature_string: &str) -> Result<SignatureParts<'_>, ParseError> {
+    let mut headers = None;
+    let mut key_id = None;
+    let mut algorithm = None;
+    let mut signature = None;
+    let mut created = None;
+    let mut expires = None;
+    let mut nonce = None;
+    let mut opaque = None;
+
+    let signature_string = without_trailing_parameters(signature_string);
+    if !signature_string.matches('"').count().is_multiple_of(2) {
+        return Err(ParseError::UnterminatedQuote);
+    }
+
+    for part in split_unquoted(signature_string, ',').map(str::trim) {
+        let (key, value) = parse_field(part)?;
+
+        let duplicate = match key {
+            "created" => created.replace(parse_integer(key, value)?).is_some(),
+            "expires" => expires.replace(parse_integer(key, value)?).is_some(),
+            _ => {
+                let field = match key {
+                    "headers" => &mut headers,
+                    "keyId" => &mut key_id,
+                    "algorithm" => &mut algorithm,
+                    "signature" => &mut signature,
+                    "nonce" => &mut nonce,
+                    "opaque" => &mut opaque,
+                    _ => continue,
+                };
+                field.replace(unquote(key, value)?).is_some()
+            }
+        };
+        if duplicate {
+            return Err(ParseError::DuplicateField {
+                key: key.to_owned(),
+            });
+        }
+    }
+
+    Ok(SignatureParts {
+        key_id: key_id.ok_or(ParseError::MissingKeyId)?,
+        signature: signature.ok_or(ParseError::MissingSignature)?,
+        headers,
+        algorithm,
+        created,
+        expires,
+        nonce,
+        opaque,
+    })
+}
+
+// The metadata of a signature, without the signature itself
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignaturePreview {
+    pub key_id: String,
+    pub algorithm: Option<String>,
+    // As listed in `headers`, or just `date` if it's absent
+    pub covered_headers: Vec<String>,
+    pub created: Option<u64>,
+    pub expires: Option<u64>,
+}
+
+impl From<&SignatureParts<'_>> for SignaturePreview {
+    fn from(parts: &SignatureParts<'_>) -> Self {
+        SignaturePreview {
+            key_id: parts.key_id.to_owned(),
+            algorithm: parts.algorithm.map(str::to_owned),
+            covered_headers: parts
+                .headers
+                .unwrap_or("date")
+                .split(' ')
+                .map(str::to_owned)
+                .collect(),
+            created: parts.created,
+            expires: parts.expires,
+        }
+    }
+}
+
+// Finds `keyId` without validating the rest of the header. If there's more than one, the first is
+// returned.
+pub fn key_id(signature_string: &str) -> Option<&str> {
+    split_unquoted(signature_string, ',')
+        .find_map(|part| part.trim().strip_prefix("keyId="))
+        .and_then(|value| value.strip_prefix('"')?.strip_suffix('"'))
+}
+
+// Decodes the `signature` field, accepting both the standard and URL-safe base64 alphabets
+pub fn decode_signature(parts: &SignatureParts<'_>) -> Result<Vec<u8>, ParseError> {
+    let decode = |alphabet: Base64Alphabet| alphabet.engine().decode(parts.signature);
+    decode(Base64Alphabet::Standard)
+        .or_else(|e| decode(Base64Alphabet::UrlSafe).map_err(|_| e))
+        .map_err(|source| ParseError::InvalidBase64 {
+            field: "signature".to_owned(),
+            source,
+        })
+}
+
+// Cuts the header at the first `;` outside of a quoted value
+fn without_trailing_parameters(signature_string: &str) -> &str {
+    match split_unquoted(signature_string, ';').next() {
+        Some(parameters) if parameters.len() < signature_string.len() => parameters.trim_end(),
+        _ => signature_string,
+    }
+}
+
+// Splits at each `separator` outside of a quoted value, so a `keyId` URL can contain a `,` or `;`
+fn split_unquoted(s: &str, separator: char) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    s.split(move |c| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == separator && !quoted
+    })
+}
+
+// Whether two signature headers hold the same signature, however they were serialized. Only the
+// `keyId`, the set of signed headers and the decoded signature are compared, so the order of the
+// parameters and of `headers`, whitespace, `algorithm` and the base64 alphabet don't matter.
+// Headers that don't parse aren't equivalent to anything.
+pub fn signature_headers_equivalent(a: &str, b: &str) -> bool {
+    let meaningful_fields = |header| {
+        let parts = parse_signature_parts(header).ok()?;
+        let mut headers = parts
+            .headers
+            .unwrap_or("date")
+            .split(' ')
+            .map(str::to_ascii_lowercase)
+            .collect::<Vec<_>>();
+        headers.sort_unstable();
+        headers.dedup();
+        Some((parts.key_id, headers, decode_signature(&parts).ok()?))
+    };
+
+    match (meaningful_fields(a), meaningful_fields(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+// Splits `key=value` into the key and the value, which may still be quoted
+fn parse_field(part: &str) -> Result<(&str, &str), ParseError> {
+    let mut kv = part.splitn(2, '=');
+    let (key, value) = match (kv.next(), kv.next()) {
+        (Some(key), Some(value)) => (key, value),
+        _ => {
+            return Err(ParseError::MissingEquals {
+                key: part.to_owned(),
+            })
+        }
+    };
+
+    if key.is_empty() {
+        return Err(ParseError::EmptyFieldName);
+    }
+
+    Ok((key, value))
+}
+
+fn unquote<'a>(key: &str, value: &'a str) -> Result<&'a str, ParseError> {
+    match value.strip_prefix('"') {
+        Some(value) => value.strip_suffix('"').ok_or(ParseError::UnterminatedQuote),
+        None => Err(ParseError::UnquotedValue {
+            key: key.to_owned(),
+        }),
+    }
+}
+
+// `created` and `expires` are unquoted, but some implementations quote them anyway
+// https://tools.ietf.org/html/draft-cavage-http-signatures-12#section-2.1.4
+fn parse_integer(key: &str, value: &str) -> Result<u64, ParseError> {
+    let value = match value.strip_prefix('"') {
+        Some(value) => value
+            .strip_suffix('"')
+            .ok_or(ParseError::UnterminatedQuote)?,
+        None => value,
+    };
+    value.parse().map_err(|_| ParseError::InvalidInteger {
+        key: key.to_owned(),
+    })
+}
@@ -0,0 +1,420 @@
+use crate::{
+    find_signature_in_request, verify_request_strict, verify_signature_parts_with_options,
+    HttpSigError, SignatureParts, VerificationError, VerificationOptions, VerificationResult,
+};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{HasPublic, PKeyRef};
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// A server's rules for which signatures it accepts, checked once a signature has verified
+pub trait SignaturePolicy {
+    fn check(&self, signature: &PolicyContext<'_>) -> Result<(), PolicyViolation>;
+}
+
+impl<P: SignaturePolicy + ?Sized> SignaturePolicy for &P {
+    fn check(&self, signature: &PolicyContext<'_>) -> Result<(), PolicyViolation> {
+        (**self).check(signature)
+    }
+}
+
+impl<P: SignaturePolicy + ?Sized> SignaturePolicy for Box<P> {
+    fn check(&self, signature: &PolicyContext<'_>) -> Result<(), PolicyViolation> {
+        (**self).check(signature)
+    }
+}
+
+// A verified signature, and the request it was on
+#[derive(Debug)]
+pub struct PolicyContext<'a> {
+    pub result: &'a VerificationResult,
+    pub created: Option<u64>,
+    pub expires: Option<u64>,
+    pub headers: &'a http::HeaderMap,
+    // `VerificationOptions::now`, or the system clock
+    pub now: SystemTime,
+}
+
+// Why a policy rejected a signature
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PolicyViolation {
+    // `created` is the signature's `created` parameter if it covers `(created)`, or else the
+    // request's `Date`
+    SignatureTooOld {
+        created: SystemTime,
+        max_age: Duration,
+    },
+    // The signature covers neither `(created)` nor a valid `Date` header
+    MissingTimestamp,
+    // A header (or pseudo-header like `(request-target)`) that must be signed isn't
+    MissingRequiredHeader(String),
+    DisallowedAlgorithm {
+        algorithm: String,
+    },
+    // The signature has no `algorithm` parameter, and the policy requires one
+    MissingAlgorithm,
+    InvalidKeyIdPrefix {
+        key_id: String,
+        expected_prefix: String,
+    },
+    // Every violation found by a `PolicyChain` in `collect_all` mode, when there's more than one
+    Multiple(Vec<PolicyViolation>),
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PolicyViolation::SignatureTooOld { created, max_age } => {
+                HttpSigError::SignatureTooOld {
+                    created: *created,
+                    max_age: *max_age,
+                }
+                .fmt(f)
+            }
+            PolicyViolation::MissingTimestamp => write!(
+                f,
+                "signature covers neither '(created)' nor a valid date header"
+            ),
+            PolicyViolation::MissingRequiredHeader(name) => {
+                write!(f, "signature doesn't cover required header '{}'", name)
+            }
+            PolicyViolation::DisallowedAlgorithm { algorithm } => {
+                write!(f, "signature algorithm '{}' is not allowed", algorithm)
+            }
+            PolicyViolation::MissingAlgorithm => {
+                write!(f, "signature has no 'algorithm' parameter")
+            }
+            PolicyViolation::InvalidKeyIdPrefix {
+                key_id,
+                expected_prefix,
+            } => write!(
+                f,
+                "key ID '{}' doesn't start with '{}'",
+                key_id, expected_prefix
+            ),
+            PolicyViolation::Multiple(violations) => {
+                for (i, violation) in violations.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("; ")?;
+                    }
+                    violation.fmt(f)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for PolicyViolation {}
+
+// Like `verify_request_strict`, and then checks the signature against `policy`
+pub fn verify_request_with_signature_policy<T>(
+    request: &http::Request<T>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+    options: &VerificationOptions,
+    policy: &impl SignaturePolicy,
+) -> Result<VerificationResult, VerificationError> {
+    verify_request_strict(request, digest, public_key, options)?;
+
+    let parts = find_signature_in_request(request)?;
+    let result = VerificationResult::from(&parts);
+    policy.check(&PolicyContext {
+        result: &result,
+        created: parts.created,
+        expires: parts.expires,
+        headers: request.headers(),
+        now: options.now.unwrap_or_else(SystemTime::now),
+    })?;
+    Ok(result)
+}
+
+// What a signature covers, for `verify_request_with_policy` to decide whether to accept it
+#[derive(Clone, Debug)]
+pub struct SignedHeaders<'a> {
+    pub key_id: &'a str,
+    pub algorithm: Option<&'a str>,
+    // Lowercased, in the order they were signed. Pseudo-headers like `(request-target)` included.
+    pub headers: Vec<String>,
+    pub created: Option<u64>,
+    pub expires: Option<u64>,
+}
+
+impl<'a> SignedHeaders<'a> {
+    fn new(parts: &SignatureParts<'a>) -> Self {
+        SignedHeaders {
+            key_id: parts.key_id,
+            algorithm: parts.algorithm,
+            headers: VerificationResult::from(parts).signed_headers,
+            created: parts.created,
+            expires: parts.expires,
+        }
+    }
+
+    // Whether the signature covers `name`, ignoring case
+    pub fn contains(&self, name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|header| header.eq_ignore_ascii_case(name))
+    }
+}
+
+// Like `verify_request`, but first asks `policy` whether the signature covers enough to be
+// accepted. Signatures it rejects return `Ok(false)` without being checked.
+//
+//     httpsig::verify_request_with_policy(&request, digest, &public_key, |signed| {
+//         signed.contains("digest") && signed.algorithm != Some("rsa-sha1")
+//     })
+pub fn verify_request_with_policy<T>(
+    request: &http::Request<T>,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+    policy: impl Fn(&SignedHeaders<'_>) -> bool,
+) -> Result<bool, HttpSigError> {
+    let parts = match find_signature_in_request(request) {
+        Ok(parts) => parts,
+        Err(HttpSigError::MissingSignature)
+        | Err(HttpSigError::InvalidSignatureHeader)
+        | Err(HttpSigError::Parse(_)) => return Ok(false),
+        Err(e) => return Err(e),
+    };
+
+    if !policy(&SignedHeaders::new(&parts)) {
+        return Ok(false);
+    }
+    verify_signature_parts_with_options(
+        request,
+        &parts,
+        digest,
+        public_key,
+        &VerificationOptions::default(),
+    )
+}
+
+// Runs several policies in order. By default the first violation is returned; with `collect_all`,
+// every policy runs so that a client can be told about all of the problems at once.
+pub struct PolicyChain {
+    policies: Vec<Box<dyn SignaturePolicy>>,
+    collect_all: bool,
+}
+
+impl PolicyChain {
+    pub fn new(policies: Vec<Box<dyn SignaturePolicy>>) -> Self {
+        PolicyChain {
+            policies,
+            collect_all: false,
+        }
+    }
+
+    // Stop at the first violation (the default)
+    pub fn fail_fast(mut self) -> Self {
+        self.collect_all = false;
+        self
+    }
+
+    // Run every policy, returning `PolicyViolation::Multiple` if more than one fails
+    pub fn collect_all(mut self) -> Self {
+        self.collect_all = true;
+        self
+    }
+}
+
+impl fmt::Debug for PolicyChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PolicyChain")
+            .field("policies", &self.policies.len())
+            .field("collect_all", &self.collect_all)
+            .finish()
+    }
+}
+
+impl SignaturePolicy for PolicyChain {
+    fn check(&self, signature: &PolicyContext<'_>) -> Result<(), PolicyViolation> {
+        if !self.collect_all {
+            return self
+                .policies
+                .iter()
+                .try_for_each(|policy| policy.check(signature));
+        }
+
+        let mut violations = Vec::new();
+        for policy in &self.policies {
+            match policy.check(signature) {
+                Ok(()) => {}
+                // Nested chains are flattened
+                Err(PolicyViolation::Multiple(nested)) => violations.extend(nested),
+                Err(violation) => violations.push(violation),
+            }
+        }
+
+        match violations.len() {
+            0 => Ok(()),
+            1 => Err(violations.remove(0)),
+            _ => Err(PolicyViolation::Multiple(violations)),
+        }
+    }
+}
+
+// Rejects signatures created more than `max_age` ago. Signatures that don't cover `(created)` are
+// dated by the `Date` header, which they must cover instead. An unsigned `created` parameter is
+// ignored, since anyone replaying the request could change it.
+#[derive(Clone, Copy, Debug)]
+pub struct MaxAgePolicy {
+    max_age: Duration,
+}
+
+impl MaxAgePolicy {
+    pub fn new(max_age: Duration) -> Self {
+        MaxAgePolicy { max_age }
+    }
+}
+
+impl SignaturePolicy for MaxAgePolicy {
+    fn check(&self, signature: &PolicyContext<'_>) -> Result<(), PolicyViolation> {
+        let created = signed_created(signature)
+            .or_else(|| signed_date(signature))
+            .ok_or(PolicyViolation::MissingTimestamp)?;
+
+        // Dates in the future are left to `VerificationOptions::max_clock_skew`
+        match signature.now.duration_since(created) {
+            Ok(age) if age > self.max_age => Err(PolicyViolation::SignatureTooOld {
+                created,
+                max_age: self.max_age,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Rejects signatures that don't cover all of the given headers, e.g. signatures covering only the
+// default `date`, which could be replayed against any other request made at the same time
+#[derive(Clone, Debug)]
+pub struct RequiredHeadersPolicy {
+    headers: Vec<String>,
+}
+
+impl RequiredHeadersPolicy {
+    pub fn new<S: AsRef<str>>(headers: impl IntoIterator<Item = S>) -> Self {
+        RequiredHeadersPolicy {
+            headers: headers
+                .into_iter()
+                .map(|name| name.as_ref().to_ascii_lowercase())
+                .collect(),
+        }
+    }
+}
+
+impl SignaturePolicy for RequiredHeadersPolicy {
+    fn check(&self, signature: &PolicyContext<'_>) -> Result<(), PolicyViolation> {
+        let signed_headers = &signature.result.signed_headers;
+        match self
+            .headers
+            .iter()
+            .find(|name| !signed_headers.contains(name))
+        {
+            Some(name) => Err(PolicyViolation::MissingRequiredHeader(name.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+// Rejects signatures whose `algorithm` isn't in the allowlist, e.g. to stop accepting `rsa-sha1`.
+// Signatures without an `algorithm` are rejected unless `allow_missing` is used, since the
+// verifier then decides the algorithm from the key.
+#[derive(Clone, Debug)]
+pub struct AllowedAlgorithmsPolicy {
+    algorithms: Vec<String>,
+    allow_missing: bool,
+}
+
+impl AllowedAlgorithmsPolicy {
+    pub fn new<S: AsRef<str>>(algorithms: impl IntoIterator<Item = S>) -> Self {
+        AllowedAlgorithmsPolicy {
+            algorithms: algorithms
+                .into_iter()
+                .map(|algorithm| algorithm.as_ref().to_owned())
+                .collect(),
+            allow_missing: false,
+        }
+    }
+
+    // Whether to accept signatures with no `algorithm` parameter
+    pub fn allow_missing(mut self, allow_missing: bool) -> Self {
+        self.allow_missing = allow_missing;
+        self
+    }
+}
+
+impl SignaturePolicy for AllowedAlgorithmsPolicy {
+    fn check(&self, signature: &PolicyContext<'_>) -> Result<(), PolicyViolation> {
+        match &signature.result.algorithm {
+            Some(algorithm)
+                if self
+                    .algorithms
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(algorithm)) =>
+            {
+                Ok(())
+            }
+            Some(algorithm) => Err(PolicyViolation::DisallowedAlgorithm {
+                algorithm: algorithm.clone(),
+            }),
+            None if self.allow_missing => Ok(()),
+            None => Err(PolicyViolation::MissingAlgorithm),
+        }
+    }
+}
+
+// Rejects signatures whose `keyId` doesn't start with `prefix`, e.g. to keep each tenant of a
+// multi-tenant service to its own keys (`tenant-123/signing-key-v1`). The comparison is
+// case-sensitive.
+#[derive(Clone, Debug)]
+pub struct RequiredKeyIdPrefixPolicy {
+    prefix: String,
+}
+
+impl RequiredKeyIdPrefixPolicy {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        RequiredKeyIdPrefixPolicy {
+            prefix: prefix.into(),
+        }
+    }
+}
+
+impl SignaturePolicy for RequiredKeyIdPrefixPolicy {
+    fn check(&self, signature: &PolicyContext<'_>) -> Result<(), PolicyViolation> {
+        let key_id = &signature.result.key_id;
+        if key_id.starts_with(&self.prefix) {
+            Ok(())
+        } else {
+            Err(PolicyViolation::InvalidKeyIdPrefix {
+                key_id: key_id.clone(),
+                expected_prefix: self.prefix.clone(),
+            })
+        }
+    }
+}
+
+fn signs(signature: &PolicyContext<'_>, header: &str) -> bool {
+    signature
+        .result
+        .signed_headers
+        .iter()
+        .any(|name| name == header)
+}
+
+fn signed_created(signature: &PolicyContext<'_>) -> Option<SystemTime> {
+    if !signs(signature, "(created)") {
+        return None;
+    }
+    Some(UNIX_EPOCH + Duration::from_secs(signature.created?))
+}
+
+fn signed_date(signature: &PolicyContext<'_>) -> Option<SystemTime> {
+    if !signs(signature, "date") {
+        return None;
+    }
+    let date = signature.headers.get("date")?.to_str().ok()?;
+    httpdate::parse_http_date(date).ok()
+}
@@ -0,0 +1,111 @@
+use crate::HttpSigError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineFolding {
+    // Folded header lines are a parse error, as they are in `httparse`
+    Reject,
+    // Lines starting with whitespace continue the previous header (obsolete line folding, see
+    // https://tools.ietf.org/html/rfc7230#section-3.2.4) and are joined with a single space
+    Unfold,
+}
+
+// Parses a raw HTTP/1.x request into an `http::Request` whose body is everything after the headers
+pub fn parse_request(
+    buf: &[u8],
+    folding: LineFolding,
+) -> Result<http::Request<Vec<u8>>, HttpSigError> {
+    let unfolded;
+    let buf = match folding {
+        LineFolding::Reject => buf,
+        LineFolding::Unfold => {
+            unfolded = unfold_headers(buf);
+            &unfolded[..]
+        }
+    };
+
+    let mut headers = [httparse::EMPTY_HEADER; 64];
+    let mut req = httparse::Request::new(&mut headers);
+    let bytes_parsed = match req.parse(buf)? {
+        httparse::Status::Complete(n) => n,
+        httparse::Status::Partial => return Err(HttpSigError::IncompleteRequest),
+    };
+
+    let version = match req.version {
+        Some(0) => http::Version::HTTP_10,
+        _ => http::Version::HTTP_11,
+    };
+
+    let mut builder = http::Request::builder();
+    builder
+        .method(req.method.unwrap_or_default())
+        .uri(req.path.unwrap_or_default())
+        .version(version);
+
+    for header in req.headers.iter() {
+        builder.header(header.name, header.value);
+    }
+
+    Ok(builder.body(buf[bytes_parsed..].to_vec())?)
+}
+
+// Copies a request head from a framework using a different version of the `http` crate
+#[cfg(any(feature = "warp", feature = "actix-web", feature = "axum"))]
+pub(crate) fn request_head<'a>(
+    method: &str,
+    uri: &str,
+    headers: impl IntoIterator<Item = (&'a str, &'a [u8])>,
+) -> Result<http::Request<()>, HttpSigError> {
+    let mut builder = http::Request::builder();
+    builder.method(method).uri(uri);
+    for (name, value) in headers {
+        builder.header(name, value);
+    }
+
+    Ok(builder.body(())?)
+}
+
+// Replaces each line break followed by whitespace in the header section with a single space. The
+// body is copied unchanged.
+fn unfold_headers(buf: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(buf.len());
+    let mut i = 0;
+
+    while i < buf.len() {
+        let line_break_len = match &buf[i..] {
+            [b'\r', b'\n', ..] => 2,
+            [b'\n', ..] => 1,
+            _ => 0,
+        };
+
+        if line_break_len == 0 {
+            output.push(buf[i]);
+            i += 1;
+            continue;
+        }
+
+        let next = i + line_break_len;
+        match buf.get(next) {
+            Some(b' ') | Some(b'\t') => {
+                let mut end = next;
+                while let Some(b' ') | Some(b'\t') = buf.get(end) {
+                    end += 1;
+                }
+
+                output.push(b' ');
+                i = end;
+            }
+            _ => {
+                output.extend_from_slice(&buf[i..next]);
+                i = next;
+
+                // An empty line ends the header section
+                if buf[i..].starts_with(b"\r\n") || buf[i..].starts_with(b"\n") {
+                    output.extend_from_slice(&buf[i..]);
+                    break;
+                }
+            }
+        }
+    }
+
+    output
+}
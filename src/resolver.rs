@@ -0,0 +1,160 @@
+// Fetches the public key for a `keyId` that is a URL, as in ActivityPub, where it points to the
+// actor (or to the key itself) and the key is in its `publicKeyPem`:
+//
+// {"id": "https://example.com/users/alice", "publicKey": {"id": "https://example.com/users/alice#main-key", "publicKeyPem": "..."}}
+//
+// Both resolvers use the blocking `reqwest::Client`; `AsyncHttpKeyResolver` (with the `tokio`
+// feature) runs it on tokio's blocking thread pool. The crate doesn't enable any of reqwest's TLS
+// features, so enable one (e.g. `default-tls`) to fetch keys from `https` URLs.
+
+use crate::{HttpSigError, KeyResolver};
+use openssl::pkey::{PKey, Public};
+use reqwest::header::ACCEPT;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
+
+#[cfg(feature = "tokio")]
+pub use self::non_blocking::{AsyncHttpKeyResolver, AsyncKeyResolver};
+
+const ACCEPT_JSON_LD: &str = "application/activity+json, application/ld+json";
+
+// Keys are cached forever once fetched, so a rotated key is only picked up by a new resolver
+pub struct HttpKeyResolver {
+    client: reqwest::Client,
+    cache: RwLock<HashMap<String, PKey<Public>>>,
+}
+
+impl HttpKeyResolver {
+    pub fn new(client: reqwest::Client) -> Self {
+        HttpKeyResolver {
+            client,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl KeyResolver for HttpKeyResolver {
+    fn resolve(&self, key_id: &str) -> Result<Option<PKey<Public>>, HttpSigError> {
+        let cached = self
+            .cache
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(key_id)
+            .cloned();
+        if cached.is_some() {
+            return Ok(cached);
+        }
+
+        let key = fetch(&self.client, key_id)?;
+        if let Some(key) = &key {
+            self.cache
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(key_id.to_owned(), key.clone());
+        }
+        Ok(key)
+    }
+}
+
+fn fetch(client: &reqwest::Client, key_id: &str) -> Result<Option<PKey<Public>>, HttpSigError> {
+    // Key IDs that aren't URLs are for some other resolver
+    let url = match reqwest::Url::parse(key_id) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+        _ => return Ok(None),
+    };
+
+    let response = client.get(url).header(ACCEPT, ACCEPT_JSON_LD).send()?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND
+        || response.status() == reqwest::StatusCode::GONE
+    {
+        return Ok(None);
+    }
+    let document: Value = response.error_for_status()?.json()?;
+
+    let pem =
+        public_key_pem(&document, key_id).ok_or_else(|| HttpSigError::InvalidKeyDocument {
+            key_id: key_id.to_owned(),
+        })?;
+    Ok(Some(PKey::public_key_from_pem(pem.as_bytes())?))
+}
+
+// The document is either the key itself, or an actor with one or more keys in `publicKey`
+fn public_key_pem<'a>(document: &'a Value, key_id: &str) -> Option<&'a str> {
+    if let Some(pem) = document.get("publicKeyPem").and_then(Value::as_str) {
+        return Some(pem);
+    }
+
+    let key = match document.get("publicKey")? {
+        Value::Array(keys) => keys
+            .iter()
+            .find(|key| key.get("id").and_then(Value::as_str) == Some(key_id))
+            .or_else(|| keys.first())?,
+        key => key,
+    };
+    key.get("publicKeyPem").and_then(Value::as_str)
+}
+
+#[cfg(feature = "tokio")]
+mod non_blocking {
+    use super::fetch;
+    use crate::HttpSigError;
+    use openssl::pkey::{PKey, Public};
+    use std::collections::HashMap;
+    use std::future::Future;
+    use std::time::{Duration, Instant};
+    use tokio::sync::RwLock;
+
+    // Like `KeyResolver`, for resolvers that fetch keys without blocking
+    pub trait AsyncKeyResolver {
+        fn resolve(
+            &self,
+            key_id: &str,
+        ) -> impl Future<Output = Result<Option<PKey<Public>>, HttpSigError>> + Send;
+    }
+
+    // Cached keys are fetched again once they are older than `ttl`, so rotated keys are picked
+    // up. Keys must be resolved inside a tokio runtime.
+    pub struct AsyncHttpKeyResolver {
+        client: reqwest::Client,
+        ttl: Duration,
+        cache: RwLock<HashMap<String, (PKey<Public>, Instant)>>,
+    }
+
+    impl AsyncHttpKeyResolver {
+        pub fn new(client: reqwest::Client, ttl: Duration) -> Self {
+            AsyncHttpKeyResolver {
+                client,
+                ttl,
+                cache: RwLock::new(HashMap::new()),
+            }
+        }
+    }
+
+    impl AsyncKeyResolver for AsyncHttpKeyResolver {
+        async fn resolve(&self, key_id: &str) -> Result<Option<PKey<Public>>, HttpSigError> {
+            if let Some((key, fetched_at)) = self.cache.read().await.get(key_id) {
+                if fetched_at.elapsed() < self.ttl {
+                    return Ok(Some(key.clone()));
+                }
+            }
+
+            let client = self.client.clone();
+            let owned_key_id = key_id.to_owned();
+            let key = tokio::task::spawn_blocking(move || fetch(&client, &owned_key_id))
+                .await
+                .expect("fetching a key panicked")?;
+
+            let mut cache = self.cache.write().await;
+            match &key {
+                Some(key) => {
+                    cache.insert(key_id.to_owned(), (key.clone(), Instant::now()));
+                }
+                None => {
+                    cache.remove(key_id);
+                }
+            }
+            Ok(key)
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{
+    find_signature_in_request, sign_request, verify_signature_parts_for_target, HttpSigError,
+    RequestHead, Scratch, SignatureParams, VerificationOptions,
+};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private, Public};
+
+// Signs many requests with the same key and params. The buffers for the signing string, signature
+// and header value are kept between calls, so signing a request doesn't allocate once they've
+// grown to fit.
+pub struct ReusableSigner {
+    params: SignatureParams,
+    private_key: PKey<Private>,
+    header: String,
+    scratch: Scratch,
+}
+
+impl ReusableSigner {
+    pub fn new(params: SignatureParams, private_key: PKey<Private>) -> Self {
+        ReusableSigner {
+            params,
+            private_key,
+            header: String::new(),
+            scratch: Scratch::default(),
+        }
+    }
+
+    // Same as `add_signature_header_with_params`
+    pub fn sign<T>(&mut self, request: &mut http::Request<T>) -> Result<(), HttpSigError> {
+        sign_request(
+            request,
+            &self.params,
+            &self.private_key,
+            &mut self.header,
+            &mut self.scratch,
+        )
+    }
+}
+
+// Verifies many requests against the same key, reusing buffers like `ReusableSigner`
+pub struct ReusableVerifier {
+    digest: MessageDigest,
+    public_key: PKey<Public>,
+    options: VerificationOptions,
+    scratch: Scratch,
+}
+
+impl ReusableVerifier {
+    pub fn new(digest: MessageDigest, public_key: PKey<Public>) -> Self {
+        ReusableVerifier::with_options(digest, public_key, VerificationOptions::default())
+    }
+
+    pub fn with_options(
+        digest: MessageDigest,
+        public_key: PKey<Public>,
+        options: VerificationOptions,
+    ) -> Self {
+        ReusableVerifier {
+            digest,
+            public_key,
+            options,
+            scratch: Scratch::default(),
+        }
+    }
+
+    // Same as `verify_request_with_options`
+    pub fn verify<T>(&mut self, request: &http::Request<T>) -> Result<bool, HttpSigError> {
+        let parts = match find_signature_in_request(request) {
+            Ok(parts) => parts,
+            Err(e) => return self.options.unparsed_signature(e),
+        };
+
+        verify_signature_parts_for_target(
+            &RequestHead::new(request.method(), request.uri(), request.headers()),
+            &parts,
+            self.digest,
+            &self.public_key,
+            &self.options,
+            &mut self.scratch,
+        )
+    }
+}
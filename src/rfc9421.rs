@@ -0,0 +1,668 @@
+// Signature bases as defined by RFC 9421 (HTTP Message Signatures), the successor to the cavage
+// draft used by the rest of this crate.
+// https://www.rfc-editor.org/rfc/rfc9421.html
+//
+// Only the covered components and the signature base are handled here, and the caller provides
+// the signature parameters (e.g. `;created=1618884473;keyid="test-key"`) as a string.
+// `RequestedSignature` builds them for a signature asked for with `Accept-Signature`, and
+// `verify_request` reads them from the request's `Signature-Input` header.
+
+use crate::structured_field::{self, BareItem, Field, Item, Member};
+use crate::{request_target, write_header_values, HttpSigError, SignatureParams};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{HasPrivate, HasPublic, PKeyRef};
+use openssl::sign::{Signer, Verifier};
+use std::convert::TryFrom;
+use std::fmt::{self, Write as _};
+use std::io::Write as _;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// A component identifier, e.g. `"date"`, `"@method"` or `"example-dict";key="a"`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ComponentId {
+    pub name: String,
+    // `;key="..."`: only the given member of a dictionary-structured header is covered
+    pub key: Option<String>,
+    // `;sf`: the header is covered in its canonical structured field serialization
+    pub sf: bool,
+    // `;bs`: each of the header's values is covered as a byte sequence, for values that aren't
+    // safe to sign as text
+    pub bs: bool,
+    // `;tr`: the field is a trailer, looked up in the request's `Trailers` extension
+    pub tr: bool,
+    // `;name="..."`: the query parameter covered by `@query-param`, percent-encoded
+    pub query_param: Option<String>,
+}
+
+impl ComponentId {
+    pub fn new(name: impl Into<String>) -> Self {
+        ComponentId {
+            name: name.into().to_ascii_lowercase(),
+            key: None,
+            sf: false,
+            bs: false,
+            tr: false,
+            query_param: None,
+        }
+    }
+
+    // `"@query-param";name="..."`. `name` is compared against the request's parameter names after
+    // they've been normalized as described in `component_value`.
+    pub fn query_param(name: impl Into<String>) -> Self {
+        ComponentId {
+            query_param: Some(name.into()),
+            ..ComponentId::new("@query-param")
+        }
+    }
+
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    pub fn with_sf(mut self) -> Self {
+        self.sf = true;
+        self
+    }
+
+    pub fn with_bs(mut self) -> Self {
+        self.bs = true;
+        self
+    }
+
+    pub fn with_tr(mut self) -> Self {
+        self.tr = true;
+        self
+    }
+
+    // Parses a serialized component identifier, as found in `Signature-Input`
+    pub fn parse(input: &str) -> Result<Self, HttpSigError> {
+        let invalid = || HttpSigError::InvalidComponent {
+            component: input.to_owned(),
+        };
+
+        let item = structured_field::parse_item(input).map_err(|_| invalid())?;
+        ComponentId::from_item(item).ok_or_else(invalid)
+    }
+
+    fn from_item(item: Item) -> Option<Self> {
+        let mut component = match item.bare_item {
+            BareItem::String(name) => ComponentId::new(name),
+            _ => return None,
+        };
+
+        for (param, value) in item.params {
+            match (param.as_str(), value) {
+                ("key", BareItem::String(key)) => component.key = Some(key),
+                ("sf", BareItem::Boolean(true)) => component.sf = true,
+                ("bs", BareItem::Boolean(true)) => component.bs = true,
+                ("tr", BareItem::Boolean(true)) => component.tr = true,
+                ("name", BareItem::String(name)) => component.query_param = Some(name),
+                _ => return None,
+            }
+        }
+
+        Some(component)
+    }
+
+    fn to_item(&self) -> Item {
+        let mut item = Item {
+            bare_item: BareItem::String(self.name.clone()),
+            params: Vec::new(),
+        };
+        if let Some(key) = &self.key {
+            item.params
+                .push(("key".to_owned(), BareItem::String(key.clone())));
+        }
+        if self.sf {
+            item.params.push(("sf".to_owned(), BareItem::Boolean(true)));
+        }
+        if self.bs {
+            item.params.push(("bs".to_owned(), BareItem::Boolean(true)));
+        }
+        if self.tr {
+            item.params.push(("tr".to_owned(), BareItem::Boolean(true)));
+        }
+        if let Some(name) = &self.query_param {
+            item.params
+                .push(("name".to_owned(), BareItem::String(name.clone())));
+        }
+        item
+    }
+}
+
+impl fmt::Display for ComponentId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut output = String::new();
+        structured_field::write_item(&mut output, &self.to_item());
+        f.write_str(&output)
+    }
+}
+
+// The trailer fields of a request, sent after the body. `http::Request` has nowhere else to keep
+// them, so they're read from its extensions once the body has been received:
+//
+//     request.extensions_mut().insert(Trailers(trailers));
+//
+// Components with `;tr` are looked up here instead of in the headers.
+#[derive(Clone, Debug, Default)]
+pub struct Trailers(pub http::HeaderMap);
+
+// The value a component contributes to the signature base. Plain header values are copied byte
+// for byte, so they don't need to be UTF-8.
+//
+// For `@query-param`, the parameter's name and value are decoded as
+// `application/x-www-form-urlencoded` and percent-encoded again, so `a+b` and `a%20b` are both
+// covered as `a%20b`. A parameter that is missing or appears more than once can't be covered, as
+// RFC 9421 requires. For repeated parameters like `?a=1&a=2`, cover `@query`, which is the query
+// exactly as received, so the order of the values is signed too. Other parameters in the same
+// query can still be covered on their own.
+//
+// With `;bs`, each of the header's values has surrounding whitespace removed and is base64-encoded
+// as a byte sequence, e.g. `:Y2Fm6Q==:, :dGVh:`. It can't be combined with `;sf` or `;key`.
+// https://www.rfc-editor.org/rfc/rfc9421.html#section-2.1.3
+pub fn component_value<T>(
+    request: &http::Request<T>,
+    component: &ComponentId,
+) -> Result<Vec<u8>, HttpSigError> {
+    if component.bs && (component.name.starts_with('@') || component.key.is_some() || component.sf)
+    {
+        return Err(HttpSigError::InvalidComponent {
+            component: component.to_string(),
+        });
+    }
+    if component.name.starts_with('@') {
+        return derived_component_value(request, component);
+    }
+    if component.query_param.is_some() {
+        return Err(HttpSigError::InvalidComponent {
+            component: component.to_string(),
+        });
+    }
+
+    let headers = if component.tr {
+        match request.extensions().get::<Trailers>() {
+            Some(Trailers(trailers)) => trailers,
+            None => {
+                return Err(HttpSigError::MissingHeader {
+                    name: component.name.clone(),
+                })
+            }
+        }
+    } else {
+        request.headers()
+    };
+    if !headers.contains_key(component.name.as_str()) {
+        return Err(HttpSigError::MissingHeader {
+            name: component.name.clone(),
+        });
+    }
+
+    if component.bs {
+        return Ok(byte_sequence_values(headers, &component.name));
+    }
+
+    let mut combined = Vec::new();
+    write_header_values(&mut combined, headers, &component.name);
+    if component.key.is_none() && !component.sf {
+        return Ok(combined);
+    }
+
+    // Structured fields are always ASCII
+    let combined = String::from_utf8(combined).map_err(|_| invalid_field(component))?;
+
+    if let Some(key) = &component.key {
+        let dictionary =
+            structured_field::parse_dictionary(&combined).map_err(|_| invalid_field(component))?;
+        let member = dictionary
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, member)| member)
+            .ok_or_else(|| HttpSigError::MissingDictionaryKey {
+                name: component.name.clone(),
+                key: key.clone(),
+            })?;
+
+        let mut output = String::new();
+        structured_field::write_member(&mut output, member);
+        Ok(output.into_bytes())
+    } else {
+        let field: Field =
+            structured_field::parse_field(&combined).map_err(|_| invalid_field(component))?;
+
+        let mut output = String::new();
+        structured_field::write_field(&mut output, &field);
+        Ok(output.into_bytes())
+    }
+}
+
+fn byte_sequence_values(headers: &http::HeaderMap, name: &str) -> Vec<u8> {
+    let mut output = String::new();
+    for (i, value) in headers.get_all(name).iter().enumerate() {
+        if i > 0 {
+            output.push_str(", ");
+        }
+        let value = value.as_bytes();
+        let start = value
+            .iter()
+            .position(|b| !matches!(b, b' ' | b'\t'))
+            .unwrap_or(value.len());
+        let end = value
+            .iter()
+            .rposition(|b| !matches!(b, b' ' | b'\t'))
+            .map_or(start, |i| i + 1);
+
+        structured_field::write_bare_item(
+            &mut output,
+            &BareItem::ByteSequence(value[start..end].to_vec()),
+        );
+    }
+    output.into_bytes()
+}
+
+fn derived_component_value<T>(
+    request: &http::Request<T>,
+    component: &ComponentId,
+) -> Result<Vec<u8>, HttpSigError> {
+    let invalid = || HttpSigError::InvalidComponent {
+        component: component.to_string(),
+    };
+
+    // `;key`, `;sf` and `;tr` only apply to fields, and `;name` only to `@query-param`
+    if component.key.is_some()
+        || component.sf
+        || component.tr
+        || component.query_param.is_some() != (component.name == "@query-param")
+    {
+        return Err(invalid());
+    }
+
+    let uri = request.uri();
+    let value = match component.name.as_str() {
+        "@method" => request.method().as_str().to_owned(),
+        "@path" => uri.path().to_owned(),
+        "@query" => format!("?{}", uri.query().unwrap_or("")),
+        "@request-target" => request_target(uri).to_owned(),
+        "@query-param" => {
+            let name = component.query_param.as_deref().unwrap_or_default();
+            query_param_value(uri.query().unwrap_or(""), name)?
+        }
+        "@authority" => match request.headers().get(http::header::HOST) {
+            Some(host) => return Ok(host.as_bytes().to_ascii_lowercase()),
+            None => uri
+                .authority_part()
+                .ok_or_else(invalid)?
+                .as_str()
+                .to_ascii_lowercase(),
+        },
+        // A request received by a server usually has only a path, so these need the scheme it was
+        // received with to be put back into the URI first
+        "@scheme" => uri
+            .scheme_part()
+            .ok_or_else(invalid)?
+            .as_str()
+            .to_ascii_lowercase(),
+        "@target-uri" => {
+            let scheme = uri.scheme_part().ok_or_else(invalid)?;
+            let authority = uri.authority_part().ok_or_else(invalid)?;
+            format!("{}://{}{}", scheme, authority, request_target(uri))
+        }
+        _ => return Err(invalid()),
+    };
+
+    Ok(value.into_bytes())
+}
+
+// https://www.rfc-editor.org/rfc/rfc9421.html#section-2.2.8
+fn query_param_value(query: &str, name: &str) -> Result<String, HttpSigError> {
+    let mut values = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter_map(|pair| {
+            let mut pair = pair.splitn(2, '=');
+            if reencode(pair.next().unwrap_or("")) == name {
+                Some(reencode(pair.next().unwrap_or("")))
+            } else {
+                None
+            }
+        });
+
+    match (values.next(), values.next()) {
+        (Some(value), None) => Ok(value),
+        (None, _) => Err(HttpSigError::MissingQueryParam {
+            name: name.to_owned(),
+        }),
+        (Some(_), Some(_)) => Err(HttpSigError::RepeatedQueryParam {
+            name: name.to_owned(),
+        }),
+    }
+}
+
+// Decodes `+` and percent-escapes, then percent-encodes everything except `*-._` and ASCII
+// alphanumerics. Invalid escapes are kept as they are.
+fn reencode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    let mut output = String::with_capacity(decoded.len());
+    for byte in decoded {
+        if byte.is_ascii_alphanumeric() || b"*-._".contains(&byte) {
+            output.push(byte as char);
+        } else {
+            let _ = write!(output, "%{:02X}", byte);
+        }
+    }
+    output
+}
+
+fn invalid_field(component: &ComponentId) -> HttpSigError {
+    HttpSigError::InvalidStructuredField {
+        name: component.name.clone(),
+    }
+}
+
+// Builds the signature base for the given components. `signature_params` is appended to the
+// inner list of components in the `@signature-params` line.
+pub fn signature_base<T>(
+    request: &http::Request<T>,
+    components: &[ComponentId],
+    signature_params: &str,
+) -> Result<Vec<u8>, HttpSigError> {
+    let mut output = Vec::new();
+    let mut inner_list = String::from("(");
+
+    for (i, component) in components.iter().enumerate() {
+        if components[..i].contains(component) {
+            return Err(HttpSigError::InvalidComponent {
+                component: component.to_string(),
+            });
+        }
+
+        write!(&mut output, "{}: ", component)?;
+        output.extend(component_value(request, component)?);
+        output.push(b'\n');
+
+        if i > 0 {
+            inner_list.push(' ');
+        }
+        write!(&mut inner_list, "{}", component)?;
+    }
+
+    write!(
+        &mut output,
+        "\"@signature-params\": {}){}",
+        inner_list, signature_params
+    )?;
+
+    Ok(output)
+}
+
+// Like `signature_base`, with the `keyid` and `nonce` parameters taken from `params`, e.g.
+// `;keyid="Test";nonce="abc123"`. These are the exact bytes `compute_signature` would sign.
+pub fn compute_rfc9421_signature_base<T>(
+    request: &http::Request<T>,
+    components: &[ComponentId],
+    params: &SignatureParams,
+) -> Result<Vec<u8>, HttpSigError> {
+    let mut signature_params = vec![(
+        "keyid".to_owned(),
+        BareItem::String(params.signed_key_id().into_owned()),
+    )];
+    if let Some(nonce) = &params.nonce {
+        signature_params.push(("nonce".to_owned(), BareItem::String(nonce.clone())));
+    }
+
+    let mut serialized = String::new();
+    structured_field::write_parameters(&mut serialized, &signature_params);
+    signature_base(request, components, &serialized)
+}
+
+pub fn compute_signature<T>(
+    request: &http::Request<T>,
+    components: &[ComponentId],
+    signature_params: &str,
+    digest: MessageDigest,
+    private_key: &PKeyRef<impl HasPrivate>,
+) -> Result<Vec<u8>, HttpSigError> {
+    let base = signature_base(request, components, signature_params)?;
+
+    let mut signer = Signer::new(digest, private_key)?;
+    signer.update(&base)?;
+    Ok(signer.sign_to_vec()?)
+}
+
+pub fn verify_signature<T>(
+    request: &http::Request<T>,
+    components: &[ComponentId],
+    signature_params: &str,
+    signature: &[u8],
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+) -> Result<bool, HttpSigError> {
+    let base = signature_base(request, components, signature_params)?;
+
+    let mut verifier = Verifier::new(digest, public_key)?;
+    verifier.update(&base)?;
+    Ok(verifier.verify(signature)?)
+}
+
+// Verifies the signature labelled `label` in the request's `Signature-Input` and `Signature`
+// headers. The components and parameters are the ones declared in `Signature-Input`, and its
+// serialized member is the `@signature-params` line of the signature base, so a `Signature-Input`
+// that doesn't declare exactly what was signed won't verify. Fails with `MissingSignature` if
+// either header has no member called `label`.
+pub fn verify_request<T>(
+    request: &http::Request<T>,
+    label: &str,
+    digest: MessageDigest,
+    public_key: &PKeyRef<impl HasPublic>,
+) -> Result<bool, HttpSigError> {
+    let (components, signature_params) = match dictionary_member(request, "signature-input", label)?
+    {
+        Member::InnerList(items, params) => {
+            let components = items
+                .into_iter()
+                .map(ComponentId::from_item)
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| invalid_header("signature-input"))?;
+            let mut signature_params = String::new();
+            structured_field::write_parameters(&mut signature_params, &params);
+            (components, signature_params)
+        }
+        Member::Item(_) => return Err(invalid_header("signature-input")),
+    };
+
+    let signature = match dictionary_member(request, "signature", label)? {
+        Member::Item(Item {
+            bare_item: BareItem::ByteSequence(signature),
+            ..
+        }) => signature,
+        _ => return Err(invalid_header("signature")),
+    };
+
+    verify_signature(
+        request,
+        &components,
+        &signature_params,
+        &signature,
+        digest,
+        public_key,
+    )
+}
+
+// The member of the dictionary-structured header `name` called `label`
+fn dictionary_member<T>(
+    request: &http::Request<T>,
+    name: &str,
+    label: &str,
+) -> Result<Member, HttpSigError> {
+    if !request.headers().contains_key(name) {
+        return Err(HttpSigError::MissingHeader {
+            name: name.to_owned(),
+        });
+    }
+
+    let mut value = Vec::new();
+    write_header_values(&mut value, request.headers(), name);
+    let value = std::str::from_utf8(&value).map_err(|_| invalid_header(name))?;
+    let dictionary = structured_field::parse_dictionary(value).map_err(|_| invalid_header(name))?;
+    dictionary
+        .into_iter()
+        .find(|(key, _)| key == label)
+        .map(|(_, member)| member)
+        .ok_or(HttpSigError::MissingSignature)
+}
+
+fn invalid_header(name: &str) -> HttpSigError {
+    HttpSigError::InvalidStructuredField {
+        name: name.to_owned(),
+    }
+}
+
+// A signature asked for by an `Accept-Signature` header, e.g.
+// `sig1=("@method" "@target-uri" "content-digest");keyid="test-key-rsa-pss";created`
+// https://www.rfc-editor.org/rfc/rfc9421.html#section-5.1
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestedSignature {
+    // The label to use for the signature in `Signature-Input` and `Signature`
+    pub label: String,
+    pub components: Vec<ComponentId>,
+    pub key_id: Option<String>,
+    pub algorithm: Option<String>,
+    // Whether to include a `created` timestamp
+    pub created: bool,
+    pub nonce: Option<String>,
+    pub tag: Option<String>,
+}
+
+impl RequestedSignature {
+    pub fn new(label: impl Into<String>, components: Vec<ComponentId>) -> Self {
+        RequestedSignature {
+            label: label.into(),
+            components,
+            key_id: None,
+            algorithm: None,
+            created: false,
+            nonce: None,
+            tag: None,
+        }
+    }
+
+    // The signature parameters for `compute_signature`, e.g. `;created=1618884473;keyid="test"`.
+    // `created` is only used if the signature asked for it.
+    pub fn signature_params(&self, created: SystemTime) -> String {
+        let mut params = Vec::new();
+        if self.created {
+            let timestamp = created
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since_epoch| since_epoch.as_secs());
+            params.push((
+                "created".to_owned(),
+                BareItem::Integer(i64::try_from(timestamp).unwrap_or(i64::MAX)),
+            ));
+        }
+        params.extend(self.string_params());
+
+        let mut output = String::new();
+        structured_field::write_parameters(&mut output, &params);
+        output
+    }
+
+    fn string_params(&self) -> Vec<(String, BareItem)> {
+        [
+            ("keyid", &self.key_id),
+            ("alg", &self.algorithm),
+            ("nonce", &self.nonce),
+            ("tag", &self.tag),
+        ]
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = value.as_ref()?;
+            Some(((*name).to_owned(), BareItem::String(value.clone())))
+        })
+        .collect()
+    }
+}
+
+// Parameters other than `keyid`, `alg`, `created`, `nonce` and `tag` are ignored
+pub fn parse_accept_signature(input: &str) -> Result<Vec<RequestedSignature>, HttpSigError> {
+    let invalid = || HttpSigError::InvalidStructuredField {
+        name: "accept-signature".to_owned(),
+    };
+
+    let dictionary = structured_field::parse_dictionary(input).map_err(|_| invalid())?;
+    dictionary
+        .into_iter()
+        .map(|(label, member)| {
+            let (items, params) = match member {
+                Member::InnerList(items, params) => (items, params),
+                Member::Item(_) => return Err(invalid()),
+            };
+
+            let components = items
+                .into_iter()
+                .map(ComponentId::from_item)
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(invalid)?;
+            let mut requested = RequestedSignature::new(label, components);
+            for (name, value) in params {
+                match (name.as_str(), value) {
+                    ("keyid", BareItem::String(key_id)) => requested.key_id = Some(key_id),
+                    ("alg", BareItem::String(algorithm)) => requested.algorithm = Some(algorithm),
+                    ("created", BareItem::Boolean(created)) => requested.created = created,
+                    ("nonce", BareItem::String(nonce)) => requested.nonce = Some(nonce),
+                    ("tag", BareItem::String(tag)) => requested.tag = Some(tag),
+                    ("keyid" | "alg" | "created" | "nonce" | "tag", _) => return Err(invalid()),
+                    _ => {}
+                }
+            }
+            Ok(requested)
+        })
+        .collect()
+}
+
+// The value of an `Accept-Signature` header asking for `signatures`
+pub fn accept_signature_header(signatures: &[RequestedSignature]) -> String {
+    let dictionary = signatures
+        .iter()
+        .map(|requested| {
+            let mut params = Vec::new();
+            if requested.created {
+                params.push(("created".to_owned(), BareItem::Boolean(true)));
+            }
+            params.extend(requested.string_params());
+
+            let items = requested
+                .components
+                .iter()
+                .map(ComponentId::to_item)
+                .collect();
+            (requested.label.clone(), Member::InnerList(items, params))
+        })
+        .collect();
+
+    let mut output = String::new();
+    structured_field::write_dictionary(&mut output, &dictionary);
+    output
+}
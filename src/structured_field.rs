@@ -0,0 +1,442 @@
+// Just enough of RFC 8941 (Structured Field Values) to parse a field and serialize it again in
+// canonical form, which is what RFC 9421 signs for `;sf` and `;key` components.
+// https://www.rfc-editor.org/rfc/rfc8941.html
+
+use crate::Base64Alphabet;
+use base64::Engine as _;
+use std::fmt::Write as _;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum BareItem {
+    Integer(i64),
+    // Stored in thousandths, since decimals have at most three fractional digits
+    Decimal(i64),
+    String(String),
+    Token(String),
+    ByteSequence(Vec<u8>),
+    Boolean(bool),
+}
+
+pub(crate) type Parameters = Vec<(String, BareItem)>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Item {
+    pub bare_item: BareItem,
+    pub params: Parameters,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Member {
+    Item(Item),
+    InnerList(Vec<Item>, Parameters),
+}
+
+pub(crate) type Dictionary = Vec<(String, Member)>;
+
+// The three top-level field types
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Field {
+    Dictionary(Dictionary),
+    List(Vec<Member>),
+    Item(Item),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ParseError;
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+pub(crate) fn parse_dictionary(input: &str) -> Result<Dictionary> {
+    Parser::new(input).top_level(Parser::dictionary)
+}
+
+pub(crate) fn parse_list(input: &str) -> Result<Vec<Member>> {
+    Parser::new(input).top_level(Parser::list)
+}
+
+pub(crate) fn parse_item(input: &str) -> Result<Item> {
+    Parser::new(input).top_level(Parser::item)
+}
+
+// For fields whose type isn't known up front. A value that is valid as more than one type
+// serializes the same either way, so the first type that parses is used.
+pub(crate) fn parse_field(input: &str) -> Result<Field> {
+    parse_dictionary(input)
+        .map(Field::Dictionary)
+        .or_else(|_| parse_list(input).map(Field::List))
+        .or_else(|_| parse_item(input).map(Field::Item))
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser {
+            input: input.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_sp(&mut self) {
+        while self.eat(b' ') {}
+    }
+
+    fn skip_ows(&mut self) {
+        while self.eat(b' ') || self.eat(b'\t') {}
+    }
+
+    fn top_level<T>(mut self, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.skip_sp();
+        let value = parse(&mut self)?;
+        self.skip_sp();
+        if self.peek().is_some() {
+            return Err(ParseError);
+        }
+        Ok(value)
+    }
+
+    fn dictionary(&mut self) -> Result<Dictionary> {
+        let mut dictionary = Dictionary::new();
+        while self.peek().is_some() {
+            let key = self.key()?;
+            let member = if self.eat(b'=') {
+                self.member()?
+            } else {
+                Member::Item(Item {
+                    bare_item: BareItem::Boolean(true),
+                    params: self.parameters()?,
+                })
+            };
+            insert(&mut dictionary, key, member);
+
+            if !self.list_separator()? {
+                break;
+            }
+        }
+        Ok(dictionary)
+    }
+
+    fn list(&mut self) -> Result<Vec<Member>> {
+        let mut members = Vec::new();
+        while self.peek().is_some() {
+            members.push(self.member()?);
+
+            if !self.list_separator()? {
+                break;
+            }
+        }
+        Ok(members)
+    }
+
+    // Returns whether another member follows
+    fn list_separator(&mut self) -> Result<bool> {
+        self.skip_ows();
+        if self.peek().is_none() {
+            return Ok(false);
+        }
+        if !self.eat(b',') {
+            return Err(ParseError);
+        }
+        self.skip_ows();
+        if self.peek().is_none() {
+            // Trailing comma
+            return Err(ParseError);
+        }
+        Ok(true)
+    }
+
+    fn member(&mut self) -> Result<Member> {
+        if self.eat(b'(') {
+            let mut items = Vec::new();
+            loop {
+                self.skip_sp();
+                if self.eat(b')') {
+                    return Ok(Member::InnerList(items, self.parameters()?));
+                }
+                items.push(self.item()?);
+                match self.peek() {
+                    Some(b' ') | Some(b')') => {}
+                    _ => return Err(ParseError),
+                }
+            }
+        }
+        self.item().map(Member::Item)
+    }
+
+    fn item(&mut self) -> Result<Item> {
+        Ok(Item {
+            bare_item: self.bare_item()?,
+            params: self.parameters()?,
+        })
+    }
+
+    fn parameters(&mut self) -> Result<Parameters> {
+        let mut params = Parameters::new();
+        while self.eat(b';') {
+            self.skip_sp();
+            let key = self.key()?;
+            let value = if self.eat(b'=') {
+                self.bare_item()?
+            } else {
+                BareItem::Boolean(true)
+            };
+            insert(&mut params, key, value);
+        }
+        Ok(params)
+    }
+
+    fn key(&mut self) -> Result<String> {
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if c.is_ascii_lowercase() || c == b'*' => {}
+            _ => return Err(ParseError),
+        }
+        while let Some(c) = self.peek() {
+            if c.is_ascii_lowercase() || c.is_ascii_digit() || b"_-.*".contains(&c) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        Ok(self.slice(start))
+    }
+
+    fn bare_item(&mut self) -> Result<BareItem> {
+        match self.peek() {
+            Some(c) if c == b'-' || c.is_ascii_digit() => self.number(),
+            Some(b'"') => self.string(),
+            Some(c) if c.is_ascii_alphabetic() || c == b'*' => self.token(),
+            Some(b':') => self.byte_sequence(),
+            Some(b'?') => self.boolean(),
+            _ => Err(ParseError),
+        }
+    }
+
+    fn number(&mut self) -> Result<BareItem> {
+        let negative = self.eat(b'-');
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let integer_digits = self.pos - start;
+        if integer_digits == 0 {
+            return Err(ParseError);
+        }
+        let integer: i64 = self.slice(start).parse().map_err(|_| ParseError)?;
+        let sign = if negative { -1 } else { 1 };
+
+        if !self.eat(b'.') {
+            if integer_digits > 15 {
+                return Err(ParseError);
+            }
+            return Ok(BareItem::Integer(sign * integer));
+        }
+
+        let fraction_start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let fraction_digits = self.pos - fraction_start;
+        if integer_digits > 12 || fraction_digits == 0 || fraction_digits > 3 {
+            return Err(ParseError);
+        }
+        let fraction: i64 = self.slice(fraction_start).parse().map_err(|_| ParseError)?;
+        let thousandths = integer * 1000 + fraction * 10_i64.pow(3 - fraction_digits as u32);
+        Ok(BareItem::Decimal(sign * thousandths))
+    }
+
+    fn string(&mut self) -> Result<BareItem> {
+        self.next();
+        let mut output = String::new();
+        loop {
+            match self.next() {
+                Some(b'\\') => match self.next() {
+                    Some(c @ b'"') | Some(c @ b'\\') => output.push(c as char),
+                    _ => return Err(ParseError),
+                },
+                Some(b'"') => return Ok(BareItem::String(output)),
+                Some(c @ 0x20..=0x7e) => output.push(c as char),
+                _ => return Err(ParseError),
+            }
+        }
+    }
+
+    fn token(&mut self) -> Result<BareItem> {
+        let start = self.pos;
+        self.pos += 1;
+        while self.peek().is_some_and(is_token_char) {
+            self.pos += 1;
+        }
+        Ok(BareItem::Token(self.slice(start)))
+    }
+
+    fn byte_sequence(&mut self) -> Result<BareItem> {
+        self.next();
+        let start = self.pos;
+        while let Some(c) = self.next() {
+            if c == b':' {
+                let encoded = &self.input[start..self.pos - 1];
+                let decoded = Base64Alphabet::Standard
+                    .engine()
+                    .decode(encoded)
+                    .map_err(|_| ParseError)?;
+                return Ok(BareItem::ByteSequence(decoded));
+            }
+            if !(c.is_ascii_alphanumeric() || b"+/=".contains(&c)) {
+                return Err(ParseError);
+            }
+        }
+        Err(ParseError)
+    }
+
+    fn boolean(&mut self) -> Result<BareItem> {
+        self.next();
+        match self.next() {
+            Some(b'0') => Ok(BareItem::Boolean(false)),
+            Some(b'1') => Ok(BareItem::Boolean(true)),
+            _ => Err(ParseError),
+        }
+    }
+
+    // Everything consumed since `start`, which the callers have checked is ASCII
+    fn slice(&self, start: usize) -> String {
+        String::from_utf8_lossy(&self.input[start..self.pos]).into_owned()
+    }
+}
+
+fn is_token_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~:/".contains(&c)
+}
+
+// Later duplicates replace the value of earlier ones, keeping the original position
+fn insert<V>(entries: &mut Vec<(String, V)>, key: String, value: V) {
+    match entries.iter_mut().find(|(k, _)| *k == key) {
+        Some(entry) => entry.1 = value,
+        None => entries.push((key, value)),
+    }
+}
+
+pub(crate) fn write_field(out: &mut String, field: &Field) {
+    match field {
+        Field::Dictionary(dictionary) => write_dictionary(out, dictionary),
+        Field::List(members) => {
+            for (i, member) in members.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_member(out, member);
+            }
+        }
+        Field::Item(item) => write_item(out, item),
+    }
+}
+
+pub(crate) fn write_dictionary(out: &mut String, dictionary: &Dictionary) {
+    for (i, (key, member)) in dictionary.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        out.push_str(key);
+        match member {
+            // `true` is implied by a key without a value
+            Member::Item(Item {
+                bare_item: BareItem::Boolean(true),
+                params,
+            }) => write_parameters(out, params),
+            member => {
+                out.push('=');
+                write_member(out, member);
+            }
+        }
+    }
+}
+
+pub(crate) fn write_member(out: &mut String, member: &Member) {
+    match member {
+        Member::Item(item) => write_item(out, item),
+        Member::InnerList(items, params) => {
+            out.push('(');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+                write_item(out, item);
+            }
+            out.push(')');
+            write_parameters(out, params);
+        }
+    }
+}
+
+pub(crate) fn write_item(out: &mut String, item: &Item) {
+    write_bare_item(out, &item.bare_item);
+    write_parameters(out, &item.params);
+}
+
+pub(crate) fn write_parameters(out: &mut String, params: &Parameters) {
+    for (key, value) in params {
+        out.push(';');
+        out.push_str(key);
+        if *value != BareItem::Boolean(true) {
+            out.push('=');
+            write_bare_item(out, value);
+        }
+    }
+}
+
+pub(crate) fn write_bare_item(out: &mut String, bare_item: &BareItem) {
+    match bare_item {
+        BareItem::Integer(i) => {
+            let _ = write!(out, "{}", i);
+        }
+        BareItem::Decimal(thousandths) => {
+            if *thousandths < 0 {
+                out.push('-');
+            }
+            let abs = thousandths.unsigned_abs();
+            let fraction = format!("{:03}", abs % 1000);
+            let fraction = fraction.trim_end_matches('0');
+            let fraction = if fraction.is_empty() { "0" } else { fraction };
+            let _ = write!(out, "{}.{}", abs / 1000, fraction);
+        }
+        BareItem::String(s) => {
+            out.push('"');
+            for c in s.chars() {
+                if c == '"' || c == '\\' {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+            out.push('"');
+        }
+        BareItem::Token(token) => out.push_str(token),
+        BareItem::ByteSequence(bytes) => {
+            out.push(':');
+            Base64Alphabet::Standard.engine().encode_string(bytes, out);
+            out.push(':');
+        }
+        BareItem::Boolean(b) => out.push_str(if *b { "?1" } else { "?0" }),
+    }
+}
@@ -0,0 +1,30 @@
+// Ephemeral key pairs for tests, so that tests for other key types don't need fixed PEM blobs.
+// Generating RSA keys is slow, so tests that don't care about the key type should keep using a
+// fixed key.
+
+use crate::HttpSigError;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private, Public};
+use openssl::rsa::Rsa;
+
+pub fn generate_rsa(bits: u32) -> Result<(PKey<Private>, PKey<Public>), HttpSigError> {
+    key_pair(PKey::from_rsa(Rsa::generate(bits)?)?)
+}
+
+pub fn generate_ec_p256() -> Result<(PKey<Private>, PKey<Public>), HttpSigError> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    key_pair(PKey::from_ec_key(EcKey::generate(&group)?)?)
+}
+
+// Ed25519 keys can't sign or verify requests yet, since the signing string is always hashed with a
+// separate digest first. The pair only works with OpenSSL's `Signer::new_without_digest` and
+// `Verifier::new_without_digest` directly.
+pub fn generate_ed25519() -> Result<(PKey<Private>, PKey<Public>), HttpSigError> {
+    key_pair(PKey::generate_ed25519()?)
+}
+
+fn key_pair(private_key: PKey<Private>) -> Result<(PKey<Private>, PKey<Public>), HttpSigError> {
+    let public_key = PKey::public_key_from_der(&private_key.public_key_to_der()?)?;
+    Ok((private_key, public_key))
+}
@@ -0,0 +1,80 @@
+use crate::{
+    add_signature_header_with_params, verify_request_parts, verify_request_with_resolver,
+    HttpSigError, KeyResolver, SignatureParams, VerificationResult,
+};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{HasPrivate, HasPublic, PKeyRef};
+use std::ops::Deref;
+
+// A request that has been signed. Functions that must only send signed requests can take this
+// instead of an `http::Request`. It derefs to the request, but doesn't allow changing it, since
+// that could invalidate the signature.
+#[derive(Debug)]
+pub struct SignedRequest<T>(http::Request<T>);
+
+impl<T> SignedRequest<T> {
+    pub fn sign(
+        mut request: http::Request<T>,
+        params: &SignatureParams,
+        private_key: &PKeyRef<impl HasPrivate>,
+    ) -> Result<Self, HttpSigError> {
+        add_signature_header_with_params(&mut request, params, private_key)?;
+        Ok(SignedRequest(request))
+    }
+
+    pub fn into_inner(self) -> http::Request<T> {
+        self.0
+    }
+}
+
+impl<T> Deref for SignedRequest<T> {
+    type Target = http::Request<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+// A request whose signature has been verified, along with what was verified. Handlers that must
+// only act on verified requests can take this instead of an `http::Request`.
+#[derive(Debug)]
+pub struct VerifiedRequest<T> {
+    request: http::Request<T>,
+    result: VerificationResult,
+}
+
+impl<T> VerifiedRequest<T> {
+    // Fails with `InvalidSignature` if the signature doesn't match
+    pub fn verify(
+        request: http::Request<T>,
+        digest: MessageDigest,
+        public_key: &PKeyRef<impl HasPublic>,
+    ) -> Result<Self, HttpSigError> {
+        let (parts, body) = request.into_parts();
+        let result = verify_request_parts(&parts, digest, public_key)?;
+        Ok(VerifiedRequest {
+            request: http::Request::from_parts(parts, body),
+            result,
+        })
+    }
+
+    pub fn verify_with_resolver(
+        request: http::Request<T>,
+        resolver: &impl KeyResolver,
+    ) -> Result<Self, HttpSigError> {
+        let result = verify_request_with_resolver(&request, resolver)?;
+        Ok(VerifiedRequest { request, result })
+    }
+
+    pub fn request(&self) -> &http::Request<T> {
+        &self.request
+    }
+
+    pub fn result(&self) -> &VerificationResult {
+        &self.result
+    }
+
+    pub fn into_parts(self) -> (http::Request<T>, VerificationResult) {
+        (self.request, self.result)
+    }
+}
@@ -0,0 +1,127 @@
+// Signature verification for `warp` routes. See the README for how to combine
+// `signature_required` with other filters and turn its rejection into a response.
+//
+// warp uses a newer version of the `http` crate than this library, so the request head is copied
+// into an `http` 0.1 request before verifying.
+
+use crate::request::request_head;
+use crate::{
+    verify_request_with_resolver_and_options, HttpSigError, KeyResolver, VerificationOptions,
+    VerificationResult,
+};
+use openssl::hash::MessageDigest;
+use std::fmt;
+use std::sync::Arc;
+use warp::http::{HeaderMap, Method, StatusCode};
+use warp::path::FullPath;
+use warp::reject::{Reject, Rejection};
+use warp::Filter;
+
+pub type VerifiedSignature = VerificationResult;
+
+#[derive(Debug)]
+pub struct HttpSigRejection(pub HttpSigError);
+
+impl HttpSigRejection {
+    pub fn error(&self) -> &HttpSigError {
+        &self.0
+    }
+
+    // `401 Unauthorized` if the request wasn't signed by a known key, or `400 Bad Request` if the
+    // signature couldn't be read at all
+    pub fn status(&self) -> StatusCode {
+        match self.0 {
+            HttpSigError::MissingSignature
+            | HttpSigError::InvalidSignature
+            | HttpSigError::UnknownKey { .. }
+            | HttpSigError::MissingHeader { .. }
+            | HttpSigError::SignatureLengthMismatch { .. }
+            | HttpSigError::DigestMismatch { .. }
+            | HttpSigError::ClockSkew { .. }
+            | HttpSigError::DateNotSigned
+            | HttpSigError::SignatureExpired { .. }
+            | HttpSigError::SignatureTooOld { .. } => StatusCode::UNAUTHORIZED,
+            HttpSigError::OpenSsl(_) | HttpSigError::Io(_) | HttpSigError::Fmt(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl fmt::Display for HttpSigRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Reject for HttpSigRejection {}
+
+// Verifies with the default `VerificationOptions`, which don't check when the request was signed,
+// so a captured request is accepted again whenever it's replayed. Use
+// `signature_required_with_options` with `max_clock_skew` or `max_signature_age` set to prevent
+// that.
+pub fn signature_required<R>(
+    key_store: R,
+) -> impl Filter<Extract = (VerifiedSignature,), Error = Rejection> + Clone
+where
+    R: KeyResolver + Send + Sync + 'static,
+{
+    signature_required_with_options(key_store, VerificationOptions::default())
+}
+
+pub fn signature_required_with_options<R>(
+    key_store: R,
+    options: VerificationOptions,
+) -> impl Filter<Extract = (VerifiedSignature,), Error = Rejection> + Clone
+where
+    R: KeyResolver + Send + Sync + 'static,
+{
+    let key_store = Arc::new(key_store);
+    let options = Arc::new(options);
+
+    warp::method()
+        .and(warp::path::full())
+        .and(
+            warp::query::raw()
+                .map(Some)
+                .or(warp::any().map(|| None))
+                .unify(),
+        )
+        .and(warp::header::headers_cloned())
+        .and_then(
+            move |method: Method, path: FullPath, query: Option<String>, headers: HeaderMap| {
+                let result = to_request(&method, path.as_str(), query.as_deref(), &headers)
+                    .and_then(|request| {
+                        verify_request_with_resolver_and_options(
+                            &request,
+                            &key_store,
+                            MessageDigest::sha256(),
+                            &options,
+                        )
+                    });
+
+                async move { result.map_err(|e| warp::reject::custom(HttpSigRejection(e))) }
+            },
+        )
+}
+
+fn to_request(
+    method: &Method,
+    path: &str,
+    query: Option<&str>,
+    headers: &HeaderMap,
+) -> Result<http::Request<()>, HttpSigError> {
+    let uri = match query {
+        Some(query) => format!("{}?{}", path, query),
+        None => path.to_owned(),
+    };
+
+    request_head(
+        method.as_str(),
+        &uri,
+        headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_bytes())),
+    )
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "actix-web")]
+
+mod common;
+
+use crate::common::*;
+use actix_web::http::StatusCode;
+use actix_web::web::ReqData;
+use actix_web::{test, web, App, HttpResponse};
+use httpsig::{HttpSigMiddleware, InMemoryKeyStore, VerificationOptions, VerificationResult};
+use openssl::pkey::PKey;
+use std::time::{Duration, UNIX_EPOCH};
+
+// https://tools.ietf.org/html/draft-cavage-http-signatures-10#appendix-C.2
+const BASIC_SIGNATURE: &str = r#"keyId="Test",algorithm="rsa-sha256",headers="(request-target) host date",signature="qdx+H7PHHDZgy4y/Ahn9Tny9V3GP6YgBPyUXMmoxWtLbHpUnXS2mg2+SbrQDMCJypxBLSPQR2aAjn7ndmw2iicw3HMbe8VfEdKFYRqzic+efkb3nndiv/x1xSHDJWeSWkx3ButlYSuBskLu6kd9Fswtemr3lgdDEmn04swr2Os0=""#;
+
+async fn handler(result: ReqData<VerificationResult>) -> HttpResponse {
+    HttpResponse::Ok().body(result.into_inner().key_id)
+}
+
+fn appendix_request() -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/foo?param=value&pet=dog")
+        .insert_header(("host", "example.com"))
+        .insert_header(("date", "Sun, 05 Jan 2014 21:31:40 GMT"))
+        .insert_header(("content-type", "application/json"))
+}
+
+fn middleware() -> Result<HttpSigMiddleware<InMemoryKeyStore>, BoxError> {
+    let mut key_store = InMemoryKeyStore::new();
+    key_store.insert("Test", PKey::public_key_from_pem(PUBLIC_PEM)?);
+    Ok(HttpSigMiddleware::new(key_store))
+}
+
+fn status_and_body(request: test::TestRequest) -> Result<(StatusCode, Vec<u8>), BoxError> {
+    status_and_body_with(middleware()?, request)
+}
+
+fn status_and_body_with(
+    middleware: HttpSigMiddleware<InMemoryKeyStore>,
+    request: test::TestRequest,
+) -> Result<(StatusCode, Vec<u8>), BoxError> {
+    actix_web::rt::System::new().block_on(async move {
+        let app = test::init_service(
+            App::new()
+                .wrap(middleware)
+                .route("/foo", web::post().to(handler)),
+        )
+        .await;
+
+        let response = test::call_service(&app, request.to_request()).await;
+        let status = response.status();
+        let body = test::read_body(response).await;
+        Ok((status, body.to_vec()))
+    })
+}
+
+#[test]
+fn middleware_accepts_valid_signature() -> Result<(), BoxError> {
+    let request = appendix_request().insert_header(("signature", BASIC_SIGNATURE));
+    assert_eq!(
+        status_and_body(request)?,
+        (StatusCode::OK, b"Test".to_vec())
+    );
+    Ok(())
+}
+
+#[test]
+fn middleware_rejects_missing_signature() -> Result<(), BoxError> {
+    let (status, _) = status_and_body(appendix_request())?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    Ok(())
+}
+
+#[test]
+fn middleware_rejects_tampered_request() -> Result<(), BoxError> {
+    let request = appendix_request()
+        .insert_header(("date", "Sun, 05 Jan 2014 21:31:41 GMT"))
+        .insert_header(("signature", BASIC_SIGNATURE));
+    let (status, _) = status_and_body(request)?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    Ok(())
+}
+
+#[test]
+fn middleware_with_options_rejects_replayed_request() -> Result<(), BoxError> {
+    let options = |now: u64| VerificationOptions {
+        max_clock_skew: Some(Duration::from_secs(300)),
+        now: Some(UNIX_EPOCH + Duration::from_secs(now)),
+        ..VerificationOptions::default()
+    };
+    let request = || appendix_request().insert_header(("signature", BASIC_SIGNATURE));
+
+    let accepting = middleware()?.with_options(options(1_388_957_500 + 300));
+    let (status, _) = status_and_body_with(accepting, request())?;
+    assert_eq!(status, StatusCode::OK);
+
+    let rejecting = middleware()?.with_options(options(1_388_957_500 + 301));
+    let (status, _) = status_and_body_with(rejecting, request())?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    Ok(())
+}
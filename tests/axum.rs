@@ -0,0 +1,104 @@
+#![cfg(feature = "axum")]
+
+mod common;
+
+use crate::common::*;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use futures::executor::block_on;
+use httpsig::axum::{HttpSigState, SharedKeyResolver, SignedRequest};
+use httpsig::VerificationOptions;
+use openssl::pkey::PKey;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use tower::ServiceExt;
+
+// https://tools.ietf.org/html/draft-cavage-http-signatures-10#appendix-C.2
+const BASIC_SIGNATURE: &str = r#"keyId="Test",algorithm="rsa-sha256",headers="(request-target) host date",signature="qdx+H7PHHDZgy4y/Ahn9Tny9V3GP6YgBPyUXMmoxWtLbHpUnXS2mg2+SbrQDMCJypxBLSPQR2aAjn7ndmw2iicw3HMbe8VfEdKFYRqzic+efkb3nndiv/x1xSHDJWeSWkx3ButlYSuBskLu6kd9Fswtemr3lgdDEmn04swr2Os0=""#;
+
+async fn handler(SignedRequest(info): SignedRequest) -> String {
+    format!("{} {}", info.key_id, info.signed_headers.join(" "))
+}
+
+fn key_resolver() -> Result<SharedKeyResolver, BoxError> {
+    let mut key_store = httpsig::InMemoryKeyStore::new();
+    key_store.insert("Test", PKey::public_key_from_pem(PUBLIC_PEM)?);
+    Ok(Arc::new(key_store))
+}
+
+fn app() -> Result<Router, BoxError> {
+    Ok(Router::new()
+        .route("/foo", post(handler))
+        .with_state(key_resolver()?))
+}
+
+fn appendix_request(signature: Option<&str>) -> Result<Request<Body>, BoxError> {
+    let mut builder = Request::post("/foo?param=value&pet=dog")
+        .header("host", "example.com")
+        .header("date", "Sun, 05 Jan 2014 21:31:40 GMT")
+        .header("content-type", "application/json");
+    if let Some(signature) = signature {
+        builder = builder.header("signature", signature);
+    }
+    Ok(builder.body(Body::from(r#"{"hello": "world"}"#))?)
+}
+
+fn send(request: Request<Body>) -> Result<(StatusCode, String), BoxError> {
+    send_to(app()?, request)
+}
+
+fn send_to(app: Router, request: Request<Body>) -> Result<(StatusCode, String), BoxError> {
+    block_on(async {
+        let response = app.oneshot(request).await?;
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        Ok((status, String::from_utf8(body.to_vec())?))
+    })
+}
+
+#[test]
+fn extractor_accepts_valid_signature() -> Result<(), BoxError> {
+    let (status, body) = send(appendix_request(Some(BASIC_SIGNATURE))?)?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "Test (request-target) host date");
+    Ok(())
+}
+
+#[test]
+fn extractor_rejects_missing_signature() -> Result<(), BoxError> {
+    let (status, _) = send(appendix_request(None)?)?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    Ok(())
+}
+
+#[test]
+fn extractor_rejects_unknown_key() -> Result<(), BoxError> {
+    let signature = BASIC_SIGNATURE.replace(r#"keyId="Test""#, r#"keyId="Other""#);
+    let (status, body) = send(appendix_request(Some(&signature))?)?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body, "unknown key id 'Other'");
+    Ok(())
+}
+
+#[test]
+fn extractor_with_options_rejects_replayed_request() -> Result<(), BoxError> {
+    let app = |now: u64| -> Result<Router, BoxError> {
+        let state = HttpSigState::new(key_resolver()?).with_options(VerificationOptions {
+            max_clock_skew: Some(Duration::from_secs(300)),
+            now: Some(UNIX_EPOCH + Duration::from_secs(now)),
+            ..VerificationOptions::default()
+        });
+        Ok(Router::new().route("/foo", post(handler)).with_state(state))
+    };
+
+    let request = || appendix_request(Some(BASIC_SIGNATURE));
+    let (status, _) = send_to(app(1_388_957_500 + 300)?, request()?)?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send_to(app(1_388_957_500 + 301)?, request()?)?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body, "date header is 301s away from the current time");
+    Ok(())
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use crate::common::*;
+use httpsig::{HttpSigError, VerificationOptions};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// `HTTP_REQUEST`'s date, Sun, 05 Jan 2014 21:31:40 GMT
+const REQUEST_TIME: u64 = 1_388_957_500;
+
+fn options(seconds_after_request: u64) -> VerificationOptions {
+    VerificationOptions {
+        max_clock_skew: Some(Duration::from_secs(300)),
+        now: Some(UNIX_EPOCH + Duration::from_secs(REQUEST_TIME + seconds_after_request)),
+        ..Default::default()
+    }
+}
+
+fn verify_with_date(date: &str, options: &VerificationOptions) -> Result<bool, HttpSigError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM).unwrap();
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM).unwrap();
+
+    let mut request = parse_request(HTTP_REQUEST);
+    request.headers_mut().insert(
+        "date",
+        http::header::HeaderValue::from_bytes(date.as_bytes()).unwrap(),
+    );
+    httpsig::add_signature_header(&mut request, "Test", MessageDigest::sha256(), &private_key)?;
+
+    httpsig::verify_request_with_options(&request, MessageDigest::sha256(), &public_key, options)
+}
+
+#[test]
+fn imf_fixdate() -> Result<(), BoxError> {
+    assert!(verify_with_date(
+        "Sun, 05 Jan 2014 21:31:40 GMT",
+        &options(10)
+    )?);
+    Ok(())
+}
+
+#[test]
+fn rfc850_date() -> Result<(), BoxError> {
+    assert!(verify_with_date(
+        "Sunday, 05-Jan-14 21:31:40 GMT",
+        &options(10)
+    )?);
+    Ok(())
+}
+
+#[test]
+fn asctime_date() -> Result<(), BoxError> {
+    assert!(verify_with_date("Sun Jan  5 21:31:40 2014", &options(10))?);
+    Ok(())
+}
+
+#[test]
+fn date_too_old() {
+    match verify_with_date("Sun, 05 Jan 2014 21:31:40 GMT", &options(301)) {
+        Err(HttpSigError::ClockSkew { skew }) => assert_eq!(skew, Duration::from_secs(301)),
+        other => panic!("expected ClockSkew, got {:?}", other),
+    }
+}
+
+#[test]
+fn date_in_future() {
+    let options = VerificationOptions {
+        now: Some(UNIX_EPOCH + Duration::from_secs(REQUEST_TIME - 600)),
+        ..options(0)
+    };
+
+    match verify_with_date("Sun, 05 Jan 2014 21:31:40 GMT", &options) {
+        Err(HttpSigError::ClockSkew { skew }) => assert_eq!(skew, Duration::from_secs(600)),
+        other => panic!("expected ClockSkew, got {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_date() {
+    match verify_with_date("yesterday", &options(0)) {
+        Err(HttpSigError::InvalidDate(_)) => {}
+        other => panic!("expected InvalidDate, got {:?}", other),
+    }
+}
+
+#[test]
+fn non_ascii_date() {
+    match verify_with_date("Sun, 05 Jan 2014 21:31:40 GMT\u{e9}", &options(0)) {
+        Err(HttpSigError::InvalidDate(_)) => {}
+        other => panic!("expected InvalidDate, got {:?}", other),
+    }
+}
+
+// An unsigned `Date` could be changed to pass the check, so it isn't trusted
+#[test]
+fn date_not_signed() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+
+    let mut request = parse_request(HTTP_REQUEST);
+    let params = httpsig::SignatureParams {
+        headers: Some(vec!["(request-target)".to_owned(), "host".to_owned()]),
+        ..httpsig::SignatureParams::new("Test", MessageDigest::sha256())
+    };
+    httpsig::add_signature_header_with_params(&mut request, &params, &private_key)?;
+
+    match httpsig::verify_request_with_options(
+        &request,
+        MessageDigest::sha256(),
+        &public_key,
+        &options(0),
+    ) {
+        Err(HttpSigError::DateNotSigned) => {}
+        other => panic!("expected DateNotSigned, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn skew_not_checked_by_default() -> Result<(), BoxError> {
+    let options = VerificationOptions {
+        now: Some(SystemTime::now()),
+        ..Default::default()
+    };
+    assert!(verify_with_date("Sun, 05 Jan 2014 21:31:40 GMT", &options)?);
+    Ok(())
+}
@@ -0,0 +1,94 @@
+// Runs the vectors in `tests/vectors`, see the README there for the format
+mod common;
+
+use crate::common::*;
+use httpsig::{LineFolding, SignatureParams};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn vectors_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors")
+}
+
+fn field<'a>(vector: &'a Value, name: &str) -> Result<&'a str, BoxError> {
+    vector[name]
+        .as_str()
+        .ok_or_else(|| format!("missing string field '{}'", name).into())
+}
+
+fn run_vector(vector: &Value) -> Result<(), BoxError> {
+    let dir = vectors_dir();
+    let digest = match vector["digest"].as_str().unwrap_or("sha256") {
+        "sha256" => MessageDigest::sha256(),
+        "sha384" => MessageDigest::sha384(),
+        "sha512" => MessageDigest::sha512(),
+        other => return Err(format!("unknown digest '{}'", other).into()),
+    };
+    let signature_header = field(vector, "signature_header")?;
+    let expected = httpsig::parse_signature_parts(signature_header)?;
+    let mut request =
+        httpsig::parse_request(field(vector, "request")?.as_bytes(), LineFolding::Reject)?;
+
+    if let Some(private_key) = vector["private_key"].as_str() {
+        let private_key = PKey::private_key_from_pem(&fs::read(dir.join(private_key))?)?;
+        let headers = match vector["headers"].as_array() {
+            Some(headers) => headers
+                .iter()
+                .map(|h| h.as_str().map(str::to_owned).ok_or("non-string header"))
+                .collect::<Result<_, _>>()?,
+            None => vec!["date".to_owned()],
+        };
+        let params = SignatureParams {
+            headers: Some(headers),
+            ..SignatureParams::new(expected.key_id, digest)
+        };
+
+        let header = httpsig::create_signature_header_with_params(&request, &params, &private_key)?;
+        let signed = httpsig::parse_signature_parts(&header)?;
+        if signed.signature != expected.signature {
+            return Err(format!("signed as {}", header).into());
+        }
+    }
+
+    let public_key = PKey::public_key_from_pem(&fs::read(dir.join(field(vector, "public_key")?))?)?;
+    request
+        .headers_mut()
+        .insert("signature", signature_header.parse()?);
+    let verified = httpsig::verify_request(&request, digest, &public_key)?;
+    if Some(verified) != vector["verifies"].as_bool() {
+        return Err(format!("verification returned {}", verified).into());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn conformance_vectors() -> Result<(), BoxError> {
+    let mut paths = fs::read_dir(vectors_dir())?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+    paths.sort();
+    assert!(!paths.is_empty(), "no vectors found");
+
+    let mut failures = Vec::new();
+    for path in &paths {
+        let result = fs::read(path)
+            .map_err(BoxError::from)
+            .and_then(|json| Ok(serde_json::from_slice(&json)?))
+            .and_then(|vector| run_vector(&vector));
+        if let Err(e) = result {
+            failures.push(format!("{}: {}", path.display(), e));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "failed vectors:\n{}",
+        failures.join("\n")
+    );
+    Ok(())
+}
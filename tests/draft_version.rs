@@ -0,0 +1,133 @@
+mod common;
+
+use crate::common::*;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use httpsig::{DraftVersion, HttpSigError, VerificationOptions};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+
+const CREATED: u64 = 1402170695;
+const EXPIRES: u64 = 1402170995;
+
+// Signs `(request-target) (created) (expires) host` by hand, since signing doesn't support the
+// draft 12 pseudo-headers
+fn signed_request(algorithm: &str) -> Result<http::Request<&'static [u8]>, BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let mut request = parse_request(HTTP_REQUEST);
+
+    let signing_string = format!(
+        "(request-target): post /foo?param=value&pet=dog\n\
+         (created): {}\n\
+         (expires): {}\n\
+         host: example.com",
+        CREATED, EXPIRES
+    );
+    let mut signer = Signer::new(MessageDigest::sha512(), &private_key)?;
+    signer.update(signing_string.as_bytes())?;
+    let signature = STANDARD.encode(&signer.sign_to_vec()?);
+
+    let header = format!(
+        r#"keyId="Test",algorithm="{}",created={},expires={},headers="(request-target) (created) (expires) host",signature="{}""#,
+        algorithm, CREATED, EXPIRES, signature
+    );
+    request.headers_mut().insert("signature", header.parse()?);
+    Ok(request)
+}
+
+fn options(draft_version: DraftVersion) -> VerificationOptions {
+    VerificationOptions {
+        draft_version,
+        // Before `expires`
+        now: Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(CREATED)),
+        ..VerificationOptions::default()
+    }
+}
+
+// With no `Date` signed, the clock skew check uses `created`
+#[test]
+fn clock_skew_checks_created() -> Result<(), BoxError> {
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let request = signed_request("hs2019")?;
+    let skewed = |seconds: u64| VerificationOptions {
+        max_clock_skew: Some(std::time::Duration::from_secs(60)),
+        now: Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(CREATED + seconds)),
+        ..options(DraftVersion::Draft12)
+    };
+    let verify = |options: &VerificationOptions| {
+        httpsig::verify_request_with_options(
+            &request,
+            MessageDigest::sha512(),
+            &public_key,
+            options,
+        )
+    };
+
+    assert!(verify(&skewed(30))?);
+    match verify(&skewed(120)) {
+        Err(HttpSigError::ClockSkew { skew }) => assert_eq!(skew.as_secs(), 120),
+        other => panic!("expected ClockSkew, got {:?}", other),
+    }
+    // Draft 11 doesn't sign `created`
+    match verify(&VerificationOptions {
+        draft_version: DraftVersion::Draft11,
+        ..skewed(30)
+    }) {
+        Err(HttpSigError::DateNotSigned) => {}
+        other => panic!("expected DateNotSigned, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn created_and_expires_pseudo_headers() -> Result<(), BoxError> {
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let request = signed_request("hs2019")?;
+
+    assert!(httpsig::verify_request_with_options(
+        &request,
+        MessageDigest::sha512(),
+        &public_key,
+        &options(DraftVersion::Draft12)
+    )?);
+
+    // Draft 11 has no such pseudo-headers, so they're missing from the request
+    assert!(!httpsig::verify_request_with_options(
+        &request,
+        MessageDigest::sha512(),
+        &public_key,
+        &options(DraftVersion::Draft11)
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn created_with_rsa_algorithm() -> Result<(), BoxError> {
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let request = signed_request("rsa-sha512")?;
+
+    match httpsig::verify_request_with_options(
+        &request,
+        MessageDigest::sha512(),
+        &public_key,
+        &options(DraftVersion::Draft12),
+    ) {
+        Err(HttpSigError::PseudoHeaderNotAllowed { name, algorithm }) => {
+            assert_eq!(name, "(created)");
+            assert_eq!(algorithm, "rsa-sha512");
+        }
+        other => panic!("expected PseudoHeaderNotAllowed, got {:?}", other),
+    }
+
+    assert!(!httpsig::verify_request_with_options(
+        &request,
+        MessageDigest::sha512(),
+        &public_key,
+        &options(DraftVersion::Draft11)
+    )?);
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use crate::common::*;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use httpsig::HttpSigError;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+
+// Errors from other crates keep their message, with some context
+#[test]
+fn display_wrapped_errors() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let mut request = parse_request(HTTP_REQUEST);
+
+    request.headers_mut().remove("date");
+    let params = httpsig::SignatureParams {
+        headers: Some(vec!["date".to_owned()]),
+        ..httpsig::SignatureParams::new("Test", MessageDigest::sha256())
+    };
+    let error =
+        httpsig::add_signature_header_with_params(&mut request, &params, &private_key).unwrap_err();
+    assert_eq!(error.to_string(), "required header 'date' is not present");
+
+    let error = HttpSigError::from(STANDARD.decode("!").unwrap_err());
+    assert!(error.to_string().starts_with("invalid base64 signature: "));
+
+    let error = HttpSigError::from("\n".parse::<http::header::HeaderValue>().unwrap_err());
+    assert!(error.to_string().starts_with("invalid header value: "));
+
+    Ok(())
+}
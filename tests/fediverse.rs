@@ -0,0 +1,289 @@
+mod common;
+
+use crate::common::*;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use std::time::{Duration, UNIX_EPOCH};
+
+// An actor's `publicKeyPem`, as served in the `publicKey` object of their ActivityPub profile
+const ACTOR_PUBLIC_PEM: &[u8] = br#"-----BEGIN PUBLIC KEY-----
+MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEArcSxp4ugiZ9H5FECgV/L
+YTUcsNjWFXnx7v4NfsB+c66KbS367RfacY3aLmd4w/gjcHmrVDAvnkb8WZKkmeal
+4CXQcajfeI5cbEG2pviym4NRRJJrUAeVishzsdzK2dn8ukrXl1AEM5Jvg0sJsokV
+C44r12p30t5CgHZKi10eMO1lXaGl5q6/6nrtNl8EhLW9p7WvPu7AoBh7Y2yOTM3Y
+w8JLLywsEm6vKeklimzC7at74+dZL9JeV8vtSzD8y/lr2rpW+ifW19iUWCWBHRjl
+UzuhzROjHjfs0wqi9TvRwS9CXLMx7ReJ6mGJuMBjtIN5ixHPVudMOXTA0lTe8qk2
+ZQIDAQAB
+-----END PUBLIC KEY-----"#;
+
+// A `Follow` activity delivered to an inbox, in the shape Mastodon sends it
+const INBOX_REQUEST: &[u8] = br#"POST /users/bob/inbox HTTP/1.1
+Host: social.example
+Date: Tue, 07 Jun 2022 18:32:10 GMT
+Digest: SHA-256=+ggloFQVdRWvdDat5jyKEbg6hv6b1N5IPDB4Ibkmbn4=
+Content-Type: application/activity+json
+Content-Length: 213
+
+{"@context":"https://www.w3.org/ns/activitystreams","id":"https://mastodon.example/users/alice#follows/1","type":"Follow","actor":"https://mastodon.example/users/alice","object":"https://social.example/users/bob"}"#;
+
+const SIGNATURE: &str = "pNcrzezYoF8RT3a6WnAIuWMvHhdv2Q/yBT9h3H8gE1uzPrp2QGqvN2TGwCq4CZMui3nJz9THAU63+tpl5bn9s11imKXsxr//vHzR25kR21VEPBHWHVd0Aj+jnGUDTtIUf4X3DqwMld3O9Xe/anV5ZDGuWHhJovJEKZQ8/rgOISJChvGtG8kqzKBJW0uauCXTK51oh9ZxcLuv69+uFVumFHpEycL0aQ72Cf03ybj8LtL/nwK6o4OCWtMO5Sax4XxhpsatGxFddYk/YkMC+TxnNBqiQnCojjnznP/haExmJOwQUQrivnQQI2MWx5lLTcf8fLS1PjDG0H117HKp3enF/A==";
+
+fn verify_inbox_request(signature_header: &str) -> Result<bool, BoxError> {
+    let public_key = PKey::public_key_from_pem(ACTOR_PUBLIC_PEM)?;
+    let mut request = httpsig::parse_request(INBOX_REQUEST, httpsig::LineFolding::Reject)?;
+    request
+        .headers_mut()
+        .insert("signature", signature_header.parse()?);
+
+    Ok(
+        httpsig::verify_request(&request, MessageDigest::sha256(), &public_key)?
+            && httpsig::verify_digest_header(&request)?,
+    )
+}
+
+#[test]
+fn verify_mastodon() -> Result<(), BoxError> {
+    assert!(verify_inbox_request(&format!(
+        r#"keyId="https://mastodon.example/users/alice#main-key",algorithm="rsa-sha256",headers="(request-target) host date digest",signature="{}""#,
+        SIGNATURE
+    ))?);
+
+    Ok(())
+}
+
+// Other fediverse servers put a space after each comma
+#[test]
+fn verify_spaced_parameters() -> Result<(), BoxError> {
+    assert!(verify_inbox_request(&format!(
+        r#"keyId="https://mastodon.example/users/alice#main-key", algorithm="rsa-sha256", headers="(request-target) host date digest", signature="{}""#,
+        SIGNATURE
+    ))?);
+
+    Ok(())
+}
+
+#[test]
+fn verify_tampered_body() -> Result<(), BoxError> {
+    let mut request = httpsig::parse_request(INBOX_REQUEST, httpsig::LineFolding::Reject)?;
+    assert!(httpsig::verify_digest_header(&request)?);
+
+    request.body_mut().extend_from_slice(b" ");
+    assert!(!httpsig::verify_digest_header(&request)?);
+
+    request.headers_mut().remove("digest");
+    assert!(!httpsig::verify_digest_header(&request)?);
+
+    // Malformed headers are a mismatch too, not an error
+    for digest in &["SHA-256", "SHA-256=not base64!", "SHA-256=caf\u{e9}"] {
+        request.headers_mut().insert(
+            "digest",
+            http::header::HeaderValue::from_bytes(digest.as_bytes())?,
+        );
+        assert!(!httpsig::verify_digest_header(&request)?, "{}", digest);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn verify_digest_multiple_algorithms() -> Result<(), BoxError> {
+    let mut request = parse_request(HTTP_REQUEST);
+    request.headers_mut().insert(
+        "digest",
+        "MD5=unsupported, SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=".parse()?,
+    );
+
+    assert!(httpsig::verify_digest_header(&request)?);
+
+    Ok(())
+}
+
+#[test]
+fn streaming_digest() -> Result<(), BoxError> {
+    use httpsig::DigestVerifier;
+    use std::io::Write;
+
+    let body = br#"{"hello": "world"}"#;
+    let sha256 = "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=";
+    let sha512 = httpsig::compute_rfc3230_digest(body, httpsig::DigestAlgorithm::Sha512)?;
+
+    let stream = |chunk_size: usize| -> Result<DigestVerifier, BoxError> {
+        let mut verifier = DigestVerifier::new()?;
+        for chunk in body.chunks(chunk_size) {
+            verifier.write_all(chunk)?;
+        }
+        Ok(verifier)
+    };
+
+    for &chunk_size in &[1, 5, body.len()] {
+        assert!(stream(chunk_size)?.verify(sha256)?);
+        assert!(stream(chunk_size)?.verify(&sha512)?);
+        assert!(stream(chunk_size)?.verify(&format!("MD5=unsupported, {}", sha256))?);
+    }
+
+    let mut truncated = DigestVerifier::new()?;
+    truncated.update(&body[..body.len() - 1])?;
+    assert!(!truncated.verify(sha256)?);
+    assert!(!stream(4)?.verify("SHA-256=Y48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=")?);
+    assert!(!stream(4)?.verify("MD5=unsupported")?);
+
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn streaming_digest_async() -> Result<(), BoxError> {
+    use std::pin::Pin;
+    use tokio::io::AsyncWrite;
+
+    let body = br#"{"hello": "world"}"#;
+    let mut verifier = httpsig::DigestVerifier::new()?;
+    for chunk in body.chunks(4) {
+        let written = futures::executor::block_on(futures::future::poll_fn(|cx| {
+            Pin::new(&mut verifier).poll_write(cx, chunk)
+        }))?;
+        assert_eq!(written, chunk.len());
+    }
+    assert!(verifier.verify("SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=")?);
+
+    Ok(())
+}
+
+#[test]
+fn digest_formats() -> Result<(), BoxError> {
+    use httpsig::{DigestAlgorithm, DigestFormat};
+
+    let body = br#"{"hello": "world"}"#;
+    assert_eq!(
+        httpsig::compute_rfc3230_digest(body, DigestAlgorithm::Sha256)?,
+        "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE="
+    );
+    assert_eq!(
+        httpsig::compute_rfc9530_digest(body, DigestAlgorithm::Sha256)?,
+        "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:"
+    );
+    assert_eq!(
+        httpsig::compute_rfc9530_digest(body, DigestAlgorithm::Sha512)?,
+        "sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:"
+    );
+
+    let mut request = parse_request(HTTP_REQUEST);
+    request.headers_mut().remove("digest");
+    httpsig::add_content_digest_header(
+        &mut request,
+        DigestAlgorithm::Sha256,
+        DigestFormat::Rfc9530,
+    )?;
+    assert_eq!(
+        request.headers().get("content-digest").unwrap(),
+        "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:"
+    );
+    assert!(request.headers().get("digest").is_none());
+
+    httpsig::add_content_digest_header(
+        &mut request,
+        DigestAlgorithm::Sha512,
+        DigestFormat::Rfc3230,
+    )?;
+    assert!(httpsig::verify_digest_header(&request)?);
+
+    Ok(())
+}
+
+#[test]
+fn sign_activitypub_request() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+
+    let mut request = http::Request::post("https://social.example/users/bob/inbox")
+        .header("content-type", "application/activity+json")
+        .body(br#"{"type":"Follow"}"#.to_vec())?;
+
+    httpsig::sign_activitypub_request(
+        &mut request,
+        "https://mastodon.example/users/alice#main-key",
+        &private_key,
+    )?;
+
+    let parts = httpsig::find_signature_in_request(&request)?;
+    assert_eq!(
+        parts.key_id,
+        "https://mastodon.example/users/alice#main-key"
+    );
+    assert_eq!(parts.headers, Some("(request-target) host date digest"));
+    assert_eq!(request.headers().get("host").unwrap(), "social.example");
+    assert!(request.headers().contains_key("date"));
+
+    assert!(httpsig::verify_request(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+    assert!(httpsig::verify_digest_header(&request)?);
+
+    // The inbox sees the request in origin form
+    let mut received = http::Request::post("/users/bob/inbox").body(request.body().clone())?;
+    *received.headers_mut() = request.headers().clone();
+    assert!(httpsig::verify_request(
+        &received,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn sign_activitypub_request_at() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let now = UNIX_EPOCH + Duration::from_secs(1_388_957_500);
+
+    let sign = || -> Result<http::Request<Vec<u8>>, BoxError> {
+        let mut request = http::Request::post("https://social.example/users/bob/inbox")
+            .body(br#"{"type":"Follow"}"#.to_vec())?;
+        httpsig::sign_activitypub_request_at(
+            &mut request,
+            "https://mastodon.example/users/alice#main-key",
+            &private_key,
+            now,
+        )?;
+        Ok(request)
+    };
+
+    let (first, second) = (sign()?, sign()?);
+    assert_eq!(
+        first.headers().get("date").unwrap(),
+        "Sun, 05 Jan 2014 21:31:40 GMT"
+    );
+    assert_eq!(first.headers(), second.headers());
+
+    Ok(())
+}
+
+#[test]
+fn key_id_transform() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+
+    let params = httpsig::SignatureParams::new(
+        "https://mastodon.example/users/alice",
+        MessageDigest::sha256(),
+    )
+    .with_key_id_transform(|actor| format!("{}#main-key", actor));
+
+    let mut request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header_with_params(&mut request, &params, &private_key)?;
+
+    let parts = httpsig::find_signature_in_request(&request)?;
+    assert_eq!(
+        parts.key_id,
+        "https://mastodon.example/users/alice#main-key"
+    );
+    assert!(httpsig::verify_request(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use crate::common::*;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+
+#[test]
+fn encode_form() {
+    assert_eq!(
+        httpsig::encode_form(&[("pet", "dog"), ("name", "Mr. Fluffy"), ("pet", "cat")]),
+        "name=Mr.+Fluffy&pet=dog&pet=cat"
+    );
+    assert_eq!(
+        httpsig::encode_form(&[("a&b", "1=2"), ("é", "~")]),
+        "a%26b=1%3D2&%C3%A9=%7E"
+    );
+    assert_eq!(httpsig::encode_form(&[]), "");
+}
+
+#[test]
+fn sign_form_request() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+
+    let mut request = http::Request::builder()
+        .method("POST")
+        .uri("/foo")
+        .header("host", "example.com")
+        .header("date", "Sun, 05 Jan 2014 21:31:40 GMT")
+        .body(Vec::new())?;
+    let params = httpsig::SignatureParams::new("Test", MessageDigest::sha256());
+    httpsig::sign_form_request(
+        &mut request,
+        &[("pet", "dog"), ("name", "Mr. Fluffy")],
+        &params,
+        &private_key,
+    )?;
+
+    assert_eq!(request.body(), b"name=Mr.+Fluffy&pet=dog");
+    assert_eq!(
+        request.headers()["content-type"],
+        "application/x-www-form-urlencoded"
+    );
+    assert_eq!(request.headers()["content-length"], "23");
+    assert_eq!(
+        request.headers()["digest"],
+        httpsig::compute_rfc3230_digest(
+            b"name=Mr.+Fluffy&pet=dog",
+            httpsig::DigestAlgorithm::Sha256
+        )?
+        .as_str()
+    );
+
+    assert!(httpsig::verify_digest_header(&request)?);
+    assert!(httpsig::verify_request(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+    let signature = httpsig::find_signature_in_request(&request)?;
+    assert_eq!(
+        signature.headers,
+        Some("(request-target) host date content-type content-length digest")
+    );
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "http1")]
+
+mod common;
+
+use crate::common::*;
+use httpsig::{HttpSigError, SignableMessage, SignatureParams};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+
+#[test]
+fn sign_http1_request() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let params = SignatureParams::new("Test", MessageDigest::sha256());
+
+    let mut request = http1::Request::post("https://example.com/foo?param=value&pet=dog")
+        .header("Host", "example.com")
+        .header("Date", "Sun, 05 Jan 2014 21:31:40 GMT")
+        .body(())?;
+    httpsig::sign_message(&mut request, &params, &private_key)?;
+    assert!(httpsig::verify_message(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    // The same signature as the same request in `http` 0.1
+    let mut expected = http::Request::post("https://example.com/foo?param=value&pet=dog")
+        .header("Host", "example.com")
+        .header("Date", "Sun, 05 Jan 2014 21:31:40 GMT")
+        .body(())?;
+    httpsig::add_signature_header_with_params(&mut expected, &params, &private_key)?;
+    assert_eq!(
+        request.headers()["signature"].as_bytes(),
+        expected.headers()["signature"].as_bytes()
+    );
+
+    // Tampering is detected
+    request
+        .headers_mut()
+        .insert("date", "Mon, 06 Jan 2014 21:31:40 GMT".parse()?);
+    assert!(!httpsig::verify_message(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn sign_http1_response() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let params = SignatureParams {
+        headers: Some(vec!["date".to_owned(), "content-type".to_owned()]),
+        ..SignatureParams::new("Test", MessageDigest::sha256())
+    };
+
+    let mut response = http1::Response::builder()
+        .header("Date", "Sun, 05 Jan 2014 21:31:40 GMT")
+        .header("Content-Type", "application/json")
+        .body(())?;
+    httpsig::sign_message(&mut response, &params, &private_key)?;
+    assert!(httpsig::verify_message(
+        &response,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn set_http1_header_name_case() -> Result<(), BoxError> {
+    let mut request = http1::Request::post("/inbox").body(())?;
+    request.set_header("X-Signed-By", "Test")?;
+    assert_eq!(request.headers()["x-signed-by"], "Test");
+
+    match request.set_header("x signed by", "Test") {
+        Err(HttpSigError::Http(_)) => {}
+        other => panic!("expected invalid header name, got {:?}", other),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use crate::common::*;
+use httpsig::SignatureParams;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Note {
+    content: &'static str,
+    sensitive: bool,
+}
+
+const NOTE: Note = Note {
+    content: "Hello, world",
+    sensitive: false,
+};
+const NOTE_JSON: &str = r#"{"content":"Hello, world","sensitive":false}"#;
+
+fn parts(content_type: Option<&str>) -> Result<http::request::Parts, BoxError> {
+    let mut builder = http::Request::post("https://example.com/inbox");
+    builder
+        .header("Host", "example.com")
+        .header("Date", "Sun, 05 Jan 2014 21:31:40 GMT");
+    if let Some(content_type) = content_type {
+        builder.header("Content-Type", content_type);
+    }
+    Ok(builder.body(())?.into_parts().0)
+}
+
+#[test]
+fn sign_json_body() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let params = SignatureParams::new("Test", MessageDigest::sha256());
+
+    let request =
+        httpsig::sign_request_with_body(parts(None)?, &NOTE, None, &params, &private_key)?;
+    assert_eq!(request.body(), NOTE_JSON.as_bytes());
+    assert_eq!(request.headers()["content-type"], "application/json");
+    assert_eq!(
+        request.headers()["content-length"],
+        NOTE_JSON.len().to_string().as_str()
+    );
+
+    // `Content-Type`, `Content-Length` and `Digest` are all signed
+    assert!(httpsig::verify_request(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+    assert!(httpsig::verify_digest_header(&request)?);
+    let signature = request.headers()["signature"].to_str()?;
+    assert!(signature.contains("content-type content-length digest"));
+
+    Ok(())
+}
+
+#[test]
+fn sign_json_body_content_type() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let params = SignatureParams::new("Test", MessageDigest::sha256());
+    let activity_json = "application/activity+json";
+
+    // A `Content-Type` that was already set is kept
+    let request = httpsig::sign_request_with_body(
+        parts(Some(activity_json))?,
+        &NOTE,
+        None,
+        &params,
+        &private_key,
+    )?;
+    assert_eq!(request.headers()["content-type"], activity_json);
+
+    // Unless one is given
+    let request = httpsig::sign_request_with_body(
+        parts(Some("text/plain"))?,
+        &NOTE,
+        Some(activity_json),
+        &params,
+        &private_key,
+    )?;
+    assert_eq!(request.headers()["content-type"], activity_json);
+    assert_eq!(request.headers().get_all("content-type").iter().count(), 1);
+
+    Ok(())
+}
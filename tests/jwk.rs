@@ -0,0 +1,105 @@
+#![cfg(feature = "jwk")]
+
+mod common;
+
+use crate::common::*;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, PKeyRef, Private};
+
+fn base64url(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// Signs with `private_key` and checks that the JWK's key verifies it
+fn assert_verifies(
+    private_key: &PKeyRef<Private>,
+    digest: MessageDigest,
+    jwk: &str,
+) -> Result<(), BoxError> {
+    let public_key = httpsig::keys::public_key_from_jwk(jwk)?;
+
+    let mut request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header(&mut request, "Test", digest, private_key)?;
+    assert!(httpsig::verify_request(&request, digest, &public_key)?);
+    Ok(())
+}
+
+#[test]
+fn rsa_jwk() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let rsa = private_key.rsa()?;
+    let jwk = format!(
+        r#"{{"kty":"RSA","kid":"Test","n":"{}","e":"{}"}}"#,
+        base64url(&rsa.n().to_vec()),
+        base64url(&rsa.e().to_vec())
+    );
+    assert_verifies(&private_key, MessageDigest::sha256(), &jwk)
+}
+
+#[test]
+fn ec_jwk() -> Result<(), BoxError> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    let ec_key = EcKey::generate(&group)?;
+    let (mut x, mut y) = (BigNum::new()?, BigNum::new()?);
+    let mut ctx = BigNumContext::new()?;
+    ec_key
+        .public_key()
+        .affine_coordinates_gfp(&group, &mut x, &mut y, &mut ctx)?;
+    let jwk = format!(
+        r#"{{"kty":"EC","crv":"P-256","x":"{}","y":"{}"}}"#,
+        base64url(&x.to_vec_padded(32)?),
+        base64url(&y.to_vec_padded(32)?)
+    );
+    let private_key = PKey::from_ec_key(ec_key)?;
+    assert_verifies(&private_key, MessageDigest::sha256(), &jwk)
+}
+
+#[test]
+fn ed25519_jwk() -> Result<(), BoxError> {
+    let private_key = PKey::generate_ed25519()?;
+    let jwk = format!(
+        r#"{{"kty":"OKP","crv":"Ed25519","x":"{}"}}"#,
+        base64url(&private_key.raw_public_key()?)
+    );
+    // Signing doesn't support Ed25519, so just compare the keys
+    assert!(httpsig::keys::public_key_from_jwk(&jwk)?.public_eq(&private_key));
+    Ok(())
+}
+
+#[test]
+fn invalid_jwk() {
+    let cases = vec![
+        (
+            r#"{"kty":"oct","k":"c2VjcmV0"}"#,
+            "unsupported key type 'oct'",
+        ),
+        (r#"{"kty":"RSA","e":"AQAB"}"#, "missing 'n'"),
+        (
+            r#"{"kty":"EC","crv":"P-192","x":"AA","y":"AA"}"#,
+            "unsupported curve 'P-192'",
+        ),
+        (
+            r#"{"kty":"RSA","n":"AQ!B","e":"AQAB"}"#,
+            "'n' is not base64url: Invalid symbol 33, offset 2.",
+        ),
+    ];
+
+    for (jwk, reason) in cases {
+        match httpsig::keys::public_key_from_jwk(jwk) {
+            Err(e @ httpsig::HttpSigError::InvalidJwk { .. }) => {
+                assert_eq!(e.to_string(), format!("invalid JWK: {}", reason))
+            }
+            other => panic!("expected InvalidJwk for {}, got {:?}", jwk, other),
+        }
+    }
+
+    match httpsig::keys::public_key_from_jwk("not json") {
+        Err(httpsig::HttpSigError::Json(_)) => {}
+        other => panic!("expected a JSON error, got {:?}", other),
+    }
+}
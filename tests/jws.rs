@@ -0,0 +1,95 @@
+#![cfg(feature = "jws")]
+
+mod common;
+
+use crate::common::*;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use httpsig::jws::{self, JwsAlgorithm};
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::PKey;
+use openssl::rsa::RsaPrivateKeyBuilder;
+use openssl::sign::Verifier;
+
+fn base64url(input: &str) -> Result<BigNum, BoxError> {
+    let bytes = URL_SAFE_NO_PAD.decode(input)?;
+    Ok(BigNum::from_slice(&bytes)?)
+}
+
+// https://tools.ietf.org/html/rfc7515#appendix-A.2, detached
+#[test]
+fn rs256_matches_rfc7515_example() -> Result<(), BoxError> {
+    let rsa = RsaPrivateKeyBuilder::new(
+        base64url("ofgWCuLjybRlzo0tZWJjNiuSfb4p4fAkd_wWJcyQoTbji9k0l8W26mPddxHmfHQp-Vaw-4qPCJrcS2mJPMEzP1Pt0Bm4d4QlL-yRT-SFd2lZS-pCgNMsD1W_YpRPEwOWvG6b32690r2jZ47soMZo9wGzjb_7OMg0LOL-bSf63kpaSHSXndS5z5rexMdbBYUsLA9e-KXBdQOS-UTo7WTBEMa2R2CapHg665xsmtdVMTBQY4uDZlxvb3qCo5ZwKh9kG4LT6_I5IhlJH7aGhyxXFvUK-DWNmoudF8NAco9_h9iaGNj8q2ethFkMLs91kzk2PAcDTW9gb54h4FRWyuXpoQ")?,
+        base64url("AQAB")?,
+        base64url("Eq5xpGnNCivDflJsRQBXHx1hdR1k6Ulwe2JZD50LpXyWPEAeP88vLNO97IjlA7_GQ5sLKMgvfTeXZx9SE-7YwVol2NXOoAJe46sui395IW_GO-pWJ1O0BkTGoVEn2bKVRUCgu-GjBVaYLU6f3l9kJfFNS3E0QbVdxzubSu3Mkqzjkn439X0M_V51gfpRLI9JYanrC4D4qAdGcopV_0ZHHzQlBjudU2QvXt4ehNYTCBr6XCLQUShb1juUO1ZdiYoFaFQT5Tw8bGUl_x_jTj3ccPDVZFD9pIuhLhBOneufuBiB4cS98l2SR_RQyGWSeWjnczT0QU91p1DhOVRuOopznQ")?,
+    )?
+    .build();
+    let private_key = PKey::from_rsa(rsa)?;
+
+    let payload =
+        b"{\"iss\":\"joe\",\r\n \"exp\":1300819380,\r\n \"http://example.com/is_root\":true}";
+    assert_eq!(
+        jws::compute_detached_jws(payload, &private_key, JwsAlgorithm::Rs256)?,
+        "eyJhbGciOiJSUzI1NiJ9..cC4hiUPoj9Eetdgtv3hF80EGrhuB__dzERat0XF9g2VtQgr9PJbu3XOiZj5RZmh7AAuHIm4Bh-0Qc_lF5YKt_O8W2Fp5jujGbds9uJdbF9CUAr7t1dnZcAcQjbKBYNX4BAynRFdiuB--f_nZLgrnbyTyWzO75vRK5h6xBArLIARNPvkSjtQBMHlb1L07Qe7K0GarZRmB_eSN9383LcOLn6_dO--xi12jzDwusC-eOkHWEsqtFZESc6BfI7noOPqvhJ1phCnvWh6IeYI2w9QOYEUipUTI8np6LbgGY9Fs98rqVt5AXLIhWkWywlVmtVrBp0igcN_IoypGlUPQGe77Rw"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn es256_over_request_signing_string() -> Result<(), BoxError> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    let private_key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+    let headers = vec!["(request-target)".to_owned(), "host".to_owned()];
+
+    let jws = jws::compute_detached_jws_for_request(
+        &parse_request(HTTP_REQUEST),
+        Some(&headers),
+        &private_key,
+        JwsAlgorithm::Es256,
+    )?;
+    let mut segments = jws.split('.');
+    let (header, payload, signature) = (
+        segments.next().unwrap(),
+        segments.next().unwrap(),
+        segments.next().unwrap(),
+    );
+    assert_eq!(header, "eyJhbGciOiJFUzI1NiJ9");
+    assert_eq!(payload, "");
+
+    // The verifier rebuilds the payload from the request
+    let signing_input = format!(
+        "{}.{}",
+        header,
+        URL_SAFE_NO_PAD
+            .encode("(request-target): post /foo?param=value&pet=dog\nhost: example.com")
+    );
+    let signature = URL_SAFE_NO_PAD.decode(signature)?;
+    assert_eq!(signature.len(), 64);
+    let der = EcdsaSig::from_private_components(
+        BigNum::from_slice(&signature[..32])?,
+        BigNum::from_slice(&signature[32..])?,
+    )?
+    .to_der()?;
+
+    let mut verifier = Verifier::new(MessageDigest::sha256(), &private_key)?;
+    verifier.update(signing_input.as_bytes())?;
+    assert!(verifier.verify(&der)?);
+
+    Ok(())
+}
+
+#[test]
+fn incompatible_key() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    match jws::compute_detached_jws(b"", &private_key, JwsAlgorithm::Es256) {
+        Err(httpsig::HttpSigError::IncompatibleKey { algorithm }) => assert_eq!(algorithm, "ES256"),
+        other => panic!("expected incompatible key error, got {:?}", other),
+    }
+    Ok(())
+}
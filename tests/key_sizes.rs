@@ -0,0 +1,87 @@
+mod common;
+
+use crate::common::*;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use openssl::dsa::Dsa;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+
+fn assert_round_trip(private_key: &PKey<Private>, digest: MessageDigest) -> Result<(), BoxError> {
+    let public_key = PKey::public_key_from_der(&private_key.public_key_to_der()?)?;
+
+    let mut request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header(&mut request, "Test", digest, private_key)?;
+    assert!(httpsig::verify_request(&request, digest, &public_key)?);
+
+    let mut verifier = httpsig::ReusableVerifier::new(digest, public_key.clone());
+    assert!(verifier.verify(&request)?);
+
+    let options = httpsig::VerificationOptions::default();
+    assert!(httpsig::verify_request_strict(&request, digest, &public_key, &options).is_ok());
+
+    request
+        .headers_mut()
+        .insert("date", "Mon, 06 Jan 2014 21:31:40 GMT".parse()?);
+    assert!(!httpsig::verify_request(&request, digest, &public_key)?);
+
+    Ok(())
+}
+
+#[test]
+fn rsa_key_sizes() -> Result<(), BoxError> {
+    for &bits in &[3072, 4096] {
+        let private_key = PKey::from_rsa(Rsa::generate(bits)?)?;
+        assert_round_trip(&private_key, MessageDigest::sha256())?;
+        assert_round_trip(&private_key, MessageDigest::sha512())?;
+    }
+    Ok(())
+}
+
+#[test]
+fn rsa_4096_signature_length() -> Result<(), BoxError> {
+    let private_key = PKey::from_rsa(Rsa::generate(4096)?)?;
+    let public_key = PKey::public_key_from_der(&private_key.public_key_to_der()?)?;
+
+    let signature = httpsig::compute_signature(
+        &parse_request(HTTP_REQUEST),
+        MessageDigest::sha256(),
+        &private_key,
+    )?;
+    assert_eq!(signature.len(), 512);
+
+    // One byte short, so it's rejected before verifying
+    let header = format!(
+        r#"keyId="Test",signature="{}""#,
+        STANDARD.encode(&signature[1..])
+    );
+    let mut request = parse_request(HTTP_REQUEST);
+    request.headers_mut().insert("signature", header.parse()?);
+    let options = httpsig::VerificationOptions {
+        check_signature_length: true,
+        ..Default::default()
+    };
+    match httpsig::verify_request_with_options(
+        &request,
+        MessageDigest::sha256(),
+        &public_key,
+        &options,
+    ) {
+        Err(httpsig::HttpSigError::SignatureLengthMismatch {
+            expected: 512,
+            actual: 511,
+        }) => Ok(()),
+        other => panic!("expected SignatureLengthMismatch, got {:?}", other),
+    }
+}
+
+// DSA signatures are DER-encoded, so their length varies from one signature to the next
+#[test]
+fn dsa() -> Result<(), BoxError> {
+    let private_key = PKey::from_dsa(Dsa::generate(2048)?)?;
+    for _ in 0..8 {
+        assert_round_trip(&private_key, MessageDigest::sha256())?;
+    }
+    Ok(())
+}
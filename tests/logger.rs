@@ -0,0 +1,174 @@
+#![cfg(feature = "audit-log")]
+
+mod common;
+
+use crate::common::*;
+use httpsig::{
+    HttpSigError, JsonSignatureLogger, OnParseError, SignatureParams, VerificationOptions,
+};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+// Lets the test read what the logger wrote while `VerificationOptions` still holds it
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    fn records(&self) -> Result<Vec<serde_json::Value>, BoxError> {
+        let log = String::from_utf8(self.0.lock().unwrap().clone())?;
+        Ok(log
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?)
+    }
+}
+
+#[test]
+fn json_audit_log() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let params = SignatureParams {
+        headers: Some(vec!["(request-target)".to_owned(), "host".to_owned()]),
+        ..SignatureParams::new("Test", MessageDigest::sha256())
+    };
+
+    let buffer = SharedBuffer::default();
+    let options = VerificationOptions {
+        logger: Some(Arc::new(JsonSignatureLogger::new(buffer.clone()))),
+        ..VerificationOptions::default()
+    };
+
+    let mut request = parse_request(HTTP_REQUEST);
+    request
+        .headers_mut()
+        .insert("x-request-id", "abc-123".parse()?);
+    httpsig::add_signature_header_with_params(&mut request, &params, &private_key)?;
+    assert!(httpsig::verify_request_with_options(
+        &request,
+        MessageDigest::sha256(),
+        &public_key,
+        &options
+    )?);
+
+    *request.uri_mut() = "/bar".parse()?;
+    assert!(!httpsig::verify_request_with_options(
+        &request,
+        MessageDigest::sha256(),
+        &public_key,
+        &options
+    )?);
+
+    // Requests without a signature aren't logged
+    let unsigned = parse_request(HTTP_REQUEST);
+    assert!(!httpsig::verify_request_with_options(
+        &unsigned,
+        MessageDigest::sha256(),
+        &public_key,
+        &options
+    )?);
+
+    let records = buffer.records()?;
+    assert_eq!(records.len(), 2);
+
+    for record in &records {
+        assert!(record["timestamp"].is_u64());
+        assert_eq!(record["request_id"], "abc-123");
+        assert_eq!(record["key_id"], "Test");
+        assert_eq!(
+            record["signed_headers"],
+            serde_json::json!(["(request-target)", "host"])
+        );
+        assert_eq!(record["algorithm"], serde_json::Value::Null);
+    }
+    assert_eq!(records[0]["outcome"], "valid");
+    assert_eq!(records[0]["failure_reason"], serde_json::Value::Null);
+    assert_eq!(records[1]["outcome"], "invalid");
+    assert_eq!(
+        records[1]["failure_reason"],
+        "signature verification failed"
+    );
+
+    Ok(())
+}
+
+// Signatures that don't verify are logged with the actual reason, and errors are returned as they
+// were
+#[test]
+fn json_audit_log_failure_reasons() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let buffer = SharedBuffer::default();
+    let options = VerificationOptions {
+        logger: Some(Arc::new(JsonSignatureLogger::new(buffer.clone()))),
+        ..VerificationOptions::default()
+    };
+    let verify = |request: &http::Request<&[u8]>, options: &VerificationOptions| {
+        httpsig::verify_request_with_options(request, MessageDigest::sha256(), &public_key, options)
+    };
+
+    // A covered header that was removed
+    let params = SignatureParams {
+        headers: Some(vec!["(request-target)".to_owned(), "digest".to_owned()]),
+        ..SignatureParams::new("Test", MessageDigest::sha256())
+    };
+    let mut request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header_with_params(&mut request, &params, &private_key)?;
+    request.headers_mut().remove("digest");
+    assert!(!verify(&request, &options)?);
+
+    // A signature header that can't be parsed, ignored by `OnParseError::TreatAsUnsigned`
+    let mut malformed = parse_request(HTTP_REQUEST);
+    malformed
+        .headers_mut()
+        .insert("signature", r#"signature="abc""#.parse()?);
+    assert!(!verify(&malformed, &options)?);
+
+    // An error from verifying comes back unchanged
+    let skewed = VerificationOptions {
+        max_clock_skew: Some(Duration::from_secs(300)),
+        now: Some(UNIX_EPOCH + Duration::from_secs(1_388_957_500 + 301)),
+        on_parse_error: OnParseError::FailClosed,
+        ..options.clone()
+    };
+    let mut request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header(&mut request, "Test", MessageDigest::sha256(), &private_key)?;
+    match verify(&request, &skewed) {
+        Err(HttpSigError::ClockSkew { skew }) => assert_eq!(skew, Duration::from_secs(301)),
+        other => panic!("expected clock skew, got {:?}", other),
+    }
+
+    let records = buffer.records()?;
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0]["key_id"], "Test");
+    assert_eq!(
+        records[0]["failure_reason"],
+        "required header 'digest' is not present"
+    );
+    assert_eq!(records[1]["key_id"], serde_json::Value::Null);
+    assert_eq!(
+        records[1]["failure_reason"],
+        "signature header is missing required 'keyId' field"
+    );
+    assert_eq!(
+        records[2]["failure_reason"],
+        "date header is 301s away from the current time"
+    );
+    for record in &records {
+        assert_eq!(record["outcome"], "invalid");
+    }
+
+    Ok(())
+}
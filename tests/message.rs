@@ -0,0 +1,135 @@
+mod common;
+
+use crate::common::*;
+use httpsig::{HttpSigError, SignableMessage, SignatureParams};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+
+// A request type that doesn't use the `http` crate
+struct CustomRequest {
+    method: String,
+    path: String,
+    headers: Vec<(String, Vec<u8>)>,
+}
+
+impl SignableMessage for CustomRequest {
+    fn method(&self) -> Option<&str> {
+        Some(&self.method)
+    }
+
+    fn uri_target(&self) -> Option<&str> {
+        Some(&self.path)
+    }
+
+    fn headers_iter(&self) -> Box<dyn Iterator<Item = (&str, &[u8])> + '_> {
+        Box::new(
+            self.headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_slice())),
+        )
+    }
+
+    fn set_header(&mut self, name: &'static str, value: &str) -> Result<(), HttpSigError> {
+        self.headers.retain(|(header_name, _)| header_name != name);
+        self.headers
+            .push((name.to_owned(), value.as_bytes().to_vec()));
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_request() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+
+    let mut request = CustomRequest {
+        method: "POST".to_owned(),
+        path: "/foo?param=value&pet=dog".to_owned(),
+        headers: vec![
+            ("Host".to_owned(), b"example.com".to_vec()),
+            ("Date".to_owned(), b"Sun, 05 Jan 2014 21:31:40 GMT".to_vec()),
+        ],
+    };
+    let params = SignatureParams {
+        headers: Some(vec![
+            "(request-target)".to_owned(),
+            "host".to_owned(),
+            "date".to_owned(),
+        ]),
+        ..SignatureParams::new("Test", MessageDigest::sha256())
+    };
+    httpsig::sign_message(&mut request, &params, &private_key)?;
+    assert!(httpsig::verify_message(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    // Same signature as for the equivalent `http::Request`
+    let mut http_request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header_with_params(&mut http_request, &params, &private_key)?;
+    assert_eq!(
+        request.header("signature"),
+        vec![http_request.headers()["signature"].as_bytes()]
+    );
+
+    request.path = "/bar".to_owned();
+    assert!(!httpsig::verify_message(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn response() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+
+    let mut response = http::Response::builder()
+        .header("date", "Sun, 05 Jan 2014 21:31:40 GMT")
+        .header("content-type", "application/json")
+        .body(())?;
+    let params = SignatureParams::new("Test", MessageDigest::sha256());
+    httpsig::sign_message(&mut response, &params, &private_key)?;
+
+    let signature = httpsig::parse_signature_parts(response.headers()["signature"].to_str()?)?;
+    assert_eq!(signature.headers, Some("date content-type"));
+    assert!(httpsig::verify_message(
+        &response,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    let params = SignatureParams {
+        headers: Some(vec!["(request-target)".to_owned(), "date".to_owned()]),
+        ..params
+    };
+    match httpsig::sign_message(&mut response, &params, &private_key) {
+        Err(HttpSigError::MissingHeader { name }) => assert_eq!(name, "(request-target)"),
+        other => panic!("expected missing header error, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+// Names that `HeaderName::from_static` would panic on are normalized, or rejected with an error
+#[test]
+fn set_header_name_case() -> Result<(), BoxError> {
+    let mut request = parse_request(HTTP_REQUEST);
+    request.set_header("X-Signed-By", "Test")?;
+    assert_eq!(request.headers()["x-signed-by"], "Test");
+
+    let mut response = http::Response::new(());
+    response.set_header("X-Signed-By", "Test")?;
+    assert_eq!(response.headers()["x-signed-by"], "Test");
+
+    match request.set_header("x signed by", "Test") {
+        Err(HttpSigError::Http(_)) => {}
+        other => panic!("expected invalid header name, got {:?}", other),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use crate::common::*;
+use httpsig::{HttpSigError, InMemoryNonceStore, SignatureParams, VerificationOptions};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+
+fn signed_request(nonce: &str, headers: &[&str]) -> Result<http::Request<&'static [u8]>, BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let params = SignatureParams {
+        headers: Some(headers.iter().map(|&h| h.to_owned()).collect()),
+        nonce: Some(nonce.to_owned()),
+        ..SignatureParams::new("Test", MessageDigest::sha256())
+    };
+
+    let mut request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header_with_params(&mut request, &params, &private_key)?;
+    Ok(request)
+}
+
+fn verify(
+    request: &http::Request<&[u8]>,
+    nonce_store: &InMemoryNonceStore,
+) -> Result<bool, HttpSigError> {
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM).unwrap();
+    httpsig::verify_request_with_nonce_store(
+        request,
+        MessageDigest::sha256(),
+        &public_key,
+        &VerificationOptions::default(),
+        nonce_store,
+    )
+}
+
+#[test]
+fn nonce_is_signed() -> Result<(), BoxError> {
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let request = signed_request("abc123", &["(request-target)", "(nonce)"])?;
+
+    let parts = httpsig::find_signature_in_request(&request)?;
+    assert_eq!(parts.nonce, Some("abc123"));
+
+    let debug = httpsig::verify_request_debug(&request, MessageDigest::sha256(), &public_key)?;
+    assert_eq!(
+        debug.signing_string,
+        b"(request-target): post /foo?param=value&pet=dog\n(nonce): abc123".to_vec()
+    );
+    assert!(debug.verified);
+
+    Ok(())
+}
+
+#[test]
+fn replayed_nonce_is_rejected() -> Result<(), BoxError> {
+    let nonce_store = InMemoryNonceStore::new();
+    let request = signed_request("abc123", &["(request-target)", "host", "date", "(nonce)"])?;
+
+    assert!(verify(&request, &nonce_store)?);
+    match verify(&request, &nonce_store) {
+        Err(HttpSigError::ReplayedNonce { nonce }) => assert_eq!(nonce, "abc123"),
+        other => panic!("expected replayed nonce error, got {:?}", other),
+    }
+
+    // A different nonce is a different request
+    let request = signed_request("def456", &["(request-target)", "host", "date", "(nonce)"])?;
+    assert!(verify(&request, &nonce_store)?);
+
+    Ok(())
+}
+
+#[test]
+fn nonce_must_be_signed() -> Result<(), BoxError> {
+    let nonce_store = InMemoryNonceStore::new();
+    let request = signed_request("abc123", &["(request-target)", "host", "date"])?;
+
+    match verify(&request, &nonce_store) {
+        Err(HttpSigError::NonceNotSigned) => {}
+        other => panic!("expected nonce not signed error, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+// Swapping in a fresh nonce breaks the signature, and doesn't use up the nonce
+#[test]
+fn tampered_nonce() -> Result<(), BoxError> {
+    let nonce_store = InMemoryNonceStore::new();
+    let mut request = signed_request("abc123", &["(request-target)", "host", "date", "(nonce)"])?;
+
+    let signature = request.headers()["signature"]
+        .to_str()?
+        .replace("abc123", "xyz789");
+    request
+        .headers_mut()
+        .insert("signature", signature.parse()?);
+    assert!(!verify(&request, &nonce_store)?);
+
+    let request = signed_request("xyz789", &["(request-target)", "host", "date", "(nonce)"])?;
+    assert!(verify(&request, &nonce_store)?);
+
+    Ok(())
+}
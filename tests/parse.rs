@@ -0,0 +1,210 @@
+use httpsig::parse::{self, ParseError};
+use httpsig::{HttpSigError, SignaturePreview};
+use std::error::Error;
+
+#[test]
+fn parse_signature_parts() -> Result<(), ParseError> {
+    let parts = parse::parse_signature_parts(
+        r#"keyId="Test", algorithm="rsa-sha256", created=1402170695, expires="1402170995", signature="YWJj""#,
+    )?;
+    assert_eq!(parts.key_id, "Test");
+    assert_eq!(parts.algorithm, Some("rsa-sha256"));
+    assert_eq!(parts.headers, None);
+    assert_eq!(parts.signature, "YWJj");
+    assert_eq!(parts.created, Some(1402170695));
+    assert_eq!(parts.expires, Some(1402170995));
+    assert_eq!(
+        parts.to_string(),
+        r#"keyId="Test",algorithm="rsa-sha256",created=1402170695,expires=1402170995,signature="YWJj""#
+    );
+    assert_eq!(parse::decode_signature(&parts)?, b"abc");
+
+    Ok(())
+}
+
+// `,` and `;` inside a quoted value don't end the parameter
+#[test]
+fn quoted_separators() -> Result<(), ParseError> {
+    let header = r#"keyId="https://example.com/a,b;c",headers="date",signature="YWJj";extra=1"#;
+    let parts = parse::parse_signature_parts(header)?;
+    assert_eq!(parts.key_id, "https://example.com/a,b;c");
+    assert_eq!(parts.headers, Some("date"));
+    assert_eq!(parts.signature, "YWJj");
+    assert_eq!(parse::key_id(header), Some("https://example.com/a,b;c"));
+
+    Ok(())
+}
+
+#[test]
+fn parse_errors() {
+    let cases = vec![
+        (
+            r#"keyId="Test,signature="YWJj""#,
+            ParseError::UnterminatedQuote,
+        ),
+        (r#"keyId=",signature="YWJj""#, ParseError::UnterminatedQuote),
+        (
+            r#"keyId=Test,signature="YWJj""#,
+            ParseError::UnquotedValue {
+                key: "keyId".to_owned(),
+            },
+        ),
+        (
+            r#"keyId="Test",signature="YWJj","#,
+            ParseError::MissingEquals { key: "".to_owned() },
+        ),
+        (
+            r#"keyId="Test",created=soon,signature="YWJj""#,
+            ParseError::InvalidInteger {
+                key: "created".to_owned(),
+            },
+        ),
+        (r#"signature="YWJj""#, ParseError::MissingKeyId),
+        (r#"keyId="Test""#, ParseError::MissingSignature),
+        (
+            r#"keyId="Test",="x",signature="YWJj""#,
+            ParseError::EmptyFieldName,
+        ),
+        (
+            r#"keyId="Test",signature="YWJj",keyId="Other""#,
+            ParseError::DuplicateField {
+                key: "keyId".to_owned(),
+            },
+        ),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(
+            parse::parse_signature_parts(input).unwrap_err(),
+            expected,
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn invalid_base64() {
+    let parts = parse::parse_signature_parts(r#"keyId="Test",signature="!!""#).unwrap();
+    let error = parse::decode_signature(&parts).unwrap_err();
+
+    match &error {
+        ParseError::InvalidBase64 { field, .. } => assert_eq!(field, "signature"),
+        other => panic!("expected invalid base64 error, got {:?}", other),
+    }
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn error_messages() {
+    assert_eq!(
+        ParseError::MissingKeyId.to_string(),
+        "signature header is missing required 'keyId' field"
+    );
+
+    let error = HttpSigError::from(ParseError::MissingKeyId);
+    assert_eq!(
+        error.to_string(),
+        "signature header is missing required 'keyId' field"
+    );
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn key_id() {
+    assert_eq!(
+        parse::key_id(r#"algorithm="rsa-sha256", keyId="Test",signature="YWJj""#),
+        Some("Test")
+    );
+    assert_eq!(parse::key_id(r#"keyId="Test""#), Some("Test"));
+    assert_eq!(parse::key_id(r#"signature="keyId=x""#), None);
+    assert_eq!(parse::key_id(r#"keyId=Test,signature="YWJj""#), None);
+}
+
+#[test]
+fn preview_signature() -> Result<(), HttpSigError> {
+    let request = http::Request::builder()
+        .header(
+            "signature",
+            r#"keyId="Test",algorithm="rsa-sha256",created=1402170695,headers="(request-target) Host date",signature="not base64!""#,
+        )
+        .body(())?;
+
+    let preview = httpsig::preview_signature(&request)?;
+    assert_eq!(
+        preview,
+        SignaturePreview {
+            key_id: "Test".to_owned(),
+            algorithm: Some("rsa-sha256".to_owned()),
+            covered_headers: vec!["(request-target)".into(), "Host".into(), "date".into()],
+            created: Some(1402170695),
+            expires: None,
+        }
+    );
+
+    let request = http::Request::builder()
+        .header(
+            "authorization",
+            r#"Signature keyId="Test",signature="YWJj""#,
+        )
+        .body(())?;
+    assert_eq!(
+        httpsig::preview_signature(&request)?.covered_headers,
+        vec!["date"]
+    );
+
+    let request = http::Request::builder()
+        .header("signature", r#"keyId="Test""#)
+        .body(())?;
+    match httpsig::preview_signature(&request) {
+        Err(HttpSigError::Parse(ParseError::MissingSignature)) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn signature_headers_equivalent() {
+    let header = r#"keyId="Test",headers="(request-target) host date",signature="+/+/""#;
+    let equivalent = [
+        header,
+        // Reordered parameters and headers, with whitespace and an `algorithm`
+        r#"signature="+/+/", algorithm="rsa-sha256", headers="date (request-target) Host", keyId="Test""#,
+        // URL-safe and unpadded
+        r#"keyId="Test",headers="(request-target) host date",signature="-_-_""#,
+    ];
+    for other in equivalent.iter() {
+        assert!(
+            parse::signature_headers_equivalent(header, other),
+            "{}",
+            other
+        );
+        assert!(
+            parse::signature_headers_equivalent(other, header),
+            "{}",
+            other
+        );
+    }
+
+    let different = [
+        r#"keyId="Other",headers="(request-target) host date",signature="+/+/""#,
+        r#"keyId="Test",headers="(request-target) host",signature="+/+/""#,
+        r#"keyId="Test",headers="(request-target) host date",signature="+/+A""#,
+        r#"keyId="Test",headers="(request-target) host date""#,
+        r#"keyId="Test",headers="(request-target) host date",signature="not base64!""#,
+    ];
+    for other in different.iter() {
+        assert!(
+            !parse::signature_headers_equivalent(header, other),
+            "{}",
+            other
+        );
+    }
+
+    // No `headers` means just `date`
+    assert!(parse::signature_headers_equivalent(
+        r#"keyId="Test",signature="YWJj""#,
+        r#"keyId="Test",headers="date",signature="YWJj""#,
+    ));
+}
@@ -0,0 +1,335 @@
+mod common;
+
+use crate::common::*;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use httpsig::{
+    AllowedAlgorithmsPolicy, MaxAgePolicy, PolicyChain, PolicyViolation, RequiredHeadersPolicy,
+    RequiredKeyIdPrefixPolicy, SignatureParams, SignaturePolicy, SignedHeaders, VerificationError,
+    VerificationOptions,
+};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use std::time::{Duration, UNIX_EPOCH};
+
+// The `Date` header of `HTTP_REQUEST`
+const DATE: u64 = 1_388_957_500;
+
+fn signed_request(headers: &[&str]) -> Result<http::Request<&'static [u8]>, BoxError> {
+    signed_request_with_key_id("Test", headers)
+}
+
+fn signed_request_with_key_id(
+    key_id: &str,
+    headers: &[&str],
+) -> Result<http::Request<&'static [u8]>, BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let params = SignatureParams {
+        headers: Some(headers.iter().map(|&h| h.to_owned()).collect()),
+        ..SignatureParams::new(key_id, MessageDigest::sha256())
+    };
+
+    let mut request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header_with_params(&mut request, &params, &private_key)?;
+    Ok(request)
+}
+
+fn verify_at(
+    request: &http::Request<&[u8]>,
+    now: u64,
+    policy: &impl SignaturePolicy,
+) -> Result<(), VerificationError> {
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM).unwrap();
+    let options = VerificationOptions {
+        now: Some(UNIX_EPOCH + Duration::from_secs(now)),
+        ..VerificationOptions::default()
+    };
+    httpsig::verify_request_with_signature_policy(
+        request,
+        MessageDigest::sha256(),
+        &public_key,
+        &options,
+        policy,
+    )
+    .map(|_| ())
+}
+
+// Signs `(request-target) host (created)` by hand, since signing doesn't support `(created)`
+fn signed_request_with_created(created: u64) -> Result<http::Request<&'static [u8]>, BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let mut request = parse_request(HTTP_REQUEST);
+
+    let signing_string = format!(
+        "(request-target): post /foo?param=value&pet=dog\n\
+         host: example.com\n\
+         (created): {}",
+        created
+    );
+    let mut signer = Signer::new(MessageDigest::sha256(), &private_key)?;
+    signer.update(signing_string.as_bytes())?;
+    let signature = STANDARD.encode(signer.sign_to_vec()?);
+
+    let header = format!(
+        r#"keyId="Test",algorithm="hs2019",created={},headers="(request-target) host (created)",signature="{}""#,
+        created, signature
+    );
+    request.headers_mut().insert("signature", header.parse()?);
+    Ok(request)
+}
+
+// Adds a parameter that the signature doesn't cover, like `created` or `algorithm`
+fn with_parameter(
+    mut request: http::Request<&'static [u8]>,
+    parameter: &str,
+) -> Result<http::Request<&'static [u8]>, BoxError> {
+    let header = format!("{},{}", request.headers()["signature"].to_str()?, parameter);
+    request.headers_mut().insert("signature", header.parse()?);
+    Ok(request)
+}
+
+fn assert_too_old(result: Result<(), VerificationError>, expected_created: u64) {
+    match result {
+        Err(VerificationError::SignatureTooOld { created, max_age }) => {
+            assert_eq!(created, UNIX_EPOCH + Duration::from_secs(expected_created));
+            assert_eq!(max_age, Duration::from_secs(300));
+        }
+        other => panic!("expected signature too old, got {:?}", other),
+    }
+}
+
+#[test]
+fn max_age_from_date() -> Result<(), BoxError> {
+    let policy = MaxAgePolicy::new(Duration::from_secs(300));
+    let request = signed_request(&["(request-target)", "host", "date"])?;
+
+    verify_at(&request, DATE + 299, &policy)?;
+    verify_at(&request, DATE + 300, &policy)?;
+    assert_too_old(verify_at(&request, DATE + 301, &policy), DATE);
+
+    // An unsigned `Date` could have been changed, so it doesn't count
+    let request = signed_request(&["(request-target)", "host"])?;
+    match verify_at(&request, DATE, &policy) {
+        Err(VerificationError::Policy(PolicyViolation::MissingTimestamp)) => {}
+        other => panic!("expected missing timestamp, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn max_age_from_created() -> Result<(), BoxError> {
+    let policy = MaxAgePolicy::new(Duration::from_secs(300));
+    let created = DATE - 1000;
+    let request = signed_request_with_created(created)?;
+
+    // A signed `created` is used over `Date`
+    verify_at(&request, created + 299, &policy)?;
+    verify_at(&request, created + 300, &policy)?;
+    assert_too_old(verify_at(&request, created + 301, &policy), created);
+    assert_too_old(verify_at(&request, DATE, &policy), created);
+
+    Ok(())
+}
+
+#[test]
+fn max_age_ignores_unsigned_created() -> Result<(), BoxError> {
+    let policy = MaxAgePolicy::new(Duration::from_secs(300));
+    let now = DATE + 1000;
+
+    // A replayed request with a fresh `created` added is still dated by its signed `Date`
+    let request = with_parameter(
+        signed_request(&["(request-target)", "host", "date"])?,
+        &format!("created={}", now),
+    )?;
+    assert_too_old(verify_at(&request, now, &policy), DATE);
+
+    // With no signed `Date` to fall back on, there's no timestamp at all
+    let request = with_parameter(
+        signed_request(&["(request-target)", "host"])?,
+        &format!("created={}", now),
+    )?;
+    match verify_at(&request, now, &policy) {
+        Err(VerificationError::Policy(PolicyViolation::MissingTimestamp)) => {}
+        other => panic!("expected missing timestamp, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn required_headers() -> Result<(), BoxError> {
+    let policy = RequiredHeadersPolicy::new(vec!["(request-target)", "Host", "date"]);
+
+    let request = signed_request(&["(request-target)", "host", "date", "digest"])?;
+    verify_at(&request, DATE, &policy)?;
+
+    for (headers, missing) in &[
+        (&["date"][..], "(request-target)"),
+        (&["(request-target)", "date"][..], "host"),
+        (&["(request-target)", "host"][..], "date"),
+    ] {
+        let request = signed_request(headers)?;
+        match verify_at(&request, DATE, &policy) {
+            Err(VerificationError::Policy(PolicyViolation::MissingRequiredHeader(name))) => {
+                assert_eq!(name, *missing)
+            }
+            other => panic!("expected missing {}, got {:?}", missing, other),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn allowed_algorithms() -> Result<(), BoxError> {
+    let policy = AllowedAlgorithmsPolicy::new(vec!["rsa-sha256", "ecdsa-p256-sha256", "ed25519"]);
+    let signed = || signed_request(&["(request-target)", "host", "date"]);
+
+    let request = with_parameter(signed()?, r#"algorithm="rsa-sha256""#)?;
+    verify_at(&request, DATE, &policy)?;
+
+    let request = with_parameter(signed()?, r#"algorithm="rsa-sha1""#)?;
+    match verify_at(&request, DATE, &policy) {
+        Err(VerificationError::Policy(PolicyViolation::DisallowedAlgorithm { algorithm })) => {
+            assert_eq!(algorithm, "rsa-sha1")
+        }
+        other => panic!("expected disallowed algorithm, got {:?}", other),
+    }
+
+    // No `algorithm` at all
+    match verify_at(&signed()?, DATE, &policy) {
+        Err(VerificationError::Policy(PolicyViolation::MissingAlgorithm)) => {}
+        other => panic!("expected missing algorithm, got {:?}", other),
+    }
+    verify_at(&signed()?, DATE, &policy.allow_missing(true))?;
+
+    Ok(())
+}
+
+#[test]
+fn required_key_id_prefix() -> Result<(), BoxError> {
+    let policy = RequiredKeyIdPrefixPolicy::new("tenant-123/");
+    let headers = ["(request-target)", "host", "date"];
+
+    let request = signed_request_with_key_id("tenant-123/signing-key-v1", &headers)?;
+    verify_at(&request, DATE, &policy)?;
+
+    for key_id in &[
+        "tenant-1234/signing-key-v1",
+        "tenant-12/signing-key-v1",
+        "TENANT-123/key",
+    ] {
+        let request = signed_request_with_key_id(key_id, &headers)?;
+        match verify_at(&request, DATE, &policy) {
+            Err(VerificationError::Policy(PolicyViolation::InvalidKeyIdPrefix {
+                key_id: rejected,
+                expected_prefix,
+            })) => {
+                assert_eq!(rejected, *key_id);
+                assert_eq!(expected_prefix, "tenant-123/");
+            }
+            other => panic!("expected invalid prefix for {}, got {:?}", key_id, other),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn policy_chain() -> Result<(), BoxError> {
+    let chain = || {
+        PolicyChain::new(vec![
+            Box::new(MaxAgePolicy::new(Duration::from_secs(300))),
+            Box::new(
+                PolicyChain::new(vec![
+                    Box::new(RequiredHeadersPolicy::new(vec!["date"])),
+                    Box::new(AllowedAlgorithmsPolicy::new(vec!["rsa-sha256"])),
+                ])
+                .collect_all(),
+            ),
+        ])
+    };
+
+    let request = with_parameter(
+        signed_request(&["(request-target)", "host", "date"])?,
+        r#"algorithm="rsa-sha256""#,
+    )?;
+    verify_at(&request, DATE, &chain())?;
+    verify_at(&request, DATE, &chain().collect_all())?;
+
+    // No `date`, and no `algorithm`
+    let request = signed_request(&["(request-target)", "host"])?;
+    match verify_at(&request, DATE, &chain().fail_fast()) {
+        Err(VerificationError::Policy(PolicyViolation::MissingTimestamp)) => {}
+        other => panic!("expected missing timestamp, got {:?}", other),
+    }
+
+    // The nested chain's violations are flattened
+    match verify_at(&request, DATE, &chain().collect_all()) {
+        Err(VerificationError::Policy(PolicyViolation::Multiple(violations))) => assert_eq!(
+            violations,
+            vec![
+                PolicyViolation::MissingTimestamp,
+                PolicyViolation::MissingRequiredHeader("date".to_owned()),
+                PolicyViolation::MissingAlgorithm,
+            ]
+        ),
+        other => panic!("expected multiple violations, got {:?}", other),
+    }
+
+    // A single violation isn't wrapped
+    let request = signed_request(&["(request-target)", "host", "date"])?;
+    match verify_at(&request, DATE, &chain().collect_all()) {
+        Err(VerificationError::Policy(PolicyViolation::MissingAlgorithm)) => {}
+        other => panic!("expected missing algorithm, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn policy_function() -> Result<(), BoxError> {
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let requires_digest = |signed: &SignedHeaders<'_>| signed.contains("Digest");
+
+    let request = signed_request(&["(request-target)", "host", "date", "digest"])?;
+    assert!(httpsig::verify_request_with_policy(
+        &request,
+        MessageDigest::sha256(),
+        &public_key,
+        requires_digest
+    )?);
+
+    // The signature is valid, but doesn't cover `digest`
+    let request = signed_request(&["(request-target)", "host", "date"])?;
+    assert!(httpsig::verify_request(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+    assert!(!httpsig::verify_request_with_policy(
+        &request,
+        MessageDigest::sha256(),
+        &public_key,
+        requires_digest
+    )?);
+
+    // The policy sees the signature's parameters
+    let request = with_parameter(request, "created=1388957400")?;
+    httpsig::verify_request_with_policy(
+        &request,
+        MessageDigest::sha256(),
+        &public_key,
+        |signed| {
+            assert_eq!(signed.key_id, "Test");
+            assert_eq!(signed.algorithm, None);
+            assert_eq!(signed.headers, ["(request-target)", "host", "date"]);
+            assert_eq!(signed.created, Some(1_388_957_400));
+            assert_eq!(signed.expires, None);
+            true
+        },
+    )?;
+
+    Ok(())
+}
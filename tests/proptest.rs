@@ -0,0 +1,128 @@
+mod common;
+
+use crate::common::*;
+use httpsig::LineFolding;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private, Public};
+use proptest::prelude::*;
+use std::sync::LazyLock;
+
+static PRIVATE_KEY: LazyLock<PKey<Private>> =
+    LazyLock::new(|| PKey::private_key_from_pem(PRIVATE_PEM).unwrap());
+static PUBLIC_KEY: LazyLock<PKey<Public>> =
+    LazyLock::new(|| PKey::public_key_from_pem(PUBLIC_PEM).unwrap());
+
+#[derive(Clone, Debug)]
+struct RequestHead {
+    method: String,
+    target: String,
+    headers: Vec<(String, String)>,
+}
+
+impl RequestHead {
+    fn to_request(&self) -> http::Request<()> {
+        let mut builder = http::Request::builder();
+        builder
+            .method(self.method.as_str())
+            .uri(self.target.as_str());
+        for (name, value) in &self.headers {
+            builder.header(name.as_str(), value.as_str());
+        }
+        builder.body(()).unwrap()
+    }
+}
+
+fn request_head() -> impl Strategy<Value = RequestHead> {
+    let method = prop::sample::select(vec!["GET", "POST", "PUT", "DELETE", "PATCH"]);
+    let target = "(/[a-zA-Z0-9._~-]{1,8}){1,3}(\\?[a-z0-9=&]{0,16})?";
+    // Few distinct names, so headers are often repeated. Values can have leading and trailing
+    // whitespace, which isn't signed.
+    let header = (
+        "[Xx]-[a-c]",
+        "[ \t]{0,2}[!-~]([!-~ \t]{0,16}[!-~])?[ \t]{0,2}",
+    );
+
+    (method, target, prop::collection::vec(header, 1..8)).prop_map(|(method, target, headers)| {
+        RequestHead {
+            method: method.to_owned(),
+            target,
+            headers,
+        }
+    })
+}
+
+fn sign(head: &RequestHead) -> http::Request<()> {
+    let mut request = head.to_request();
+    httpsig::add_signature_header(&mut request, "Test", MessageDigest::sha256(), &PRIVATE_KEY)
+        .unwrap();
+    request
+}
+
+fn verify<T>(request: &http::Request<T>) -> bool {
+    httpsig::verify_request(request, MessageDigest::sha256(), &PUBLIC_KEY).unwrap()
+}
+
+// Writes the request the way it'd be sent over the wire
+fn serialize<T>(request: &http::Request<T>) -> Vec<u8> {
+    let mut output = format!("{} {} HTTP/1.1\r\n", request.method(), request.uri()).into_bytes();
+    for (name, value) in request.headers() {
+        output.extend_from_slice(name.as_str().as_bytes());
+        output.extend_from_slice(b": ");
+        output.extend_from_slice(value.as_bytes());
+        output.extend_from_slice(b"\r\n");
+    }
+    output.extend_from_slice(b"\r\n");
+    output
+}
+
+// Replaces a byte that isn't leading or trailing whitespace
+fn tamper(value: &str, index: prop::sample::Index) -> String {
+    let mut bytes = value.as_bytes().to_vec();
+    let candidates: Vec<usize> = (0..bytes.len())
+        .filter(|&i| bytes[i] != b' ' && bytes[i] != b'\t')
+        .collect();
+    let i = *index.get(&candidates);
+    bytes[i] = if bytes[i] == b'a' { b'b' } else { b'a' };
+    String::from_utf8(bytes).unwrap()
+}
+
+proptest! {
+    #[test]
+    fn signed_requests_verify(head in request_head()) {
+        let request = sign(&head);
+        prop_assert!(verify(&request));
+
+        let parsed = httpsig::parse_request(&serialize(&request), LineFolding::Reject).unwrap();
+        prop_assert!(verify(&parsed));
+    }
+
+    #[test]
+    fn tampered_header_fails(
+        head in request_head(),
+        header: prop::sample::Index,
+        byte: prop::sample::Index,
+    ) {
+        let signature = sign(&head).headers()["signature"].clone();
+
+        let mut tampered = head.clone();
+        let (_, value) = header.get_mut(&mut tampered.headers);
+        *value = tamper(value, byte);
+
+        let mut request = tampered.to_request();
+        request.headers_mut().insert("signature", signature);
+        prop_assert!(!verify(&request));
+    }
+
+    #[test]
+    fn tampered_target_fails(head in request_head(), byte: prop::sample::Index) {
+        let signature = sign(&head).headers()["signature"].clone();
+
+        let mut tampered = head.clone();
+        // Keep the leading `/` so the target is still a valid path
+        tampered.target = format!("/{}", tamper(&head.target[1..], byte));
+
+        let mut request = tampered.to_request();
+        request.headers_mut().insert("signature", signature);
+        prop_assert!(!verify(&request));
+    }
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use crate::common::*;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use httpsig::{PssSaltLen, SignatureParams, SigningStringTemplate};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::rsa::Padding;
+use openssl::sign::{RsaPssSaltlen, Signer, Verifier};
+
+// What `compute_signature` signs for `HTTP_REQUEST`
+fn signing_string() -> Result<Vec<u8>, BoxError> {
+    let template = SigningStringTemplate::new(&[
+        "(request-target)",
+        "host",
+        "date",
+        "content-type",
+        "digest",
+        "content-length",
+    ]);
+    Ok(template.fill(&parse_request(HTTP_REQUEST))?)
+}
+
+// The test key is 1024 bits, so the largest salt for SHA-256 is 128 - 32 - 2 bytes
+const MAX_SALT_LEN: i32 = 94;
+
+// Signatures made with openssl directly, as another implementation would, verify with the
+// matching salt length and fail with the wrong one
+#[test]
+fn verify_pss_salt_lengths() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+
+    for &(salt_bytes, salt_len, wrong_salt_len) in &[
+        (32, PssSaltLen::DigestLength, PssSaltLen::Maximum),
+        (MAX_SALT_LEN, PssSaltLen::Maximum, PssSaltLen::DigestLength),
+        (20, PssSaltLen::Bytes(20), PssSaltLen::Bytes(21)),
+    ] {
+        let mut signer = Signer::new(MessageDigest::sha256(), &private_key)?;
+        signer.set_rsa_padding(Padding::PKCS1_PSS)?;
+        signer.set_rsa_pss_saltlen(RsaPssSaltlen::custom(salt_bytes))?;
+        signer.update(&signing_string()?)?;
+        let signature = STANDARD.encode(&signer.sign_to_vec()?);
+
+        let mut request = parse_request(HTTP_REQUEST);
+        request.headers_mut().insert(
+            "signature",
+            format!(
+                r#"keyId="Test",algorithm="hs2019",headers="(request-target) host date content-type digest content-length",signature="{}""#,
+                signature
+            )
+            .parse()?,
+        );
+        let parts = httpsig::find_signature_in_request(&request)?;
+
+        let verify = |salt_len| {
+            httpsig::verify_signature_pss(
+                &request,
+                &parts,
+                MessageDigest::sha256(),
+                &public_key,
+                salt_len,
+            )
+        };
+        assert!(verify(salt_len)?, "{:?}", salt_len);
+        assert!(!verify(wrong_salt_len)?, "{:?}", wrong_salt_len);
+        assert!(!httpsig::verify_signature_parts(
+            &request,
+            &parts,
+            MessageDigest::sha256(),
+            &public_key
+        )?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn compute_pss_salt_lengths() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let request = parse_request(HTTP_REQUEST);
+
+    for &(salt_len, salt_bytes) in &[
+        (PssSaltLen::DigestLength, 32),
+        (PssSaltLen::Maximum, MAX_SALT_LEN),
+    ] {
+        let signature = httpsig::compute_signature_pss(
+            &request,
+            MessageDigest::sha256(),
+            &private_key,
+            salt_len,
+        )?;
+
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &public_key)?;
+        verifier.set_rsa_padding(Padding::PKCS1_PSS)?;
+        verifier.set_rsa_pss_saltlen(RsaPssSaltlen::custom(salt_bytes))?;
+        verifier.update(&signing_string()?)?;
+        assert!(verifier.verify(&signature)?, "{:?}", salt_len);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn pss_signature_params_round_trip() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+
+    let mut params = SignatureParams::new("Test", MessageDigest::sha256());
+    params.rsa_pss_salt_len = Some(PssSaltLen::Maximum);
+    let mut request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header_with_params(&mut request, &params, &private_key)?;
+
+    let options = httpsig::VerificationOptions {
+        rsa_pss_salt_len: Some(PssSaltLen::Maximum),
+        ..Default::default()
+    };
+    assert!(httpsig::verify_request_with_options(
+        &request,
+        MessageDigest::sha256(),
+        &public_key,
+        &options
+    )?);
+    assert!(!httpsig::verify_request(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "reqwest")]
+
+mod common;
+
+use crate::common::*;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+// Accepts a single connection and returns the raw request it received
+fn serve_once(listener: TcpListener) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = Vec::new();
+        let mut buf = [0; 1024];
+
+        while !received.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = stream.read(&mut buf).unwrap();
+            received.extend_from_slice(&buf[..n]);
+        }
+
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        received
+    })
+}
+
+#[test]
+fn signing_client_signs_requests() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/foo?param=value", listener.local_addr()?);
+    let server = serve_once(listener);
+
+    let client = httpsig::SigningClient::new(
+        reqwest::Client::new(),
+        httpsig::SignatureParams::new("Test", MessageDigest::sha256()),
+        private_key,
+    );
+    let response = client
+        .get(&url)
+        .header("date", "Sun, 05 Jan 2014 21:31:40 GMT")
+        .send()?;
+    assert!(response.status().is_success());
+
+    let received = server.join().unwrap();
+    let request = httpsig::parse_request(&received, httpsig::LineFolding::Reject)?;
+
+    let parts = httpsig::find_signature_in_request(&request)?;
+    assert_eq!(parts.key_id, "Test");
+    assert_eq!(parts.headers, Some("(request-target) date host"));
+    assert!(httpsig::verify_request(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn signing_client_build() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+
+    let client = httpsig::SigningClient::new(
+        reqwest::Client::new(),
+        httpsig::SignatureParams::new("Test", MessageDigest::sha256()),
+        private_key,
+    );
+    let request = client.post("https://example.com:8443/inbox").build()?;
+
+    assert_eq!(request.headers().get("host").unwrap(), "example.com:8443");
+    assert!(request.headers().contains_key("signature"));
+
+    Ok(())
+}
+
+#[test]
+fn sign_reqwest_request() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let params = httpsig::SignatureParams::new("Test", MessageDigest::sha256());
+
+    let mut request = reqwest::Client::new()
+        .post("https://example.com/foo?param=value")
+        .header("date", "Sun, 05 Jan 2014 21:31:40 GMT")
+        .build()?;
+    httpsig::sign_reqwest_request(&mut request, &params, &private_key)?;
+
+    let mut http_request = http::Request::post("/foo?param=value").body(())?;
+    *http_request.headers_mut() = request.headers().clone();
+    assert_eq!(http_request.headers().get("host").unwrap(), "example.com");
+    assert!(httpsig::verify_request(
+        &http_request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    Ok(())
+}
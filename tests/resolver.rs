@@ -0,0 +1,143 @@
+#![cfg(feature = "http-resolver")]
+
+mod common;
+
+use crate::common::*;
+use httpsig::resolver::HttpKeyResolver;
+use httpsig::KeyResolver;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+// Answers a single request with `status` and `body`, returning the raw request it received
+fn serve_once(
+    listener: TcpListener,
+    status: &'static str,
+    body: String,
+) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = Vec::new();
+        let mut buf = [0; 1024];
+
+        while !received.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = stream.read(&mut buf).unwrap();
+            received.extend_from_slice(&buf[..n]);
+        }
+
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: application/activity+json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+        .unwrap();
+        String::from_utf8(received).unwrap()
+    })
+}
+
+#[test]
+fn resolve_actor_key() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let actor = format!("http://{}/users/alice", listener.local_addr()?);
+    let key_id = format!("{}#main-key", actor);
+    let document = serde_json::json!({
+        "id": actor,
+        "type": "Person",
+        "publicKey": {
+            "id": key_id,
+            "owner": actor,
+            "publicKeyPem": std::str::from_utf8(PUBLIC_PEM)?,
+        },
+    });
+    let server = serve_once(listener, "200 OK", document.to_string());
+
+    let resolver = HttpKeyResolver::new(reqwest::Client::new());
+    let mut request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header(&mut request, &key_id, MessageDigest::sha256(), &private_key)?;
+    let verified = httpsig::verify_request_with_resolver(&request, &resolver)?;
+    assert_eq!(verified.key_id, key_id);
+
+    let received = server.join().unwrap();
+    assert!(received.starts_with("GET /users/alice HTTP/1.1\r\n"));
+    assert!(received
+        .to_ascii_lowercase()
+        .contains("accept: application/activity+json, application/ld+json\r\n"));
+
+    // Cached, since the server is gone
+    let key = resolver.resolve(&key_id)?.unwrap();
+    assert!(key.public_eq(&public_key));
+
+    Ok(())
+}
+
+#[test]
+fn unresolvable_keys() -> Result<(), BoxError> {
+    let resolver = HttpKeyResolver::new(reqwest::Client::new());
+    assert!(resolver.resolve("Test")?.is_none());
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let key_id = format!("http://{}/gone#main-key", listener.local_addr()?);
+    let server = serve_once(listener, "410 Gone", String::new());
+    assert!(resolver.resolve(&key_id)?.is_none());
+    server.join().unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let key_id = format!("http://{}/users/bob#main-key", listener.local_addr()?);
+    let server = serve_once(listener, "200 OK", r#"{"id":"bob"}"#.to_owned());
+    match resolver.resolve(&key_id) {
+        Err(httpsig::HttpSigError::InvalidKeyDocument { key_id: id }) => assert_eq!(id, key_id),
+        other => panic!("expected InvalidKeyDocument, got {:?}", other.map(|_| ())),
+    }
+    server.join().unwrap();
+
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn async_resolver_expires_keys() -> Result<(), BoxError> {
+    use httpsig::resolver::{AsyncHttpKeyResolver, AsyncKeyResolver};
+    use std::time::Duration;
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    let block_on = |future| runtime.block_on(future);
+
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let key_id = format!("http://{}/users/alice#main-key", listener.local_addr()?);
+    let document = serde_json::json!({
+        "id": key_id,
+        "publicKeyPem": std::str::from_utf8(PUBLIC_PEM)?,
+    });
+    let server = serve_once(listener, "200 OK", document.to_string());
+
+    let client = reqwest::Client::new();
+    let resolver = AsyncHttpKeyResolver::new(client.clone(), Duration::from_secs(60));
+    let key = block_on(resolver.resolve(&key_id))?.unwrap();
+    assert!(key.public_eq(&public_key));
+    server.join().unwrap();
+
+    // Cached, since the server is gone
+    let key = block_on(resolver.resolve(&key_id))?.unwrap();
+    assert!(key.public_eq(&public_key));
+
+    // With no TTL the key is fetched again, which fails now that the server is gone
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let key_id = format!("http://{}/users/bob#main-key", listener.local_addr()?);
+    let server = serve_once(listener, "200 OK", document.to_string());
+    let resolver = AsyncHttpKeyResolver::new(client, Duration::from_secs(0));
+    assert!(block_on(resolver.resolve(&key_id))?.is_some());
+    server.join().unwrap();
+    assert!(block_on(resolver.resolve(&key_id)).is_err());
+
+    assert!(block_on(resolver.resolve("Test"))?.is_none());
+
+    Ok(())
+}
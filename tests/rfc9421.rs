@@ -0,0 +1,703 @@
+mod common;
+
+use crate::common::*;
+use httpsig::rfc9421::{self, ComponentId};
+use httpsig::HttpSigError;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use std::time::{Duration, UNIX_EPOCH};
+
+// https://www.rfc-editor.org/rfc/rfc9421.html#section-2.1.2
+fn dictionary_request(example_dict: &str) -> Result<http::Request<()>, BoxError> {
+    Ok(http::Request::post("/foo")
+        .header("host", "example.com")
+        .header("example-dict", example_dict)
+        .body(())?)
+}
+
+#[test]
+fn component_id_round_trip() -> Result<(), BoxError> {
+    for input in &[
+        r#""date""#,
+        r#""@method""#,
+        r#""example-dict";sf"#,
+        r#""example-dict";key="a""#,
+        r#""example-header";bs"#,
+        r#""expires";tr"#,
+    ] {
+        assert_eq!(ComponentId::parse(input)?.to_string(), *input);
+    }
+
+    assert_eq!(
+        ComponentId::parse(r#""Example-Dict";key="b""#)?,
+        ComponentId::new("example-dict").with_key("b")
+    );
+
+    for input in &["date", r#""date";bs=?0"#, r#""date";key=1"#] {
+        match ComponentId::parse(input) {
+            Err(HttpSigError::InvalidComponent { .. }) => {}
+            other => panic!("expected invalid component error, got {:?}", other),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn dictionary_member_values() -> Result<(), BoxError> {
+    let request = dictionary_request("  a=1,    b=2;x=1;y=2,   c=(a   b   c)")?;
+    let value = |component: ComponentId| -> Result<String, BoxError> {
+        Ok(String::from_utf8(rfc9421::component_value(
+            &request, &component,
+        )?)?)
+    };
+
+    assert_eq!(
+        value(ComponentId::new("example-dict"))?,
+        "a=1,    b=2;x=1;y=2,   c=(a   b   c)"
+    );
+    assert_eq!(value(ComponentId::new("example-dict").with_key("a"))?, "1");
+    assert_eq!(
+        value(ComponentId::new("example-dict").with_key("b"))?,
+        "2;x=1;y=2"
+    );
+    assert_eq!(
+        value(ComponentId::new("example-dict").with_key("c"))?,
+        "(a b c)"
+    );
+    assert_eq!(
+        value(ComponentId::new("example-dict").with_sf())?,
+        "a=1, b=2;x=1;y=2, c=(a b c)"
+    );
+
+    match rfc9421::component_value(&request, &ComponentId::new("example-dict").with_key("d")) {
+        Err(HttpSigError::MissingDictionaryKey { name, key }) => {
+            assert_eq!((name.as_str(), key.as_str()), ("example-dict", "d"))
+        }
+        other => panic!("expected missing dictionary key error, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn structured_field_serialization() -> Result<(), BoxError> {
+    let request = dictionary_request(r#"a=1.50, b=?1, c="x\"y", d=:aGVsbG8=:, e=-0.250;p"#)?;
+    assert_eq!(
+        rfc9421::component_value(&request, &ComponentId::new("example-dict").with_sf())?,
+        br#"a=1.5, b, c="x\"y", d=:aGVsbG8=:, e=-0.25;p"#
+    );
+
+    let request = dictionary_request("a=1,")?;
+    match rfc9421::component_value(&request, &ComponentId::new("example-dict").with_key("a")) {
+        Err(HttpSigError::InvalidStructuredField { name }) => assert_eq!(name, "example-dict"),
+        other => panic!("expected invalid structured field error, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn signature_base() -> Result<(), BoxError> {
+    let request = dictionary_request("a=1, b=2")?;
+    let components = [
+        ComponentId::new("@method"),
+        ComponentId::new("@authority"),
+        ComponentId::new("example-dict").with_key("b"),
+    ];
+
+    assert_eq!(
+        String::from_utf8(rfc9421::signature_base(
+            &request,
+            &components,
+            r#";keyid="test""#
+        )?)?,
+        "\"@method\": POST\n\
+         \"@authority\": example.com\n\
+         \"example-dict\";key=\"b\": 2\n\
+         \"@signature-params\": (\"@method\" \"@authority\" \"example-dict\";key=\"b\");keyid=\"test\""
+    );
+
+    Ok(())
+}
+
+// https://www.rfc-editor.org/rfc/rfc9421.html#section-2.2.2
+#[test]
+fn target_uri_and_scheme() -> Result<(), BoxError> {
+    let request = http::Request::post("https://www.example.com/path?param=value")
+        .header("host", "www.example.com")
+        .body(())?;
+    let components = [ComponentId::new("@target-uri"), ComponentId::new("@scheme")];
+
+    assert_eq!(
+        String::from_utf8(rfc9421::signature_base(&request, &components, "")?)?,
+        "\"@target-uri\": https://www.example.com/path?param=value\n\
+         \"@scheme\": https\n\
+         \"@signature-params\": (\"@target-uri\" \"@scheme\")"
+    );
+
+    // A server only sees the path, so it has to rebuild the URI first
+    let request = dictionary_request("a=1")?;
+    for component in &components {
+        match rfc9421::component_value(&request, component) {
+            Err(HttpSigError::InvalidComponent { component: name }) => {
+                assert_eq!(name, component.to_string())
+            }
+            other => panic!("expected invalid component, got {:?}", other),
+        }
+    }
+
+    Ok(())
+}
+
+// RFC 9421 doesn't define an order for repeated query parameters, so they're covered with `@query`
+#[test]
+fn repeated_query_params() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let components = [ComponentId::new("@query"), ComponentId::query_param("page")];
+    let params = r#";keyid="Test""#;
+    let request = |uri: &str| http::Request::get(uri).body(()).unwrap();
+
+    let signed = request("/search?tag=b&page=2&tag=a");
+    assert_eq!(
+        String::from_utf8(rfc9421::signature_base(&signed, &components, params)?)?,
+        "\"@query\": ?tag=b&page=2&tag=a\n\
+         \"@query-param\";name=\"page\": 2\n\
+         \"@signature-params\": (\"@query\" \"@query-param\";name=\"page\");keyid=\"Test\""
+    );
+
+    let signature = rfc9421::compute_signature(
+        &signed,
+        &components,
+        params,
+        MessageDigest::sha256(),
+        &private_key,
+    )?;
+    let verify = |uri: &str| -> Result<bool, HttpSigError> {
+        rfc9421::verify_signature(
+            &request(uri),
+            &components,
+            params,
+            &signature,
+            MessageDigest::sha256(),
+            &public_key,
+        )
+    };
+
+    assert!(verify("/search?tag=b&page=2&tag=a")?);
+    // The values of a repeated parameter can't be swapped
+    assert!(!verify("/search?tag=a&page=2&tag=b")?);
+
+    // Covering the repeated parameter on its own is an error for both signer and verifier
+    let components = [ComponentId::query_param("tag")];
+    match rfc9421::signature_base(&signed, &components, params) {
+        Err(HttpSigError::RepeatedQueryParam { name }) => assert_eq!(name, "tag"),
+        other => panic!("expected repeated query param error, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+// Only the signed member of the dictionary is protected
+#[test]
+fn sign_dictionary_member() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let components = [
+        ComponentId::new("@method"),
+        ComponentId::new("example-dict").with_key("a"),
+    ];
+    let params = r#";created=1618884473;keyid="Test""#;
+
+    let request = dictionary_request("a=1, b=2")?;
+    let signature = rfc9421::compute_signature(
+        &request,
+        &components,
+        params,
+        MessageDigest::sha256(),
+        &private_key,
+    )?;
+
+    let verify = |example_dict: &str| -> Result<bool, BoxError> {
+        Ok(rfc9421::verify_signature(
+            &dictionary_request(example_dict)?,
+            &components,
+            params,
+            &signature,
+            MessageDigest::sha256(),
+            &public_key,
+        )?)
+    };
+
+    assert!(verify("a=1, b=2")?);
+    assert!(verify("b=3,   a=1")?);
+    assert!(!verify("a=2, b=2")?);
+
+    Ok(())
+}
+
+// Plain header values are covered byte for byte, but structured fields have to be ASCII
+#[test]
+fn non_utf8_header_value() -> Result<(), BoxError> {
+    let request = http::Request::post("/foo")
+        .header("x-name", &b"caf\xe9"[..])
+        .body(())?;
+
+    assert_eq!(
+        rfc9421::component_value(&request, &ComponentId::new("x-name"))?,
+        b"caf\xe9"
+    );
+    match rfc9421::component_value(&request, &ComponentId::new("x-name").with_sf()) {
+        Err(HttpSigError::InvalidStructuredField { .. }) => {}
+        other => panic!("expected invalid structured field error, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+// https://www.rfc-editor.org/rfc/rfc9421.html#section-2.1.3
+#[test]
+fn byte_sequence_values() -> Result<(), BoxError> {
+    let request = http::Request::post("/foo")
+        .header("example-header", "value, with, lots")
+        .header("example-header", "of, commas")
+        .header("x-binary", &b" caf\xe9 \xff\t"[..])
+        .body(())?;
+    let value = |component: &ComponentId| -> Result<String, HttpSigError> {
+        Ok(String::from_utf8(rfc9421::component_value(&request, component)?).unwrap())
+    };
+
+    // Each value is encoded separately, so the commas in them are kept apart
+    assert_eq!(
+        value(&ComponentId::new("example-header").with_bs())?,
+        ":dmFsdWUsIHdpdGgsIGxvdHM=:, :b2YsIGNvbW1hcw==:"
+    );
+    // Surrounding whitespace isn't covered, but any other byte is
+    assert_eq!(
+        value(&ComponentId::new("x-binary").with_bs())?,
+        ":Y2Fm6SD/:"
+    );
+
+    for component in &[
+        ComponentId::new("example-header").with_bs().with_sf(),
+        ComponentId::new("example-header").with_bs().with_key("a"),
+        ComponentId::new("@method").with_bs(),
+    ] {
+        match value(component) {
+            Err(HttpSigError::InvalidComponent { .. }) => {}
+            other => panic!(
+                "expected invalid component for {}, got {:?}",
+                component, other
+            ),
+        }
+    }
+
+    // A signature over the byte sequences verifies
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let components = [ComponentId::parse(r#""x-binary";bs"#)?];
+    let signature = rfc9421::compute_signature(
+        &request,
+        &components,
+        r#";keyid="Test""#,
+        MessageDigest::sha256(),
+        &private_key,
+    )?;
+    assert!(rfc9421::verify_signature(
+        &request,
+        &components,
+        r#";keyid="Test""#,
+        &signature,
+        MessageDigest::sha256(),
+        &public_key,
+    )?);
+
+    Ok(())
+}
+
+// https://www.rfc-editor.org/rfc/rfc9421.html#section-2.1.4
+#[test]
+fn trailer_fields() -> Result<(), BoxError> {
+    // Stands in for a server that reads the trailers after a chunked body
+    let mut trailers = http::HeaderMap::new();
+    trailers.insert("expires", "Wed, 9 Nov 2022 07:28:00 GMT".parse()?);
+    let mut request = http::Request::post("/foo")
+        .header("trailer", "expires")
+        .header("expires", "Thu, 1 Jan 1970 00:00:00 GMT")
+        .body(())?;
+    request.extensions_mut().insert(rfc9421::Trailers(trailers));
+
+    let value = |request: &http::Request<()>, component: &ComponentId| {
+        rfc9421::component_value(request, component).map(|value| String::from_utf8(value).unwrap())
+    };
+    assert_eq!(
+        value(&request, &ComponentId::new("expires").with_tr())?,
+        "Wed, 9 Nov 2022 07:28:00 GMT"
+    );
+    assert_eq!(
+        value(&request, &ComponentId::new("expires"))?,
+        "Thu, 1 Jan 1970 00:00:00 GMT"
+    );
+    match value(&request, &ComponentId::new("@method").with_tr()) {
+        Err(HttpSigError::InvalidComponent { .. }) => {}
+        other => panic!("expected invalid component error, got {:?}", other),
+    }
+    match value(&request, &ComponentId::new("digest").with_tr()) {
+        Err(HttpSigError::MissingHeader { ref name }) if name == "digest" => {}
+        other => panic!("expected missing header error, got {:?}", other),
+    }
+
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let components = [
+        ComponentId::new("@method"),
+        ComponentId::parse(r#""expires";tr"#)?,
+    ];
+    assert_eq!(
+        rfc9421::signature_base(&request, &components, r#";keyid="Test""#)?,
+        concat!(
+            "\"@method\": POST\n",
+            "\"expires\";tr: Wed, 9 Nov 2022 07:28:00 GMT\n",
+            "\"@signature-params\": (\"@method\" \"expires\";tr);keyid=\"Test\"",
+        )
+        .as_bytes()
+    );
+    let signature = rfc9421::compute_signature(
+        &request,
+        &components,
+        r#";keyid="Test""#,
+        MessageDigest::sha256(),
+        &private_key,
+    )?;
+    let verify = |request: &http::Request<()>| {
+        rfc9421::verify_signature(
+            request,
+            &components,
+            r#";keyid="Test""#,
+            &signature,
+            MessageDigest::sha256(),
+            &public_key,
+        )
+    };
+    assert!(verify(&request)?);
+
+    // Changing the header of the same name doesn't matter, but changing the trailer does
+    request
+        .headers_mut()
+        .insert("expires", "Fri, 2 Jan 1970 00:00:00 GMT".parse()?);
+    assert!(verify(&request)?);
+    let mut trailers = http::HeaderMap::new();
+    trailers.insert("expires", "Thu, 10 Nov 2022 07:28:00 GMT".parse()?);
+    request.extensions_mut().insert(rfc9421::Trailers(trailers));
+    assert!(!verify(&request)?);
+
+    // Without trailers, the field is missing
+    request.extensions_mut().clear();
+    match verify(&request) {
+        Err(HttpSigError::MissingHeader { .. }) => {}
+        other => panic!("expected missing header error, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+// https://www.rfc-editor.org/rfc/rfc9421.html#section-2.2.8
+#[test]
+fn query_param_values() -> Result<(), BoxError> {
+    let request = http::Request::get(
+        "/parameters?var=this%20is%20a%20big%0Amultiline%20value&bar=with+plus+whitespace&fa%C3%A7ade%22%3A%20=something&dup=1&dup=2",
+    )
+    .body(())?;
+    let value = |name: &str| -> Result<String, HttpSigError> {
+        let value = rfc9421::component_value(&request, &ComponentId::query_param(name))?;
+        Ok(String::from_utf8(value).unwrap())
+    };
+
+    assert_eq!(value("var")?, "this%20is%20a%20big%0Amultiline%20value");
+    assert_eq!(value("bar")?, "with%20plus%20whitespace");
+    assert_eq!(value("fa%C3%A7ade%22%3A%20")?, "something");
+
+    match value("missing") {
+        Err(HttpSigError::MissingQueryParam { name }) => assert_eq!(name, "missing"),
+        other => panic!("expected missing query param error, got {:?}", other),
+    }
+    match value("dup") {
+        Err(HttpSigError::RepeatedQueryParam { name }) => assert_eq!(name, "dup"),
+        other => panic!("expected repeated query param error, got {:?}", other),
+    }
+
+    let component = ComponentId::parse(r#""@query-param";name="var""#)?;
+    assert_eq!(component, ComponentId::query_param("var"));
+    assert_eq!(component.to_string(), r#""@query-param";name="var""#);
+
+    for component in &[
+        ComponentId::new("@query-param"),
+        ComponentId::parse(r#""date";name="var""#)?,
+    ] {
+        match rfc9421::component_value(&request, component) {
+            Err(HttpSigError::InvalidComponent { .. }) => {}
+            other => panic!("expected invalid component error, got {:?}", other),
+        }
+    }
+
+    Ok(())
+}
+
+// Only the signed query parameter is protected
+#[test]
+fn sign_query_param() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let components = [ComponentId::new("@method"), ComponentId::query_param("id")];
+    let params = r#";created=1618884473;keyid="Test""#;
+
+    let request = |uri: &str| http::Request::get(uri).body(());
+    let signature = rfc9421::compute_signature(
+        &request("/items?id=a+b&page=1")?,
+        &components,
+        params,
+        MessageDigest::sha256(),
+        &private_key,
+    )?;
+
+    let verify = |uri: &str| -> Result<bool, BoxError> {
+        Ok(rfc9421::verify_signature(
+            &request(uri)?,
+            &components,
+            params,
+            &signature,
+            MessageDigest::sha256(),
+            &public_key,
+        )?)
+    };
+
+    assert!(verify("/items?id=a+b&page=1")?);
+    assert!(verify("/items?page=2&id=a%20b")?);
+    assert!(!verify("/items?id=c&page=1")?);
+
+    Ok(())
+}
+
+#[test]
+fn accept_signature() -> Result<(), BoxError> {
+    // From RFC 9421, section 5.1
+    let header = r#"sig1=("@method" "@target-uri" "@authority" "content-digest" "cache-control");keyid="test-key-rsa-pss";created;tag="app-123""#;
+
+    let requested = rfc9421::parse_accept_signature(header)?;
+    assert_eq!(requested.len(), 1);
+    let sig1 = &requested[0];
+    assert_eq!(sig1.label, "sig1");
+    assert_eq!(
+        sig1.components,
+        [
+            ComponentId::new("@method"),
+            ComponentId::new("@target-uri"),
+            ComponentId::new("@authority"),
+            ComponentId::new("content-digest"),
+            ComponentId::new("cache-control"),
+        ]
+    );
+    assert_eq!(sig1.key_id.as_deref(), Some("test-key-rsa-pss"));
+    assert_eq!(sig1.algorithm, None);
+    assert!(sig1.created);
+    assert_eq!(sig1.tag.as_deref(), Some("app-123"));
+
+    // The signing plan: which components to cover, and with which parameters
+    let created = UNIX_EPOCH + Duration::from_secs(1_618_884_473);
+    assert_eq!(
+        sig1.signature_params(created),
+        r#";created=1618884473;keyid="test-key-rsa-pss";tag="app-123""#
+    );
+
+    assert_eq!(
+        rfc9421::accept_signature_header(&requested),
+        r#"sig1=("@method" "@target-uri" "@authority" "content-digest" "cache-control");created;keyid="test-key-rsa-pss";tag="app-123""#
+    );
+
+    for invalid in &[r#"sig1="@method""#, "sig1=(@method)", r#"sig1=();keyid=1"#] {
+        match rfc9421::parse_accept_signature(invalid) {
+            Err(HttpSigError::InvalidStructuredField { name }) => {
+                assert_eq!(name, "accept-signature")
+            }
+            other => panic!("expected invalid field for {}, got {:?}", invalid, other),
+        }
+    }
+
+    Ok(())
+}
+
+// A client answers a server's `Accept-Signature` with a signature the server verifies
+#[test]
+fn answer_accept_signature() -> Result<(), BoxError> {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine as _;
+
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let accept_signature = rfc9421::accept_signature_header(&[rfc9421::RequestedSignature {
+        key_id: Some("Test".to_owned()),
+        created: true,
+        ..rfc9421::RequestedSignature::new(
+            "sig1",
+            vec![
+                ComponentId::new("@method"),
+                ComponentId::new("@target-uri"),
+                ComponentId::new("content-digest"),
+            ],
+        )
+    }]);
+
+    let mut request = http::Request::post("https://example.com/foo?param=value")
+        .header("host", "example.com")
+        .header(
+            "content-digest",
+            "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:",
+        )
+        .body(())?;
+
+    for requested in rfc9421::parse_accept_signature(&accept_signature)? {
+        let params = requested.signature_params(UNIX_EPOCH + Duration::from_secs(1_618_884_473));
+        let signature = rfc9421::compute_signature(
+            &request,
+            &requested.components,
+            &params,
+            MessageDigest::sha256(),
+            &private_key,
+        )?;
+
+        let components: Vec<_> = requested
+            .components
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let signature_input = format!("{}=({}){}", requested.label, components.join(" "), params);
+        let signature = format!("{}=:{}:", requested.label, STANDARD.encode(&signature));
+        request
+            .headers_mut()
+            .insert("signature-input", signature_input.parse()?);
+        request
+            .headers_mut()
+            .insert("signature", signature.parse()?);
+    }
+
+    assert_eq!(
+        request.headers()["signature-input"],
+        r#"sig1=("@method" "@target-uri" "content-digest");created=1618884473;keyid="Test""#
+    );
+    assert!(rfc9421::verify_request(
+        &request,
+        "sig1",
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    Ok(())
+}
+
+// The `Signature-Input` member is the `@signature-params` line, so it's covered by the signature
+#[test]
+fn verify_signature_input() -> Result<(), BoxError> {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine as _;
+
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let components = [
+        ComponentId::new("@method"),
+        ComponentId::new("@authority"),
+        ComponentId::new("example-dict").with_key("a"),
+    ];
+    let params = r#";created=1618884473;keyid="Test""#;
+    let signature_input = format!(
+        r#"sig1=("@method" "@authority" "example-dict";key="a"){}"#,
+        params
+    );
+
+    let request = dictionary_request("a=1, b=2")?;
+    let signature = rfc9421::compute_signature(
+        &request,
+        &components,
+        params,
+        MessageDigest::sha256(),
+        &private_key,
+    )?;
+    let signature = format!("sig1=:{}:", STANDARD.encode(&signature));
+
+    let verify = |label: &str, signature_input: &str| -> Result<bool, HttpSigError> {
+        let mut request = dictionary_request("a=1, b=2").unwrap();
+        let headers = request.headers_mut();
+        headers.insert("signature-input", signature_input.parse().unwrap());
+        headers.insert("signature", signature.parse().unwrap());
+        rfc9421::verify_request(&request, label, MessageDigest::sha256(), &public_key)
+    };
+
+    assert!(verify("sig1", &signature_input)?);
+    // Other signatures in the same headers are ignored, and whitespace isn't significant
+    assert!(verify(
+        "sig1",
+        &format!(r#"sig0=("@method");keyid="other",  {}"#, signature_input)
+    )?);
+
+    // Declaring fewer components, different ones, or different parameters breaks the signature
+    for tampered in &[
+        format!(r#"sig1=("@method" "@authority"){}"#, params),
+        format!(
+            r#"sig1=("@method" "@authority" "example-dict";key="b"){}"#,
+            params
+        ),
+        r#"sig1=("@method" "@authority" "example-dict";key="a");created=1618884474;keyid="Test""#
+            .to_owned(),
+        r#"sig1=("@method" "@authority" "example-dict";key="a");created=1618884473"#.to_owned(),
+    ] {
+        assert!(!verify("sig1", tampered)?, "{}", tampered);
+    }
+
+    match verify("sig2", &signature_input) {
+        Err(HttpSigError::MissingSignature) => {}
+        other => panic!("expected missing signature, got {:?}", other),
+    }
+    match verify("sig1", "sig1=:abc:") {
+        Err(HttpSigError::InvalidStructuredField { name }) => assert_eq!(name, "signature-input"),
+        other => panic!("expected invalid signature-input, got {:?}", other),
+    }
+    match rfc9421::verify_request(&request, "sig1", MessageDigest::sha256(), &public_key) {
+        Err(HttpSigError::MissingHeader { name }) => assert_eq!(name, "signature-input"),
+        other => panic!("expected missing signature-input, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn signature_base_from_params() -> Result<(), BoxError> {
+    use httpsig::SignatureParams;
+
+    let request = dictionary_request("a=1, b=2")?;
+    let components = [ComponentId::new("@method"), ComponentId::new("host")];
+    let params = SignatureParams {
+        nonce: Some("abc123".to_owned()),
+        ..SignatureParams::new(r#"key "1""#, MessageDigest::sha256())
+    };
+
+    assert_eq!(
+        String::from_utf8(httpsig::compute_rfc9421_signature_base(
+            &request,
+            &components,
+            &params
+        )?)?,
+        "\"@method\": POST\n\
+         \"host\": example.com\n\
+         \"@signature-params\": (\"@method\" \"host\");keyid=\"key \\\"1\\\"\";nonce=\"abc123\""
+    );
+
+    // The same bytes as passing the parameters by hand
+    let params = SignatureParams::new("Test", MessageDigest::sha256())
+        .with_key_id_transform(|key_id| format!("{}#main-key", key_id));
+    assert_eq!(
+        httpsig::compute_rfc9421_signature_base(&request, &components, &params)?,
+        rfc9421::signature_base(&request, &components, r#";keyid="Test#main-key""#)?
+    );
+
+    Ok(())
+}
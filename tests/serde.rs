@@ -0,0 +1,54 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use crate::common::*;
+use httpsig::{OwnedSignatureParts, SignatureParts};
+
+const SIGNATURE_HEADER: &str = r#"keyId="Test",algorithm="rsa-sha256",headers="(request-target) host date",signature="qdx+H7PHHDZgy4y/Ahn9Tny9V3GP6YgBPyUXMmoxWtLbHpUnXS2mg2+SbrQDMCJypxBLSPQR2aAjn7ndmw2iicw3HMbe8VfEdKFYRqzic+efkb3nndiv/x1xSHDJWeSWkx3ButlYSuBskLu6kd9Fswtemr3lgdDEmn04swr2Os0=""#;
+
+#[test]
+fn signature_parts_json_round_trip() -> Result<(), BoxError> {
+    let parts = httpsig::parse_signature_parts(SIGNATURE_HEADER).unwrap();
+
+    let json = serde_json::to_string(&parts)?;
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&json)?,
+        serde_json::json!({
+            "headers": "(request-target) host date",
+            "keyId": "Test",
+            "signature": parts.signature,
+            "algorithm": "rsa-sha256",
+        })
+    );
+
+    let parsed: SignatureParts<'_> = serde_json::from_str(&json)?;
+    assert_eq!(parsed.to_string(), parts.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn owned_signature_parts_json_round_trip() -> Result<(), BoxError> {
+    let parts = httpsig::parse_signature_parts(SIGNATURE_HEADER).unwrap();
+    let owned = OwnedSignatureParts::from(&parts);
+
+    // Strings with escapes can't be borrowed from the input, so need the owned form
+    let json = serde_json::to_string(&owned)?.replace(r#""Test""#, r#""T\u0065st""#);
+    assert!(json.contains(r#""keyId":"T\u0065st""#));
+    let parsed: OwnedSignatureParts = serde_json::from_str(&json)?;
+    assert_eq!(parsed, owned);
+    assert_eq!(parsed.as_parts().to_string(), SIGNATURE_HEADER);
+
+    Ok(())
+}
+
+#[test]
+fn signature_parts_json_optional_fields() -> Result<(), BoxError> {
+    let parsed: OwnedSignatureParts =
+        serde_json::from_str(r#"{"keyId": "Test", "signature": "c2lnbmF0dXJl"}"#)?;
+    assert_eq!(parsed.headers, None);
+    assert_eq!(parsed.algorithm, None);
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use crate::common::*;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use httpsig::{PseudoHeaderValues, SignatureParams, SigningStringTemplate};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+
+#[test]
+fn template_fill() -> Result<(), BoxError> {
+    let template = SigningStringTemplate::new(&["(request-target)", "Host", "date"]);
+    let request = parse_request(HTTP_REQUEST);
+
+    assert_eq!(
+        String::from_utf8(template.fill(&request)?)?,
+        "(request-target): post /foo?param=value&pet=dog\n\
+         host: example.com\n\
+         date: Sun, 05 Jan 2014 21:31:40 GMT"
+    );
+
+    Ok(())
+}
+
+// Signing the filled template is equivalent to signing the same headers directly
+#[test]
+fn template_signature_verifies() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let template = SigningStringTemplate::new(&[
+        "(request-target)",
+        "host",
+        "date",
+        "content-type",
+        "digest",
+        "content-length",
+    ]);
+
+    let mut request = parse_request(HTTP_REQUEST);
+    let mut signer = Signer::new(MessageDigest::sha256(), &private_key)?;
+    signer.update(&template.fill(&request)?)?;
+    let signature = STANDARD.encode(&signer.sign_to_vec()?);
+
+    // Appendix C.3
+    assert_eq!(signature, "vSdrb+dS3EceC9bcwHSo4MlyKS59iFIrhgYkz8+oVLEEzmYZZvRs8rgOp+63LEM3v+MFHB32NfpB2bEKBIvB1q52LaEUHFv120V01IL+TAD48XaERZFukWgHoBTLMhYS2Gb51gWxpeIq8knRmPnYePbF5MOkR0Zkly4zKH7s1dE=");
+
+    let header = format!(
+        r#"keyId="Test",headers="(request-target) host date content-type digest content-length",signature="{}""#,
+        signature
+    );
+    request.headers_mut().insert("signature", header.parse()?);
+    assert!(httpsig::verify_request(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn template_missing_header() {
+    let template = SigningStringTemplate::new(&["(request-target)", "x-missing"]);
+    let request = parse_request(HTTP_REQUEST);
+
+    match template.fill(&request) {
+        Err(httpsig::HttpSigError::MissingHeader { name }) => assert_eq!(name, "x-missing"),
+        other => panic!("expected missing header error, got {:?}", other),
+    }
+}
+
+#[test]
+fn template_pseudo_headers() -> Result<(), BoxError> {
+    let template =
+        SigningStringTemplate::new(&["(request-target)", "(created)", "(expires)", "(nonce)"]);
+    let request = parse_request(HTTP_REQUEST);
+
+    let values = PseudoHeaderValues {
+        created: Some(1402170695),
+        expires: Some(1402170995),
+        nonce: Some("abc"),
+    };
+    assert_eq!(
+        String::from_utf8(template.fill_with_pseudo_headers(&request, &values)?)?,
+        "(request-target): post /foo?param=value&pet=dog\n\
+         (created): 1402170695\n\
+         (expires): 1402170995\n\
+         (nonce): abc"
+    );
+
+    // They aren't part of the request, so `fill` can't find them
+    match template.fill(&request) {
+        Err(httpsig::HttpSigError::MissingHeader { name }) => assert_eq!(name, "(created)"),
+        other => panic!("expected missing header error, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+// A nonce signed through the template matches one signed with `SignatureParams::nonce`
+#[test]
+fn template_nonce_signature() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let headers = ["(request-target)", "host", "date", "(nonce)"];
+    let params = SignatureParams {
+        headers: Some(headers.iter().map(|&h| h.to_owned()).collect()),
+        nonce: Some("abc".to_owned()),
+        ..SignatureParams::new("Test", MessageDigest::sha256())
+    };
+    let mut request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header_with_params(&mut request, &params, &private_key)?;
+    let expected = httpsig::find_signature_in_request(&request)?
+        .signature
+        .to_owned();
+
+    let values = PseudoHeaderValues {
+        nonce: Some("abc"),
+        ..PseudoHeaderValues::default()
+    };
+    let mut signer = Signer::new(MessageDigest::sha256(), &private_key)?;
+    signer.update(
+        &SigningStringTemplate::new(&headers).fill_with_pseudo_headers(&request, &values)?,
+    )?;
+    assert_eq!(STANDARD.encode(signer.sign_to_vec()?), expected);
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use crate::common::*;
+use httpsig::test_support;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private, Public};
+use openssl::sign::{Signer, Verifier};
+
+fn assert_round_trip(
+    (private_key, public_key): (PKey<Private>, PKey<Public>),
+) -> Result<(), BoxError> {
+    let mut request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header(&mut request, "Test", MessageDigest::sha256(), &private_key)?;
+    assert!(httpsig::verify_request(
+        &request,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+    Ok(())
+}
+
+#[test]
+fn generated_keys_round_trip() -> Result<(), BoxError> {
+    assert_round_trip(test_support::generate_rsa(2048)?)?;
+    assert_round_trip(test_support::generate_ec_p256()?)?;
+
+    // Requests can't be signed with Ed25519 yet, so the key pair is checked directly
+    let (private_key, public_key) = test_support::generate_ed25519()?;
+    let signature = Signer::new_without_digest(&private_key)?.sign_oneshot_to_vec(b"message")?;
+    assert!(Verifier::new_without_digest(&public_key)?.verify_oneshot(&signature, b"message")?);
+
+    // Each call generates a new key
+    let (other, _) = test_support::generate_ed25519()?;
+    assert!(!other.public_eq(&public_key));
+
+    Ok(())
+}
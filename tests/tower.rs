@@ -0,0 +1,105 @@
+#![cfg(feature = "tower-http")]
+
+mod common;
+
+use crate::common::*;
+use futures::executor::block_on;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use std::convert::Infallible;
+use tower::{service_fn, ServiceBuilder, ServiceExt};
+
+// Returns the request as the inner service received it
+fn echo_service(
+    layer: httpsig::SigningLayer,
+) -> impl tower::Service<http::Request<Vec<u8>>, Response = http::Request<Vec<u8>>, Error = BoxError>
+{
+    ServiceBuilder::new().layer(layer).service(service_fn(
+        |request: http::Request<Vec<u8>>| async move { Ok::<_, Infallible>(request) },
+    ))
+}
+
+#[test]
+fn signing_layer() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+
+    let layer = httpsig::SigningLayer::new(
+        httpsig::SignatureParams::new("Test", MessageDigest::sha256()),
+        private_key,
+    )
+    .with_digest();
+
+    let request = http::Request::post("/foo?param=value&pet=dog")
+        .header("host", "example.com")
+        .header("date", "Sun, 05 Jan 2014 21:31:40 GMT")
+        .body(br#"{"hello": "world"}"#.to_vec())?;
+
+    let signed = block_on(echo_service(layer).oneshot(request))?;
+
+    assert_eq!(
+        signed.headers().get("digest").unwrap(),
+        "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE="
+    );
+    assert!(httpsig::verify_request(
+        &signed,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+    assert!(httpsig::verify_digest_header(&signed)?);
+
+    Ok(())
+}
+
+#[test]
+fn signing_layer_error() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+
+    let mut params = httpsig::SignatureParams::new("Test", MessageDigest::sha256());
+    params.headers = Some(vec!["(request-target)".to_owned(), "date".to_owned()]);
+    let layer = httpsig::SigningLayer::new(params, private_key);
+
+    let request = http::Request::get("/").body(Vec::new())?;
+    let error = block_on(echo_service(layer).oneshot(request)).unwrap_err();
+
+    match error.downcast_ref::<httpsig::HttpSigError>() {
+        Some(httpsig::HttpSigError::MissingHeader { name }) => assert_eq!(name, "date"),
+        other => panic!("expected MissingHeader, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn signing_layer_http1() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+
+    let layer = httpsig::SigningLayer::new(
+        httpsig::SignatureParams::new("Test", MessageDigest::sha256()),
+        private_key,
+    )
+    .with_digest();
+    let service = ServiceBuilder::new().layer(layer).service(service_fn(
+        |request: http1::Request<String>| async move { Ok::<_, Infallible>(request) },
+    ));
+
+    let request = http1::Request::post("http://example.com/foo?param=value&pet=dog")
+        .header("host", "example.com")
+        .header("date", "Sun, 05 Jan 2014 21:31:40 GMT")
+        .body(r#"{"hello": "world"}"#.to_owned())?;
+
+    let signed = block_on(service.oneshot(request))?;
+
+    assert_eq!(
+        signed.headers().get("digest").unwrap(),
+        "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE="
+    );
+    assert!(httpsig::verify_message(
+        &signed,
+        MessageDigest::sha256(),
+        &public_key
+    )?);
+
+    Ok(())
+}
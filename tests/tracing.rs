@@ -0,0 +1,139 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use crate::common::*;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+type SpanFields = HashMap<String, String>;
+
+// Collects the fields recorded on each span, keyed by span name, and the fields of each event
+#[derive(Clone, Default)]
+struct SpanCollector {
+    spans: Arc<Mutex<HashMap<Id, (&'static str, SpanFields)>>>,
+    events: Arc<Mutex<Vec<SpanFields>>>,
+}
+
+impl SpanCollector {
+    // Header values can hold credentials, so they should never be logged
+    fn assert_no_header_values(&self) {
+        let spans = self.spans.lock().unwrap();
+        let recorded = spans
+            .values()
+            .map(|(_, fields)| fields)
+            .chain(self.events.lock().unwrap().iter())
+            .flat_map(|fields| fields.values())
+            .cloned()
+            .collect::<Vec<_>>();
+        for value in recorded {
+            assert!(!value.contains("example.com"), "logged {:?}", value);
+            assert!(!value.contains("2014"), "logged {:?}", value);
+        }
+    }
+
+    fn fields(&self, name: &str) -> SpanFields {
+        self.spans
+            .lock()
+            .unwrap()
+            .values()
+            .find(|(span_name, _)| *span_name == name)
+            .map(|(_, fields)| fields.clone())
+            .unwrap_or_else(|| panic!("no span named {}", name))
+    }
+}
+
+struct FieldVisitor<'a>(&'a mut SpanFields);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_owned(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), value.to_owned());
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SpanCollector {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, S>) {
+        let mut fields = SpanFields::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        self.spans
+            .lock()
+            .unwrap()
+            .insert(id.clone(), (attrs.metadata().name(), fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        if let Some((_, fields)) = self.spans.lock().unwrap().get_mut(id) {
+            values.record(&mut FieldVisitor(fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut fields = SpanFields::new();
+        event.record(&mut FieldVisitor(&mut fields));
+        self.events.lock().unwrap().push(fields);
+    }
+}
+
+#[test]
+fn verification_spans() -> Result<(), BoxError> {
+    let collector = SpanCollector::default();
+    let subscriber = tracing_subscriber::registry().with(collector.clone());
+
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM)?;
+    let mut request = parse_request(HTTP_REQUEST);
+    request.headers_mut().insert(
+        "signature",
+        r#"keyId="Test",algorithm="rsa-sha256",headers="(request-target) host date",signature="qdx+H7PHHDZgy4y/Ahn9Tny9V3GP6YgBPyUXMmoxWtLbHpUnXS2mg2+SbrQDMCJypxBLSPQR2aAjn7ndmw2iicw3HMbe8VfEdKFYRqzic+efkb3nndiv/x1xSHDJWeSWkx3ButlYSuBskLu6kd9Fswtemr3lgdDEmn04swr2Os0=""#.parse()?,
+    );
+
+    let verified = tracing::subscriber::with_default(subscriber, || {
+        httpsig::verify_request(&request, MessageDigest::sha256(), &public_key)
+    })?;
+    assert!(verified);
+
+    collector.fields("verify_request");
+
+    let fields = collector.fields("verify_signature_parts");
+    assert_eq!(fields["key_id"], "Test");
+    assert_eq!(fields["algorithm"], "rsa-sha256");
+    assert_eq!(fields["signed_headers"], "(request-target) host date");
+    assert_eq!(fields["verification_result"], "true");
+    collector.assert_no_header_values();
+
+    Ok(())
+}
+
+#[test]
+fn signing_spans() -> Result<(), BoxError> {
+    let collector = SpanCollector::default();
+    let subscriber = tracing_subscriber::registry().with(collector.clone());
+
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let request = parse_request(HTTP_REQUEST);
+
+    tracing::subscriber::with_default(subscriber, || {
+        httpsig::compute_signature(&request, MessageDigest::sha256(), &private_key)
+    })?;
+
+    let fields = collector.fields("compute_signature");
+    assert_eq!(
+        fields["signed_headers"],
+        "(request-target) host date content-type digest content-length"
+    );
+    collector.assert_no_header_values();
+
+    Ok(())
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use crate::common::*;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use httpsig::{VerificationError, VerificationOptions};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use std::time::{Duration, UNIX_EPOCH};
+
+// `HTTP_REQUEST`'s date, Sun, 05 Jan 2014 21:31:40 GMT
+const REQUEST_TIME: u64 = 1_388_957_500;
+
+// Signs `HTTP_REQUEST`, adding `extra` (e.g. `,expires=123`) to the signature header
+fn signed_request(extra: &str) -> Result<http::Request<&'static [u8]>, BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let mut request = parse_request(HTTP_REQUEST);
+    let signature = STANDARD.encode(&httpsig::compute_signature(
+        &request,
+        MessageDigest::sha256(),
+        &private_key,
+    )?);
+    request.headers_mut().insert(
+        "signature",
+        format!(
+            r#"keyId="Test",headers="(request-target) host date content-type digest content-length",signature="{}"{}"#,
+            signature, extra
+        )
+        .parse()?,
+    );
+    Ok(request)
+}
+
+fn verify(
+    request: &http::Request<&[u8]>,
+    options: &VerificationOptions,
+) -> Result<(), VerificationError> {
+    let public_key = PKey::public_key_from_pem(PUBLIC_PEM).unwrap();
+    httpsig::verify_request_strict(request, MessageDigest::sha256(), &public_key, options)
+}
+
+fn at(seconds: u64) -> VerificationOptions {
+    VerificationOptions {
+        now: Some(UNIX_EPOCH + Duration::from_secs(seconds)),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn valid_signature() -> Result<(), BoxError> {
+    verify(&signed_request("")?, &VerificationOptions::default())?;
+    Ok(())
+}
+
+#[test]
+fn missing_signature_header() {
+    match verify(
+        &parse_request(HTTP_REQUEST),
+        &VerificationOptions::default(),
+    ) {
+        Err(VerificationError::MissingSignatureHeader) => {}
+        other => panic!("expected missing signature header, got {:?}", other),
+    }
+}
+
+#[test]
+fn missing_covered_header() -> Result<(), BoxError> {
+    let mut request = signed_request("")?;
+    request.headers_mut().remove("content-type");
+
+    match verify(&request, &VerificationOptions::default()) {
+        Err(VerificationError::MissingCoveredHeader { name }) => assert_eq!(name, "content-type"),
+        other => panic!("expected missing covered header, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn invalid_base64() -> Result<(), BoxError> {
+    let mut request = parse_request(HTTP_REQUEST);
+    request
+        .headers_mut()
+        .insert("signature", r#"keyId="Test",signature="!!!""#.parse()?);
+
+    match verify(&request, &VerificationOptions::default()) {
+        Err(VerificationError::InvalidBase64(_)) => {}
+        other => panic!("expected invalid base64, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn cryptographic_failure() -> Result<(), BoxError> {
+    let mut request = signed_request("")?;
+    request
+        .headers_mut()
+        .insert("content-type", "text/plain".parse()?);
+
+    match verify(&request, &VerificationOptions::default()) {
+        Err(VerificationError::CryptographicFailure) => {}
+        other => panic!("expected cryptographic failure, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn algorithm_mismatch() -> Result<(), BoxError> {
+    match verify(
+        &signed_request(r#",algorithm="ecdsa-sha256""#)?,
+        &VerificationOptions::default(),
+    ) {
+        Err(VerificationError::AlgorithmMismatch { declared, key_type }) => {
+            assert_eq!(
+                (declared.as_str(), key_type.as_str()),
+                ("ecdsa-sha256", "rsa")
+            )
+        }
+        other => panic!("expected algorithm mismatch, got {:?}", other),
+    }
+
+    verify(
+        &signed_request(r#",algorithm="hs2019""#)?,
+        &VerificationOptions::default(),
+    )?;
+    Ok(())
+}
+
+#[test]
+fn signature_expired() -> Result<(), BoxError> {
+    let request = signed_request(&format!(",expires={}", REQUEST_TIME + 60))?;
+    verify(&request, &at(REQUEST_TIME + 60))?;
+
+    match verify(&request, &at(REQUEST_TIME + 61)) {
+        Err(VerificationError::SignatureExpired { expired_at }) => {
+            assert_eq!(
+                expired_at,
+                UNIX_EPOCH + Duration::from_secs(REQUEST_TIME + 60)
+            )
+        }
+        other => panic!("expected signature expired, got {:?}", other),
+    }
+    Ok(())
+}
+
+fn max_age(seconds: u64) -> VerificationOptions {
+    VerificationOptions {
+        max_signature_age: Some(Duration::from_secs(300)),
+        ..at(seconds)
+    }
+}
+
+fn assert_too_old(result: Result<(), VerificationError>) {
+    match result {
+        Err(VerificationError::SignatureTooOld { created, max_age }) => {
+            assert_eq!(created, UNIX_EPOCH + Duration::from_secs(REQUEST_TIME));
+            assert_eq!(max_age, Duration::from_secs(300));
+        }
+        other => panic!("expected signature too old, got {:?}", other),
+    }
+}
+
+#[test]
+fn signature_too_old() -> Result<(), BoxError> {
+    // Dated by the signed `Date` header
+    let request = signed_request("")?;
+    verify(&request, &max_age(REQUEST_TIME + 300))?;
+    assert_too_old(verify(&request, &max_age(REQUEST_TIME + 301)));
+
+    // A replayed request with a fresh, unsigned `created` is still dated by `Date`
+    let request = signed_request(&format!(",created={}", REQUEST_TIME + 1000))?;
+    assert_too_old(verify(&request, &max_age(REQUEST_TIME + 1000)));
+    Ok(())
+}
+
+#[test]
+fn signature_age_needs_signed_timestamp() -> Result<(), BoxError> {
+    let private_key = PKey::private_key_from_pem(PRIVATE_PEM)?;
+    let params = httpsig::SignatureParams {
+        headers: Some(vec!["(request-target)".to_owned(), "host".to_owned()]),
+        ..httpsig::SignatureParams::new("Test", MessageDigest::sha256())
+    };
+    let mut request = parse_request(HTTP_REQUEST);
+    httpsig::add_signature_header_with_params(&mut request, &params, &private_key)?;
+    verify(&request, &at(REQUEST_TIME))?;
+
+    match verify(&request, &max_age(REQUEST_TIME)) {
+        Err(VerificationError::Other(httpsig::HttpSigError::DateNotSigned)) => {}
+        other => panic!("expected date not signed, got {:?}", other),
+    }
+    Ok(())
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "warp")]
+
+mod common;
+
+use crate::common::*;
+use futures::executor::block_on;
+use httpsig::warp::{signature_required, signature_required_with_options, HttpSigRejection};
+use httpsig::VerificationOptions;
+use openssl::pkey::PKey;
+use std::time::{Duration, UNIX_EPOCH};
+use warp::http::StatusCode;
+use warp::test::RequestBuilder;
+
+// https://tools.ietf.org/html/draft-cavage-http-signatures-10#appendix-C.2
+const BASIC_SIGNATURE: &str = r#"keyId="Test",algorithm="rsa-sha256",headers="(request-target) host date",signature="qdx+H7PHHDZgy4y/Ahn9Tny9V3GP6YgBPyUXMmoxWtLbHpUnXS2mg2+SbrQDMCJypxBLSPQR2aAjn7ndmw2iicw3HMbe8VfEdKFYRqzic+efkb3nndiv/x1xSHDJWeSWkx3ButlYSuBskLu6kd9Fswtemr3lgdDEmn04swr2Os0=""#;
+
+fn key_store() -> Result<httpsig::InMemoryKeyStore, BoxError> {
+    let mut key_store = httpsig::InMemoryKeyStore::new();
+    key_store.insert("Test", PKey::public_key_from_pem(PUBLIC_PEM)?);
+    Ok(key_store)
+}
+
+fn appendix_request() -> RequestBuilder {
+    warp::test::request()
+        .method("POST")
+        .path("/foo?param=value&pet=dog")
+        .header("host", "example.com")
+        .header("date", "Sun, 05 Jan 2014 21:31:40 GMT")
+        .header("content-type", "application/json")
+        .body(r#"{"hello": "world"}"#)
+}
+
+fn rejection_status(request: RequestBuilder) -> Result<StatusCode, BoxError> {
+    let filter = signature_required(key_store()?);
+    let rejection = block_on(request.filter(&filter)).expect_err("request should be rejected");
+    let rejection = rejection
+        .find::<HttpSigRejection>()
+        .expect("rejection should come from the signature filter");
+    Ok(rejection.status())
+}
+
+#[test]
+fn signature_required_accepts_valid_signature() -> Result<(), BoxError> {
+    let filter = signature_required(key_store()?);
+    let request = appendix_request().header("signature", BASIC_SIGNATURE);
+
+    let verified = block_on(request.filter(&filter)).expect("signature should be accepted");
+    assert_eq!(verified.key_id, "Test");
+    assert_eq!(verified.algorithm.as_deref(), Some("rsa-sha256"));
+    assert_eq!(
+        verified.signed_headers,
+        vec!["(request-target)", "host", "date"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn signature_required_combines_with_routes() -> Result<(), BoxError> {
+    use warp::Filter;
+
+    let route = warp::post()
+        .and(warp::path("foo"))
+        .and(signature_required(key_store()?))
+        .map(|signature: httpsig::warp::VerifiedSignature| signature.key_id);
+    let request = appendix_request().header("signature", BASIC_SIGNATURE);
+
+    let response = block_on(request.reply(&route));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().as_ref(), b"Test");
+
+    Ok(())
+}
+
+#[test]
+fn signature_required_rejects_missing_signature() -> Result<(), BoxError> {
+    assert_eq!(
+        rejection_status(appendix_request())?,
+        StatusCode::UNAUTHORIZED
+    );
+    Ok(())
+}
+
+#[test]
+fn signature_required_rejects_unknown_key() -> Result<(), BoxError> {
+    let request = appendix_request().header(
+        "signature",
+        BASIC_SIGNATURE.replace(r#"keyId="Test""#, r#"keyId="Other""#),
+    );
+    assert_eq!(rejection_status(request)?, StatusCode::UNAUTHORIZED);
+    Ok(())
+}
+
+#[test]
+fn signature_required_rejects_tampered_request() -> Result<(), BoxError> {
+    let request = appendix_request()
+        .path("/foo?param=value&pet=cat")
+        .header("signature", BASIC_SIGNATURE);
+    assert_eq!(rejection_status(request)?, StatusCode::UNAUTHORIZED);
+    Ok(())
+}
+
+#[test]
+fn signature_required_rejects_malformed_signature() -> Result<(), BoxError> {
+    let request = appendix_request().header("signature", "keyId=Test");
+    assert_eq!(rejection_status(request)?, StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+#[test]
+fn signature_required_with_options_rejects_replayed_request() -> Result<(), BoxError> {
+    // Five minutes either side of the appendix's `Date`
+    let options = |now: u64| VerificationOptions {
+        max_clock_skew: Some(Duration::from_secs(300)),
+        now: Some(UNIX_EPOCH + Duration::from_secs(now)),
+        ..VerificationOptions::default()
+    };
+
+    let filter = signature_required_with_options(key_store()?, options(1_388_957_500 + 300));
+    let request = appendix_request().header("signature", BASIC_SIGNATURE);
+    block_on(request.filter(&filter)).expect("signature should be accepted");
+
+    let filter = signature_required_with_options(key_store()?, options(1_388_957_500 + 301));
+    let request = appendix_request().header("signature", BASIC_SIGNATURE);
+    let rejection = block_on(request.filter(&filter)).expect_err("request should be rejected");
+    let rejection = rejection
+        .find::<HttpSigRejection>()
+        .expect("rejection should come from the signature filter");
+    assert!(matches!(
+        rejection.error(),
+        httpsig::HttpSigError::ClockSkew { .. }
+    ));
+    assert_eq!(rejection.status(), StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}